@@ -0,0 +1,54 @@
+//! 个人访问令牌（API key）：给 CI、脚本等无法走交互式登录的客户端。令牌形如 `pat_...`，
+//! 只在创建时显示一次；库里存 SHA-256 摘要与前几位明文前缀，列表里靠前缀辨认是哪一把。
+//!
+//! 每把 key 有 scope：`todos:read`/`todos:write` 控制自己的 todo，管理权限（见 `rbac`）
+//! 也可以作为 scope，但只在用户本身仍拥有该权限时生效。
+
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::RepoResult;
+
+/// 读自己的 todo（含推送）
+pub const SCOPE_TODOS_READ: &str = "todos:read";
+/// 增删改自己的 todo
+pub const SCOPE_TODOS_WRITE: &str = "todos:write";
+
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// 令牌开头的明文部分，如 `pat_3kq9ZbX1`
+    pub prefix: String,
+    /// 按名称排序
+    pub scopes: Vec<String>,
+    pub expires_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+    pub last_used_ip: Option<String>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewApiKey<'a> {
+    pub name: &'a str,
+    pub prefix: &'a str,
+    pub token_hash: &'a str,
+    pub scopes: &'a [String],
+    pub expires_at: OffsetDateTime,
+}
+
+#[async_trait::async_trait]
+pub trait ApiKeyRepo: Send + Sync {
+    /// 同一用户下名称重复时返回 `RepoError::Conflict("api_key_name_taken")`
+    async fn create(&self, user_id: Uuid, key: &NewApiKey<'_>) -> RepoResult<ApiKey>;
+    /// 该用户的全部 key（含已过期未清理的），按创建时间倒序
+    async fn list(&self, user_id: Uuid) -> RepoResult<Vec<ApiKey>>;
+    /// 吊销即删除；不存在或不属于该用户时返回 `RepoError::NotFound("api_key_not_found")`
+    async fn revoke(&self, user_id: Uuid, id: Uuid) -> RepoResult<()>;
+    /// 按摘要找到未过期的 key 并记下使用时间与来源 IP（`ip` 为空时保留原值）。同一 IP
+    /// 一分钟内的重复使用不再写库，高频调用的脚本不会把每个请求都变成一次写入
+    async fn authenticate(&self, token_hash: &str, ip: Option<&str>) -> RepoResult<Option<ApiKey>>;
+    /// 删除在 `before` 之前过期的 key，返回删除条数
+    async fn purge_expired(&self, before: OffsetDateTime) -> RepoResult<u64>;
+}
@@ -0,0 +1,123 @@
+//! 安全审计日志：谁、在什么时候、从哪里、做了什么、结果如何。
+//! 只追加：仓储接口没有修改与删除，表上也有触发器拒绝 update/delete。
+
+use std::sync::Arc;
+
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::RepoResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Failure => "failure",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "success" => Some(AuditOutcome::Success),
+            "failure" => Some(AuditOutcome::Failure),
+            _ => None,
+        }
+    }
+}
+
+/// 请求来源，由协议层（HTTP/gRPC）从请求里取出；`actor` 为空表示未登录
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub actor: Option<Uuid>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// 一条审计事件。`action` 形如 `auth.login`、`todo.delete`；失败时 `reason` 是对外的错误码
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub occurred_at: OffsetDateTime,
+    pub actor: Option<Uuid>,
+    pub action: String,
+    /// 操作对象：todo 的 ID、登录时提交的邮箱等
+    pub target: Option<String>,
+    pub outcome: AuditOutcome,
+    pub reason: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// 键集分页位置：上一页最后一条的 `(occurred_at, id)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditCursor {
+    pub occurred_at: OffsetDateTime,
+    pub id: Uuid,
+}
+
+/// 查询条件，结果按时间倒序；`from` 含、`to` 不含
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub from: Option<OffsetDateTime>,
+    pub to: Option<OffsetDateTime>,
+    pub actor: Option<Uuid>,
+    pub action: Option<String>,
+    pub outcome: Option<AuditOutcome>,
+    pub limit: u32,
+    pub before: Option<AuditCursor>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditPage {
+    pub items: Vec<AuditEvent>,
+    pub next: Option<AuditCursor>,
+}
+
+#[async_trait::async_trait]
+pub trait AuditRepo: Send + Sync {
+    async fn append(&self, event: &AuditEvent) -> RepoResult<()>;
+    async fn list(&self, query: &AuditQuery) -> RepoResult<AuditPage>;
+}
+
+pub struct Auditor {
+    repo: Arc<dyn AuditRepo>,
+}
+
+impl Auditor {
+    pub const MAX_PAGE_SIZE: u32 = 500;
+
+    pub fn new(repo: Arc<dyn AuditRepo>) -> Self {
+        Self { repo }
+    }
+
+    /// `result` 为 `Err(code)` 时记为失败，错误码写进 `reason`
+    pub async fn record(&self, ctx: &AuditContext, action: &str, target: Option<&str>, result: Result<(), &str>) -> RepoResult<()> {
+        let event = AuditEvent {
+            id: Uuid::new_v4(),
+            occurred_at: OffsetDateTime::now_utc(),
+            actor: ctx.actor,
+            action: action.to_string(),
+            target: target.map(str::to_string),
+            outcome: if result.is_ok() { AuditOutcome::Success } else { AuditOutcome::Failure },
+            reason: result.err().map(str::to_string),
+            ip: ctx.ip.clone(),
+            user_agent: ctx.user_agent.clone(),
+            request_id: ctx.request_id.clone(),
+        };
+        self.repo.append(&event).await
+    }
+
+    /// `limit` 收紧到 1..=MAX_PAGE_SIZE
+    pub async fn query(&self, query: &AuditQuery) -> RepoResult<AuditPage> {
+        let query = AuditQuery { limit: query.limit.clamp(1, Self::MAX_PAGE_SIZE), ..query.clone() };
+        self.repo.list(&query).await
+    }
+}
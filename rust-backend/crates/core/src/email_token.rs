@@ -0,0 +1,32 @@
+//! 邮件里的一次性令牌（邮箱验证、重置密码）。令牌本身由 api 用 HMAC 签名，携带用途、ID 与过期时间，
+//! 伪造或过期的令牌不必查库就能拒绝；库里只记 ID，保证每个令牌只能用一次。
+
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::RepoResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTokenPurpose {
+    VerifyEmail,
+    ResetPassword,
+}
+
+impl EmailTokenPurpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EmailTokenPurpose::VerifyEmail => "verify_email",
+            EmailTokenPurpose::ResetPassword => "reset_password",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait EmailTokenRepo: Send + Sync {
+    /// 记下新令牌，并作废该用户同一用途尚未使用的旧令牌：只有最近一封邮件里的链接有效
+    async fn issue(&self, id: Uuid, user_id: Uuid, purpose: EmailTokenPurpose, expires_at: OffsetDateTime) -> RepoResult<()>;
+    /// 原子地标记为已使用并返回所属用户；不存在、已使用、已过期或用途不符时返回 `None`
+    async fn consume(&self, id: Uuid, purpose: EmailTokenPurpose) -> RepoResult<Option<Uuid>>;
+    /// 清理已过期的令牌，返回删除条数
+    async fn purge_expired(&self) -> RepoResult<u64>;
+}
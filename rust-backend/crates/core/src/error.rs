@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+/// 仓储层错误。`NotFound`/`Conflict` 携带稳定的错误码（如 `todo_title_conflict`），
+/// HTTP 层原样返回给客户端；`Db` 只用于日志，不会出现在响应里。
+#[derive(Error, Debug)]
+pub enum RepoError {
+    #[error("not found: {0}")]
+    NotFound(&'static str),
+    #[error("conflict: {0}")]
+    Conflict(&'static str),
+    #[error("db error: {0}")]
+    Db(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type RepoResult<T> = Result<T, RepoError>;
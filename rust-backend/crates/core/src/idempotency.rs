@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::RepoResult;
+
+/// 第一次执行得到的响应，重试时原样重放
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// 同一用户、同一个键已有的记录
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    /// 第一次请求的指纹（方法、路径与请求体的摘要）
+    pub fingerprint: String,
+    /// 为空表示第一次请求还在执行
+    pub response: Option<StoredResponse>,
+}
+
+#[derive(Debug, Clone)]
+pub enum IdempotencyBegin {
+    /// 抢到了这个键，调用方执行请求后 `complete` 或 `release`
+    Started,
+    Existing(IdempotencyRecord),
+}
+
+/// `Idempotency-Key` 的存储，键按用户隔离。
+/// 执行中的记录只保留到 `locked_until`，进程在执行途中退出时键不会被一直占住
+#[async_trait::async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// 键不存在或已过期时原子地占住它，否则返回已有记录
+    async fn begin(&self, user_id: Uuid, key: &str, fingerprint: &str, locked_until: OffsetDateTime) -> RepoResult<IdempotencyBegin>;
+    /// 记下响应，保留到 `expires_at`
+    async fn complete(&self, user_id: Uuid, key: &str, response: &StoredResponse, expires_at: OffsetDateTime) -> RepoResult<()>;
+    /// 放弃执行中的记录（如服务端出错），让重试可以重新执行
+    async fn release(&self, user_id: Uuid, key: &str) -> RepoResult<()>;
+    /// 清理过期记录，返回删除条数（供计划任务调用）
+    async fn purge_expired(&self) -> RepoResult<u64>;
+}
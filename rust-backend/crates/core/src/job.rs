@@ -0,0 +1,36 @@
+use crate::RepoResult;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// 一个已领取的后台任务。`(kind, key)` 唯一：同一个 todo 的提醒只有一条，改时间就是改这一条
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: Uuid,
+    /// 如 `maintenance`、`todo.remind`
+    pub kind: String,
+    pub key: String,
+    pub payload: serde_json::Value,
+    /// 含本次领取
+    pub attempts: i32,
+    /// 本次领取的凭证：租约过期被别人重新领取，或任务被改期之后，旧凭证的确认不再生效
+    pub lease: Uuid,
+}
+
+/// 持久化的任务队列，`run_at` 到了就可以领取。与 outbox 一样按租约领取：
+/// 进程在执行中途退出，租约到期后任务会被再次领取；正常退出时当前一批会先做完，重启后不会重复执行
+#[async_trait::async_trait]
+pub trait JobRepo: Send + Sync {
+    /// 登记周期任务；`(kind, key)` 已存在时什么也不做，多个实例同时启动也只有一条
+    async fn ensure(&self, kind: &str, key: &str, run_at: OffsetDateTime) -> RepoResult<()>;
+    /// 领取至多 `limit` 条到期的任务，按 `run_at` 顺序返回。领取即把 `run_at` 推后 `lease`，
+    /// 多个实例并发领取时互不重复
+    async fn claim(&self, limit: u32, lease: time::Duration) -> RepoResult<Vec<Job>>;
+    /// 执行成功：`next` 为下次运行时间（周期任务），为空则结束
+    async fn finish(&self, job: &Job, next: Option<OffsetDateTime>) -> RepoResult<()>;
+    /// 执行失败，`retry_at` 之后重新领取
+    async fn retry_later(&self, job: &Job, error: &str, retry_at: OffsetDateTime) -> RepoResult<()>;
+    /// 超过重试上限：不再领取，保留一段时间供人工排查，之后与完成的任务一起由 `purge_finished` 清理
+    async fn mark_dead(&self, job: &Job, error: &str) -> RepoResult<()>;
+    /// 删除 `before` 之前结束（完成或转为 dead）的任务，返回删除条数（供计划任务调用）
+    async fn purge_finished(&self, before: OffsetDateTime) -> RepoResult<u64>;
+}
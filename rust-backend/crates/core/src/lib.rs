@@ -0,0 +1,31 @@
+//! 领域模型与仓储接口：只描述“做什么”，由 infra 提供 sqlx 实现，api 在启动时组装。
+
+pub mod api_key;
+pub mod audit;
+pub mod email_token;
+pub mod error;
+pub mod idempotency;
+pub mod job;
+pub mod oidc;
+pub mod outbox;
+pub mod password;
+pub mod rbac;
+pub mod recurrence;
+pub mod session;
+pub mod todo;
+pub mod user;
+
+pub use api_key::{ApiKey, ApiKeyRepo, NewApiKey, SCOPE_TODOS_READ, SCOPE_TODOS_WRITE};
+pub use audit::{AuditContext, AuditCursor, AuditEvent, AuditOutcome, AuditPage, AuditQuery, AuditRepo, Auditor};
+pub use email_token::{EmailTokenPurpose, EmailTokenRepo};
+pub use error::{RepoError, RepoResult};
+pub use idempotency::{IdempotencyBegin, IdempotencyRecord, IdempotencyStore, StoredResponse};
+pub use job::{Job, JobRepo};
+pub use oidc::{OidcLogin, OidcRepo};
+pub use outbox::{OutboxEvent, OutboxRepo, OutboxStats, TodoEvent};
+pub use password::{HashParams, LockoutPolicy, LoginAttemptRepo, PasswordError, PasswordPolicy, PasswordService};
+pub use session::{RefreshOutcome, SessionRepo};
+pub use recurrence::{Freq, Recurrence, RecurrenceError};
+pub use todo::{NewTodo, Reminder, Todo, TodoChanges, TodoCursor, TodoPage, TodoQuery, TodoRepo, TodoService, TodoSort};
+pub use rbac::{Permission, ADMIN_ROLE, DEFAULT_ROLE};
+pub use user::{UserAccess, UserCursor, UserListQuery, UserPage, UserRepo, UserSummary};
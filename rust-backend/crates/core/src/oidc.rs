@@ -0,0 +1,28 @@
+//! OIDC 登录（授权码 + PKCE）需要落库的两样东西：跳转到身份提供方之前生成的一次性登录状态，
+//! 以及外部身份（`iss` + `sub`）与本地用户的绑定。协议细节（发现文档、JWKS、换取令牌）在 api 层。
+
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::RepoResult;
+
+/// 一次进行中的登录；`state` 随授权请求发出、原样带回，回调时凭它取回 PKCE 校验码与 nonce
+#[derive(Debug, Clone)]
+pub struct OidcLogin {
+    pub state: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub expires_at: OffsetDateTime,
+}
+
+#[async_trait::async_trait]
+pub trait OidcRepo: Send + Sync {
+    async fn begin_login(&self, login: &OidcLogin) -> RepoResult<()>;
+    /// 原子地取出并删除；不存在或已过期时返回 `None`，同一个 `state` 只能回调一次
+    async fn take_login(&self, state: &str) -> RepoResult<Option<OidcLogin>>;
+    async fn find_identity(&self, issuer: &str, subject: &str) -> RepoResult<Option<Uuid>>;
+    /// 外部身份已绑定到任何用户时返回 `Conflict("identity_linked")`
+    async fn link_identity(&self, user_id: Uuid, issuer: &str, subject: &str, email: &str) -> RepoResult<()>;
+    /// 清理过期未完成的登录，返回删除条数
+    async fn purge_expired_logins(&self) -> RepoResult<u64>;
+}
@@ -0,0 +1,75 @@
+use crate::{RepoResult, Todo};
+use serde::Serialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Todo 聚合的领域事件，由 `TodoRepo` 的写操作在同一事务里写入 outbox。
+/// 删除事件带着删除前的最后状态，消费方不需要回查
+#[derive(Debug, Clone)]
+pub enum TodoEvent {
+    Created(Todo),
+    Updated(Todo),
+    Deleted(Todo),
+}
+
+impl TodoEvent {
+    pub const AGGREGATE: &'static str = "todo";
+
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            TodoEvent::Created(_) => "TodoCreated",
+            TodoEvent::Updated(_) => "TodoUpdated",
+            TodoEvent::Deleted(_) => "TodoDeleted",
+        }
+    }
+
+    pub fn todo(&self) -> &Todo {
+        match self {
+            TodoEvent::Created(t) | TodoEvent::Updated(t) | TodoEvent::Deleted(t) => t,
+        }
+    }
+
+    /// 事件体：todo 的快照，时间为 RFC 3339
+    pub fn payload(&self) -> serde_json::Value {
+        serde_json::to_value(self.todo()).expect("todo serializes to JSON")
+    }
+}
+
+/// outbox 里的一条事件；`attempts` 含本次领取
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxEvent {
+    pub id: Uuid,
+    pub aggregate_type: String,
+    pub aggregate_id: Uuid,
+    pub user_id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    #[serde(skip)]
+    pub attempts: i32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// 各状态的事件条数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutboxStats {
+    pub pending: i64,
+    pub dead: i64,
+}
+
+/// 事件投递的存储侧。投递是至少一次：领取后进程崩溃，租约到期后会被再次领取，
+/// 消费方按事件 `id` 去重
+#[async_trait::async_trait]
+pub trait OutboxRepo: Send + Sync {
+    /// 领取至多 `limit` 条到期的待投递事件，按写入顺序返回。领取即把下次可领取时间推后 `lease`，
+    /// 多个 dispatcher 并发领取时互不重复
+    async fn claim(&self, limit: u32, lease: time::Duration) -> RepoResult<Vec<OutboxEvent>>;
+    async fn mark_dispatched(&self, id: Uuid) -> RepoResult<()>;
+    /// 投递失败，`retry_at` 之后重新领取
+    async fn retry_later(&self, id: Uuid, error: &str, retry_at: OffsetDateTime) -> RepoResult<()>;
+    /// 超过重试上限：转入死信，不再领取，留给人工处理
+    async fn mark_dead(&self, id: Uuid, error: &str) -> RepoResult<()>;
+    async fn stats(&self) -> RepoResult<OutboxStats>;
+    /// 删除 `before` 之前已投递的事件，返回删除条数（供计划任务调用）
+    async fn purge_dispatched(&self, before: OffsetDateTime) -> RepoResult<u64>;
+}
@@ -0,0 +1,207 @@
+//! 密码服务：Argon2 参数、密码策略（长度与泄露密码表）、按账号/IP 的失败计数与指数锁定，
+//! 以及参数调整后在下一次成功登录时透明重哈希。
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+
+use crate::{RepoError, RepoResult};
+
+/// Argon2id 参数：内存（KiB）、迭代次数、并行度
+#[derive(Debug, Clone, Copy)]
+pub struct HashParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    /// 上限防止超长输入拖慢哈希
+    pub max_length: usize,
+    /// 小写存放，比较时忽略大小写
+    pub breached: HashSet<String>,
+}
+
+/// 读取泄露密码表：每行一个，忽略空行与 `#` 注释
+pub fn load_breached(path: &Path) -> std::io::Result<HashSet<String>> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect())
+}
+
+/// 连续失败达到阈值后锁定 `base`，之后每多失败一次时长翻倍，最长 `max`；
+/// 距上次失败超过 `window` 的计数从头开始
+#[derive(Debug, Clone, Copy)]
+pub struct LockoutPolicy {
+    pub account_threshold: u32,
+    pub ip_threshold: u32,
+    pub base: Duration,
+    pub max: Duration,
+    pub window: Duration,
+}
+
+impl LockoutPolicy {
+    fn lock_for(&self, failures: u32, threshold: u32) -> Option<Duration> {
+        let over = failures.checked_sub(threshold)?;
+        let factor = 1i32.checked_shl(over.min(30)).unwrap_or(i32::MAX);
+        Some(self.base.checked_mul(factor).map_or(self.max, |d| d.min(self.max)))
+    }
+}
+
+/// 登录失败计数的存储；key 形如 `account:<email>`、`ip:<addr>`
+#[async_trait::async_trait]
+pub trait LoginAttemptRepo: Send + Sync {
+    async fn locked_until(&self, key: &str) -> RepoResult<Option<OffsetDateTime>>;
+    /// 记一次失败并返回窗口内的累计次数
+    async fn record_failure(&self, key: &str, window: Duration) -> RepoResult<u32>;
+    async fn lock(&self, key: &str, until: OffsetDateTime) -> RepoResult<()>;
+    async fn reset(&self, key: &str) -> RepoResult<()>;
+    /// 删除最后一次失败早于 `before` 且没有生效中锁定的记录，返回删除条数
+    async fn purge_expired(&self, before: OffsetDateTime) -> RepoResult<u64>;
+}
+
+#[derive(Error, Debug)]
+pub enum PasswordError {
+    /// 错误码：`password_too_short`、`password_too_long`、`password_breached`
+    #[error("password rejected: {0}")]
+    Policy(&'static str),
+    #[error("invalid credentials")]
+    InvalidCredentials,
+    /// 错误码：`account_locked`、`ip_locked`
+    #[error("{code}, retry after {retry_after}")]
+    Locked { code: &'static str, retry_after: Duration },
+    #[error("password hash: {0}")]
+    Hash(argon2::password_hash::Error),
+    #[error("argon2 params: {0}")]
+    Params(argon2::Error),
+    #[error(transparent)]
+    Repo(#[from] RepoError),
+}
+
+pub struct PasswordService {
+    params: Params,
+    policy: PasswordPolicy,
+    lockout: LockoutPolicy,
+    attempts: Arc<dyn LoginAttemptRepo>,
+    /// 账号不存在时也校验一次，避免按响应耗时枚举邮箱
+    dummy_hash: String,
+}
+
+impl PasswordService {
+    pub fn new(
+        params: HashParams,
+        policy: PasswordPolicy,
+        lockout: LockoutPolicy,
+        attempts: Arc<dyn LoginAttemptRepo>,
+    ) -> Result<Self, PasswordError> {
+        let params = Params::new(params.m_cost, params.t_cost, params.p_cost, None).map_err(PasswordError::Params)?;
+        let mut svc = Self { params, policy, lockout, attempts, dummy_hash: String::new() };
+        svc.dummy_hash = svc.hash("dummy password")?;
+        Ok(svc)
+    }
+
+    fn argon(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    pub fn check_policy(&self, password: &str) -> Result<(), PasswordError> {
+        let len = password.chars().count();
+        if len < self.policy.min_length {
+            return Err(PasswordError::Policy("password_too_short"));
+        }
+        if len > self.policy.max_length {
+            return Err(PasswordError::Policy("password_too_long"));
+        }
+        if self.policy.breached.contains(&password.to_lowercase()) {
+            return Err(PasswordError::Policy("password_breached"));
+        }
+        Ok(())
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, PasswordError> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = self.argon().hash_password(password.as_bytes(), &salt).map_err(PasswordError::Hash)?;
+        Ok(hash.to_string())
+    }
+
+    /// 校验一次登录。`stored` 为 `None` 表示账号不存在，同样计入失败。
+    /// 成功时若存量哈希的参数与当前配置不同，返回用新参数重算的哈希，由调用方写回。
+    pub async fn verify_login(
+        &self,
+        account: &str,
+        ip: Option<IpAddr>,
+        stored: Option<&str>,
+        password: &str,
+    ) -> Result<Option<String>, PasswordError> {
+        let now = OffsetDateTime::now_utc();
+        let account_key = format!("account:{}", account.trim().to_lowercase());
+        let ip_key = ip.map(|ip| format!("ip:{ip}"));
+        let mut keys = vec![(account_key.as_str(), "account_locked", self.lockout.account_threshold)];
+        if let Some(ip_key) = &ip_key {
+            keys.push((ip_key.as_str(), "ip_locked", self.lockout.ip_threshold));
+        }
+
+        for &(key, code, _) in &keys {
+            if let Some(until) = self.attempts.locked_until(key).await? {
+                if until > now {
+                    return Err(PasswordError::Locked { code, retry_after: until - now });
+                }
+            }
+        }
+
+        let parsed = PasswordHash::new(stored.unwrap_or(&self.dummy_hash)).map_err(PasswordError::Hash)?;
+        let matched = match self.argon().verify_password(password.as_bytes(), &parsed) {
+            Ok(()) => stored.is_some(),
+            Err(argon2::password_hash::Error::Password) => false,
+            Err(e) => return Err(PasswordError::Hash(e)),
+        };
+        if !matched {
+            for &(key, _, threshold) in &keys {
+                let failures = self.attempts.record_failure(key, self.lockout.window).await?;
+                if let Some(d) = self.lockout.lock_for(failures, threshold) {
+                    self.attempts.lock(key, now + d).await?;
+                }
+            }
+            return Err(PasswordError::InvalidCredentials);
+        }
+
+        // 只清账号计数：否则一个有效账号就能替同一 IP 上的撞库清零
+        self.attempts.reset(&account_key).await?;
+        if self.needs_rehash(&parsed) {
+            return self.hash(password).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// 清理已过窗口、也不在锁定期的失败计数（供计划任务调用）
+    pub async fn purge_attempts(&self) -> RepoResult<u64> {
+        self.attempts.purge_expired(OffsetDateTime::now_utc() - self.lockout.window).await
+    }
+
+    fn needs_rehash(&self, hash: &PasswordHash<'_>) -> bool {
+        if hash.algorithm != Algorithm::Argon2id.ident() || hash.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+        match Params::try_from(hash) {
+            Ok(p) => {
+                p.m_cost() != self.params.m_cost()
+                    || p.t_cost() != self.params.t_cost()
+                    || p.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
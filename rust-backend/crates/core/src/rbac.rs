@@ -0,0 +1,40 @@
+//! 基于角色的访问控制：角色与权限存在库里（`roles`、`role_permissions`、`user_roles`），
+//! 签发访问令牌时展开成权限列表写进 claims。处理器按权限而不是角色声明要求，
+//! 角色的权限调整只改数据，不改代码。
+//!
+//! 自己的 todo 靠所有权隔离，不需要权限；这里的权限都是跨用户的管理操作。
+
+/// 权限标记类型，api 的 `RequirePermission<P>` 提取器据此检查 claims
+pub trait Permission: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+/// 查看审计日志
+pub struct AuditRead;
+/// 列出用户
+pub struct UsersRead;
+/// 停用、启用账号，强制下线
+pub struct UsersManage;
+/// 查看任意用户的 todo
+pub struct TodosAdmin;
+
+impl Permission for AuditRead {
+    const NAME: &'static str = "audit:read";
+}
+
+impl Permission for UsersRead {
+    const NAME: &'static str = "users:read";
+}
+
+impl Permission for UsersManage {
+    const NAME: &'static str = "users:manage";
+}
+
+impl Permission for TodosAdmin {
+    const NAME: &'static str = "todos:admin";
+}
+
+/// 注册时自动授予，没有额外权限
+pub const DEFAULT_ROLE: &str = "user";
+/// 拥有以上全部权限，由迁移写入
+pub const ADMIN_ROLE: &str = "admin";
@@ -0,0 +1,304 @@
+//! 重复规则：RFC 5545 RRULE 的一个子集，足够表达“每天 / 每周一、四 / 每月 / 每年，共 N 次或截至某天”。
+//!
+//! 支持 `FREQ`（`DAILY`、`WEEKLY`、`MONTHLY`、`YEARLY`，必填）、`INTERVAL`、`BYDAY`（只用于 `WEEKLY`，不带序数）、
+//! `COUNT` 与 `UNTIL`（`YYYYMMDDTHHMMSSZ` 或 `YYYYMMDD`，UTC），`COUNT` 与 `UNTIL` 互斥。
+//! 下一次按上一次的时间推算，时刻不变，一律按 UTC 计算；没有这一天的月份（31 日、2 月 29 日）跳过，与 RFC 一致。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+use time::macros::format_description;
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, Weekday};
+
+const MAX_INTERVAL: u32 = 999;
+/// 按月、按年推算时最多试这么多个周期，找不到这一天就认为规则已用完
+const MAX_SKIPS: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub freq: Freq,
+    pub interval: u32,
+    /// 按周一到周日排序、去重；为空表示沿用上一次是星期几
+    pub by_day: Vec<Weekday>,
+    /// 含当前这一次在内还剩几次
+    pub count: Option<u32>,
+    /// 晚于它的不再生成
+    pub until: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid recurrence rule: {0}")]
+pub struct RecurrenceError(pub String);
+
+impl Recurrence {
+    /// `from` 这一次之后的下一次，以及下一次应当携带的规则（`COUNT` 减一）；规则已用完时为 `None`
+    pub fn advance(&self, from: OffsetDateTime) -> Option<(OffsetDateTime, Recurrence)> {
+        let count = match self.count {
+            Some(n) if n <= 1 => return None,
+            n => n.map(|n| n - 1),
+        };
+        let next = self.next_after(from)?;
+        if self.until.is_some_and(|until| next > until) {
+            return None;
+        }
+        Some((next, Recurrence { count, ..self.clone() }))
+    }
+
+    fn next_after(&self, from: OffsetDateTime) -> Option<OffsetDateTime> {
+        let from = from.to_offset(time::UtcOffset::UTC);
+        let interval = i64::from(self.interval);
+        match self.freq {
+            Freq::Daily => from.checked_add(Duration::days(interval)),
+            Freq::Weekly if self.by_day.is_empty() => from.checked_add(Duration::weeks(interval)),
+            Freq::Weekly => {
+                let today = i64::from(from.weekday().number_days_from_monday());
+                let days = |d: &Weekday| i64::from(d.number_days_from_monday());
+                // 本周还有没轮到的就取它，否则跳到 `interval` 周后的第一个
+                match self.by_day.iter().map(days).find(|d| *d > today) {
+                    Some(d) => from.checked_add(Duration::days(d - today)),
+                    None => from.checked_add(Duration::days(7 * interval - today + days(&self.by_day[0]))),
+                }
+            }
+            Freq::Monthly => (1..=MAX_SKIPS).find_map(|k| {
+                let months = i64::from(from.month() as u8) - 1 + i64::from(k) * interval;
+                let year = i32::try_from(i64::from(from.year()) + months / 12).ok()?;
+                let month = Month::try_from(u8::try_from(months % 12 + 1).ok()?).ok()?;
+                Date::from_calendar_date(year, month, from.day()).ok().map(|d| from.replace_date(d))
+            }),
+            Freq::Yearly => (1..=MAX_SKIPS).find_map(|k| {
+                let year = i32::try_from(i64::from(from.year()) + i64::from(k) * interval).ok()?;
+                Date::from_calendar_date(year, from.month(), from.day()).ok().map(|d| from.replace_date(d))
+            }),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = RecurrenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |msg: String| RecurrenceError(msg);
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+        let (mut freq, mut interval, mut by_day, mut count, mut until) = (None, 1, Vec::new(), None, None);
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| err(format!("`{part}` is not KEY=VALUE")))?;
+            let value = value.to_ascii_uppercase();
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.as_str() {
+                        "DAILY" => Freq::Daily,
+                        "WEEKLY" => Freq::Weekly,
+                        "MONTHLY" => Freq::Monthly,
+                        "YEARLY" => Freq::Yearly,
+                        _ => return Err(err(format!("unsupported FREQ `{value}`"))),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=MAX_INTERVAL).contains(n))
+                        .ok_or_else(|| err(format!("INTERVAL must be between 1 and {MAX_INTERVAL}")))?
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        by_day.push(weekday(day).ok_or_else(|| err(format!("unsupported BYDAY `{day}`")))?);
+                    }
+                }
+                "COUNT" => count = Some(value.parse().ok().filter(|n| *n >= 1).ok_or_else(|| err("COUNT must be positive".into()))?),
+                "UNTIL" => until = Some(parse_until(&value).ok_or_else(|| err(format!("unsupported UNTIL `{value}`")))?),
+                _ => return Err(err(format!("unsupported part `{key}`"))),
+            }
+        }
+        let freq = freq.ok_or_else(|| err("FREQ is required".into()))?;
+        if !by_day.is_empty() && freq != Freq::Weekly {
+            return Err(err("BYDAY is only supported with FREQ=WEEKLY".into()));
+        }
+        if count.is_some() && until.is_some() {
+            return Err(err("COUNT and UNTIL are mutually exclusive".into()));
+        }
+        by_day.sort_by_key(|d| d.number_days_from_monday());
+        by_day.dedup();
+        Ok(Recurrence { freq, interval, by_day, count, until })
+    }
+}
+
+/// 规范形式：部件顺序固定，`INTERVAL=1` 省略，存库与响应里都用它
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let freq = match self.freq {
+            Freq::Daily => "DAILY",
+            Freq::Weekly => "WEEKLY",
+            Freq::Monthly => "MONTHLY",
+            Freq::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={freq}")?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<_> = self.by_day.iter().map(|d| format!("{d}")[..2].to_ascii_uppercase()).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        if let Some(until) = self.until {
+            let until = until.format(&format_description!("[year][month][day]T[hour][minute][second]Z")).map_err(|_| fmt::Error)?;
+            write!(f, ";UNTIL={until}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Recurrence {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        String::deserialize(d)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn weekday(s: &str) -> Option<Weekday> {
+    Some(match s {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        _ => return None,
+    })
+}
+
+/// 只有日期时取当天结束，当天的那一次仍然算在内
+fn parse_until(s: &str) -> Option<OffsetDateTime> {
+    if let Ok(t) = PrimitiveDateTime::parse(s, &format_description!("[year][month][day]T[hour][minute][second]Z")) {
+        return Some(t.assume_utc());
+    }
+    let date = Date::parse(s, &format_description!("[year][month][day]")).ok()?;
+    Some(date.with_time(Time::from_hms(23, 59, 59).ok()?).assume_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn rule(s: &str) -> Recurrence {
+        s.parse().unwrap()
+    }
+
+    /// 从 `from` 起连续推算 `n` 次
+    fn occurrences(rule: &Recurrence, from: OffsetDateTime, n: usize) -> Vec<OffsetDateTime> {
+        let mut out = Vec::new();
+        let (mut at, mut rule) = (from, rule.clone());
+        while out.len() < n {
+            let Some((next, rest)) = rule.advance(at) else { break };
+            out.push(next);
+            (at, rule) = (next, rest);
+        }
+        out
+    }
+
+    #[test]
+    fn monthly_on_the_31st_skips_short_months() {
+        let got = occurrences(&rule("FREQ=MONTHLY"), datetime!(2030-01-31 12:00 UTC), 4);
+        assert_eq!(
+            got,
+            [datetime!(2030-03-31 12:00 UTC), datetime!(2030-05-31 12:00 UTC), datetime!(2030-07-31 12:00 UTC), datetime!(2030-08-31 12:00 UTC)]
+        );
+        // 跨年时月份回绕
+        let got = occurrences(&rule("FREQ=MONTHLY;INTERVAL=5"), datetime!(2030-10-31 08:00 UTC), 2);
+        assert_eq!(got, [datetime!(2031-03-31 08:00 UTC), datetime!(2031-08-31 08:00 UTC)]);
+    }
+
+    #[test]
+    fn yearly_on_feb_29_only_lands_on_leap_years() {
+        let got = occurrences(&rule("FREQ=YEARLY"), datetime!(2028-02-29 09:00 UTC), 2);
+        assert_eq!(got, [datetime!(2032-02-29 09:00 UTC), datetime!(2036-02-29 09:00 UTC)]);
+        // 有间隔时跳到间隔与闰年重合的那一年
+        let got = occurrences(&rule("FREQ=YEARLY;INTERVAL=3"), datetime!(2028-02-29 09:00 UTC), 1);
+        assert_eq!(got, [datetime!(2040-02-29 09:00 UTC)]);
+    }
+
+    #[test]
+    fn weekly_by_day_with_interval_skips_whole_weeks() {
+        // 2030-01-07 是周一
+        let got = occurrences(&rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=FR,MO"), datetime!(2030-01-07 09:00 UTC), 4);
+        assert_eq!(
+            got,
+            [datetime!(2030-01-11 09:00 UTC), datetime!(2030-01-21 09:00 UTC), datetime!(2030-01-25 09:00 UTC), datetime!(2030-02-04 09:00 UTC)]
+        );
+        // 起点不在 BYDAY 里：本周还没轮到的先算
+        let got = occurrences(&rule("FREQ=WEEKLY;BYDAY=TH"), datetime!(2030-01-08 09:00 UTC), 2);
+        assert_eq!(got, [datetime!(2030-01-10 09:00 UTC), datetime!(2030-01-17 09:00 UTC)]);
+        // 没有 BYDAY 时沿用起点的星期几
+        let got = occurrences(&rule("FREQ=WEEKLY;INTERVAL=3"), datetime!(2030-01-09 09:00 UTC), 1);
+        assert_eq!(got, [datetime!(2030-01-30 09:00 UTC)]);
+    }
+
+    #[test]
+    fn count_and_until_limit_the_series() {
+        // COUNT 含当前这一次
+        let from = datetime!(2030-01-07 09:00 UTC);
+        assert!(rule("FREQ=DAILY;COUNT=1").advance(from).is_none());
+        let (next, rest) = rule("FREQ=DAILY;COUNT=3").advance(from).unwrap();
+        assert_eq!((next, rest.count), (datetime!(2030-01-08 09:00 UTC), Some(2)));
+        assert_eq!(occurrences(&rule("FREQ=DAILY;COUNT=3"), from, 10).len(), 2);
+
+        // 只有日期的 UNTIL 包含当天
+        let got = occurrences(&rule("FREQ=DAILY;UNTIL=20300109"), from, 10);
+        assert_eq!(got, [datetime!(2030-01-08 09:00 UTC), datetime!(2030-01-09 09:00 UTC)]);
+        let got = occurrences(&rule("FREQ=DAILY;UNTIL=20300109T085959Z"), from, 10);
+        assert_eq!(got, [datetime!(2030-01-08 09:00 UTC)]);
+    }
+
+    #[test]
+    fn rejects_invalid_and_unsupported_parts() {
+        for s in [
+            "",
+            "INTERVAL=2",
+            "FREQ",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;INTERVAL=1000",
+            "FREQ=DAILY;COUNT=0",
+            "FREQ=DAILY;BYDAY=MO",
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=MONTHLY;BYMONTHDAY=31",
+            "FREQ=DAILY;UNTIL=2030-01-01",
+            "FREQ=DAILY;COUNT=2;UNTIL=20300101",
+        ] {
+            assert!(s.parse::<Recurrence>().is_err(), "{s:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn display_is_canonical_and_round_trips() {
+        let parsed = rule("RRULE:freq=weekly;byday=th,mo,th;interval=2;until=20300131");
+        let text = parsed.to_string();
+        assert_eq!(text, "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;UNTIL=20300131T235959Z");
+        assert_eq!(rule(&text), parsed);
+        for s in ["FREQ=DAILY", "FREQ=MONTHLY;COUNT=12", "FREQ=YEARLY;INTERVAL=4"] {
+            assert_eq!(rule(s).to_string(), s);
+        }
+    }
+}
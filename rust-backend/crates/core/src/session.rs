@@ -0,0 +1,35 @@
+use crate::RepoResult;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// 刷新令牌轮换的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshOutcome {
+    /// 旧令牌作废，新令牌已写入同一族
+    Rotated { user_id: Uuid },
+    /// 已轮换过的令牌被再次使用：视为泄露，整族已吊销
+    Reused { user_id: Uuid },
+    /// 不存在、已过期或已吊销
+    Invalid,
+}
+
+/// 会话存储：刷新令牌（只存 SHA-256 摘要）与访问令牌 `jti` 黑名单。
+/// 一次登录产生一个令牌族（family），之后每次刷新在族内轮换。
+#[async_trait::async_trait]
+pub trait SessionRepo: Send + Sync {
+    /// 登录时开一个新的令牌族
+    async fn create_refresh(&self, user_id: Uuid, token_hash: &str, expires_at: OffsetDateTime) -> RepoResult<()>;
+    /// 原子地作废 `token_hash` 并写入 `new_hash`；并发刷新同一令牌时只有一个成功
+    async fn rotate_refresh(&self, token_hash: &str, new_hash: &str, expires_at: OffsetDateTime) -> RepoResult<RefreshOutcome>;
+    /// 吊销令牌所在的整族；令牌不属于该用户时什么也不做
+    async fn revoke_family(&self, user_id: Uuid, token_hash: &str) -> RepoResult<()>;
+    /// 访问令牌在 `expires_at` 之前都视为已吊销
+    async fn deny_jti(&self, jti: Uuid, expires_at: OffsetDateTime) -> RepoResult<()>;
+    /// 访问令牌是否已失效：`jti` 在黑名单里、账号已停用，或签发后用户被强制下线（`session_epoch` 变了）
+    async fn is_revoked(&self, user_id: Uuid, jti: Uuid, session_epoch: i64) -> RepoResult<bool>;
+    /// 强制下线：吊销用户全部刷新令牌与个人访问令牌，`session_epoch` 加一让已签发的访问令牌失效。
+    /// 用户不存在时返回 `RepoError::NotFound("user_not_found")`
+    async fn revoke_user(&self, user_id: Uuid) -> RepoResult<()>;
+    /// 清理过期的刷新令牌与黑名单条目，返回删除条数
+    async fn purge_expired(&self) -> RepoResult<u64>;
+}
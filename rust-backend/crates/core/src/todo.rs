@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use crate::{Recurrence, RepoResult};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// 计划相关的三个字段在加入之前写进 outbox 的事件里没有，反序列化时按空处理
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub title: String,
+    pub done: bool,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub due_at: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub remind_at: Option<OffsetDateTime>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// 提醒任务（`JobRepo` 中 `kind = "todo.remind"`、`key` 为 todo id）的内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reminder {
+    pub user_id: Uuid,
+    pub todo_id: Uuid,
+    /// 登记时的提醒时间；执行时与 todo 当前的不一致说明已经改过，不再提醒
+    #[serde(with = "time::serde::rfc3339")]
+    pub remind_at: OffsetDateTime,
+}
+
+impl Reminder {
+    pub const JOB: &'static str = "todo.remind";
+}
+
+impl Todo {
+    /// 这次写入之后应有的提醒：未完成且设了提醒时间才有
+    pub fn reminder(&self) -> Option<Reminder> {
+        let remind_at = self.remind_at.filter(|_| !self.done)?;
+        Some(Reminder { user_id: self.user_id, todo_id: self.id, remind_at })
+    }
+
+    /// 从 `before` 改成 `self` 时提醒任务要不要动：提醒时间或完成状态变了才动，
+    /// 只改标题不会让已经发过的提醒再发一次
+    pub fn reminder_changed(&self, before: &Todo) -> bool {
+        self.remind_at != before.remind_at || self.done != before.done
+    }
+
+    /// 完成一个重复的 todo 时生成的下一次：截止时间按规则推算（没有截止时间时从 `completed_at` 算起），
+    /// 提醒时间随之平移。规则已用完时为 `None`
+    pub fn next_occurrence(&self, completed_at: OffsetDateTime) -> Option<NewTodo> {
+        let rule = self.recurrence.as_ref()?;
+        let base = self.due_at.unwrap_or(completed_at);
+        let (next, recurrence) = rule.advance(base)?;
+        let shift = next - base;
+        Some(NewTodo {
+            title: self.title.clone(),
+            due_at: Some(next),
+            remind_at: self.remind_at.map(|t| t + shift),
+            recurrence: Some(recurrence),
+        })
+    }
+
+    /// 完成一个重复的 todo：有下一次时，这一次改名为 `标题 (YYYY-MM-DD)`（这一次的截止日期，没有截止时间时
+    /// 用完成日期）留作记录，原标题让给下一次，同一用户下的标题始终唯一。返回要创建的下一次
+    pub fn complete_occurrence(&mut self, completed_at: OffsetDateTime) -> Option<NewTodo> {
+        let next = self.next_occurrence(completed_at)?;
+        let date = self.due_at.unwrap_or(completed_at).to_offset(time::UtcOffset::UTC).date();
+        self.title = format!("{} ({date})", self.title);
+        Some(next)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NewTodo {
+    pub title: String,
+    pub due_at: Option<OffsetDateTime>,
+    pub remind_at: Option<OffsetDateTime>,
+    pub recurrence: Option<Recurrence>,
+}
+
+impl NewTodo {
+    pub fn titled(title: impl Into<String>) -> Self {
+        Self { title: title.into(), ..Default::default() }
+    }
+}
+
+/// 部分更新：`None` 表示不改；可以清空的字段用 `Some(None)` 表示清空
+#[derive(Debug, Clone, Default)]
+pub struct TodoChanges {
+    pub title: Option<String>,
+    pub done: Option<bool>,
+    pub due_at: Option<Option<OffsetDateTime>>,
+    pub remind_at: Option<Option<OffsetDateTime>>,
+    pub recurrence: Option<Option<Recurrence>>,
+}
+
+impl TodoChanges {
+    pub fn apply(&self, todo: &Todo) -> Todo {
+        let todo = todo.clone();
+        Todo {
+            title: self.title.clone().unwrap_or(todo.title),
+            done: self.done.unwrap_or(todo.done),
+            due_at: self.due_at.unwrap_or(todo.due_at),
+            remind_at: self.remind_at.unwrap_or(todo.remind_at),
+            recurrence: self.recurrence.clone().unwrap_or(todo.recurrence),
+            ..todo
+        }
+    }
+}
+
+/// 列表的排序方向；键集分页按 `(created_at, id)` 排序，`id` 保证顺序稳定
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TodoSort {
+    #[default]
+    CreatedDesc,
+    CreatedAsc,
+}
+
+/// 键集游标：上一页最后一条的位置，下一页从它之后开始
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TodoCursor {
+    pub created_at: OffsetDateTime,
+    pub id: Uuid,
+}
+
+/// 列表查询条件；`limit` 由调用方校验并限定上限
+#[derive(Debug, Clone, Default)]
+pub struct TodoQuery {
+    pub done: Option<bool>,
+    /// 标题子串，大小写不敏感
+    pub q: Option<String>,
+    pub sort: TodoSort,
+    pub limit: u32,
+    pub after: Option<TodoCursor>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TodoPage {
+    pub items: Vec<Todo>,
+    /// 还有下一页时为本页最后一条的位置
+    pub next: Option<TodoCursor>,
+}
+
+/// 写操作都带 `user_id` 条件：别人的 todo 与不存在的 todo 对调用方没有区别。
+/// 创建、更新与删除成功时在同一事务里写入对应的 `TodoEvent`（见 outbox.rs），
+/// 并按 `Todo::reminder` 登记、改期或取消提醒任务（见 job.rs）
+#[async_trait::async_trait]
+pub trait TodoRepo: Send + Sync {
+    /// 标题重复时返回 `RepoError::Conflict("todo_title_conflict")`
+    async fn create(&self, user_id: Uuid, new: &NewTodo) -> RepoResult<Todo>;
+    async fn get(&self, user_id: Uuid, id: Uuid) -> RepoResult<Option<Todo>>;
+    /// 按条件分页列出某个用户的 todo
+    async fn list_by_user(&self, user_id: Uuid, query: &TodoQuery) -> RepoResult<TodoPage>;
+    /// 更新出现的字段；不存在或不属于该用户时返回 `RepoError::NotFound("todo_not_found")`。
+    /// 重复的 todo 从未完成变为完成时，按 `Todo::complete_occurrence` 给这一次改名，并在同一事务里创建下一次。
+    /// 改名后与另一个 todo 同名时返回 `RepoError::Conflict("todo_title_conflict")`
+    async fn update(&self, user_id: Uuid, id: Uuid, changes: &TodoChanges) -> RepoResult<Todo>;
+    async fn delete(&self, user_id: Uuid, id: Uuid) -> RepoResult<()>;
+    /// 清理标题为空的 todo，返回删除条数（供计划任务调用）
+    async fn purge_blank(&self) -> RepoResult<u64>;
+}
+
+/// Todo 用例，HTTP（routes.rs）与 gRPC（grpc.rs）两个入口共用，协议层只做参数转换。
+/// “只能操作自己的 todo” 由仓储的 `user_id` 条件保证
+pub struct TodoService {
+    repo: Arc<dyn TodoRepo>,
+}
+
+impl TodoService {
+    /// 单页上限；调用方传入更大的值时截断而不是报错
+    pub const MAX_PAGE_SIZE: u32 = 100;
+
+    pub fn new(repo: Arc<dyn TodoRepo>) -> Self {
+        Self { repo }
+    }
+
+    pub async fn create(&self, user_id: Uuid, new: &NewTodo) -> RepoResult<Todo> {
+        self.repo.create(user_id, new).await
+    }
+
+    pub async fn get(&self, user_id: Uuid, id: Uuid) -> RepoResult<Option<Todo>> {
+        self.repo.get(user_id, id).await
+    }
+
+    pub async fn list(&self, user_id: Uuid, query: &TodoQuery) -> RepoResult<TodoPage> {
+        let query = TodoQuery { limit: query.limit.clamp(1, Self::MAX_PAGE_SIZE), ..query.clone() };
+        self.repo.list_by_user(user_id, &query).await
+    }
+
+    pub async fn update(&self, user_id: Uuid, id: Uuid, changes: &TodoChanges) -> RepoResult<Todo> {
+        self.repo.update(user_id, id, changes).await
+    }
+
+    /// 幂等：已完成的 todo 再完成一次返回同样的结果，也不会再生成下一次
+    pub async fn complete(&self, user_id: Uuid, id: Uuid) -> RepoResult<Todo> {
+        self.repo.update(user_id, id, &TodoChanges { done: Some(true), ..Default::default() }).await
+    }
+
+    pub async fn delete(&self, user_id: Uuid, id: Uuid) -> RepoResult<()> {
+        self.repo.delete(user_id, id).await
+    }
+
+    pub async fn purge_blank(&self) -> RepoResult<u64> {
+        self.repo.purge_blank().await
+    }
+}
@@ -0,0 +1,67 @@
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::RepoResult;
+
+/// 签发访问令牌时需要的账号状态
+#[derive(Debug, Clone)]
+pub struct UserAccess {
+    pub email: String,
+    /// 按名称排序
+    pub roles: Vec<String>,
+    /// 全部角色的权限并集，按名称排序
+    pub permissions: Vec<String>,
+    /// 强制下线一次加一；令牌里的值与库里不同即视为吊销
+    pub session_epoch: i64,
+    pub disabled: bool,
+    pub email_verified: bool,
+}
+
+/// 管理接口看到的用户
+#[derive(Debug, Clone)]
+pub struct UserSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub roles: Vec<String>,
+    pub disabled_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+/// 键集分页位置：上一页最后一条的 `(created_at, id)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserCursor {
+    pub created_at: OffsetDateTime,
+    pub id: Uuid,
+}
+
+/// 按注册时间倒序
+#[derive(Debug, Clone, Default)]
+pub struct UserListQuery {
+    /// 邮箱子串
+    pub q: Option<String>,
+    pub limit: u32,
+    pub after: Option<UserCursor>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserPage {
+    pub items: Vec<UserSummary>,
+    pub next: Option<UserCursor>,
+}
+
+#[async_trait::async_trait]
+pub trait UserRepo: Send + Sync {
+    /// 同时授予 `DEFAULT_ROLE`；邮箱已注册时返回 `RepoError::Conflict("email_taken")`
+    async fn create(&self, email: &str, password_hash: &str) -> RepoResult<Uuid>;
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<(Uuid, String)>>;
+    /// 登录时透明重哈希用
+    async fn set_password_hash(&self, id: Uuid, password_hash: &str) -> RepoResult<()>;
+    async fn access(&self, id: Uuid) -> RepoResult<Option<UserAccess>>;
+    /// 已验证过时保留最初的验证时间
+    async fn mark_email_verified(&self, id: Uuid) -> RepoResult<()>;
+    /// 已有该角色时什么也不做
+    async fn grant_role(&self, id: Uuid, role: &str) -> RepoResult<()>;
+    async fn list(&self, query: &UserListQuery) -> RepoResult<UserPage>;
+    /// 用户不存在时返回 `RepoError::NotFound("user_not_found")`
+    async fn set_disabled(&self, id: Uuid, disabled: bool) -> RepoResult<UserSummary>;
+}
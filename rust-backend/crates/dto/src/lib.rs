@@ -0,0 +1,296 @@
+use serde::{Serialize, Deserialize, Deserializer};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+use uuid::Uuid;
+use time::{OffsetDateTime, macros::format_description};
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct RegisterReq {
+    #[validate(length(min = 3, max = 64))]
+    pub email: String,
+    #[validate(length(min = 8))]
+    pub password: String,
+}
+
+/// 登录/注册/刷新的响应：`token` 是短期访问令牌，`expires_in` 为其剩余秒数；
+/// `refresh_token` 只能使用一次，刷新后换成新的
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuthResp {
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LoginReq { pub email: String, pub password: String }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshReq { pub refresh_token: String }
+
+/// 登出：吊销当前访问令牌；带上 `refresh_token` 时同时吊销它所在的令牌族
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct LogoutReq { pub refresh_token: Option<String> }
+
+/// 邮件链接里的 `token`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyEmailReq { pub token: String }
+
+/// 重发验证邮件、申请重置密码
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct EmailReq {
+    #[validate(length(min = 3, max = 64))]
+    pub email: String,
+}
+
+/// 身份提供方跳回 `redirect_uri` 时带的查询参数，原样转交
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OidcCallbackReq {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct PasswordResetConfirm {
+    pub token: String,
+    #[validate(length(min = 8))]
+    pub password: String,
+}
+
+/// 创建个人访问令牌；`scopes` 至少一项：`todos:read`、`todos:write`，或调用者自己拥有的管理权限
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ApiKeyCreate {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1, max = 16))]
+    pub scopes: Vec<String>,
+    /// 有效天数，默认 90
+    #[validate(range(min = 1, max = 365))]
+    pub expires_in_days: Option<u32>,
+}
+
+/// 一把访问令牌的元数据；令牌本身只在创建时返回一次
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyView {
+    pub id: Uuid,
+    pub name: String,
+    /// 令牌开头的明文部分，用来辨认是哪一把
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: String,
+    pub last_used_at: Option<String>,
+    pub last_used_ip: Option<String>,
+    pub created_at: String,
+}
+
+/// 创建结果：`token` 形如 `pat_...`，作为 `Authorization: Bearer` 使用，服务端不保存明文
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyCreated {
+    pub token: String,
+    pub key: ApiKeyView,
+}
+
+/// `due_at`、`remind_at` 为 RFC 3339；`recurrence` 为 RRULE 的子集，如 `FREQ=WEEKLY;BYDAY=MO,TH;COUNT=10`，
+/// 完成后按它生成下一次，下一次沿用标题，完成的这一次改名为 `标题 (YYYY-MM-DD)`
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct TodoCreate {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remind_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+}
+
+/// PATCH 请求体：只更新出现的字段；`due_at`、`remind_at`、`recurrence` 传 `null` 表示清空
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct TodoUpdate {
+    pub title: Option<String>,
+    pub done: Option<bool>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub due_at: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub remind_at: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub recurrence: Option<Option<String>>,
+}
+
+/// 字段出现（哪怕是 `null`）就是 `Some`；没出现时由 `default` 得到 `None`
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(d: D) -> Result<Option<T>, D::Error> {
+    T::deserialize(d).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TodoView {
+    pub id: Uuid,
+    pub title: String,
+    pub done: bool,
+    pub due_at: Option<String>,
+    pub remind_at: Option<String>,
+    /// 规范形式，部件顺序固定
+    pub recurrence: Option<String>,
+    pub created_at: String,
+}
+
+/// 列表排序：`-created_at`（默认，新的在前）或 `created_at`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum TodoSortParam {
+    #[default]
+    #[serde(rename = "-created_at")]
+    CreatedDesc,
+    #[serde(rename = "created_at")]
+    CreatedAsc,
+}
+
+/// `GET /api/v1/todos` 的查询参数；翻页时其余条件应与取得 `cursor` 的那次请求一致。
+/// 客户端（todo-cli）序列化同一个类型作查询串
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TodoListQuery {
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<u32>,
+    /// 上一页响应中的 `next_cursor`
+    pub cursor: Option<String>,
+    pub done: Option<bool>,
+    /// 标题子串，大小写不敏感
+    #[validate(length(max = 200))]
+    pub q: Option<String>,
+    #[serde(default)]
+    pub sort: TodoSortParam,
+}
+
+/// 分页响应：`next_cursor` 为空表示已到最后一页
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcomeView { Success, Failure }
+
+/// 一条审计事件；`occurred_at` 为带小数秒的 RFC 3339，可以直接作为 `from`/`to` 使用
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEventView {
+    pub id: Uuid,
+    pub occurred_at: String,
+    /// 未登录的请求（注册、登录失败、令牌被拒）为空
+    pub actor: Option<Uuid>,
+    /// 如 `auth.login`、`auth.token_rejected`、`todo.delete`、`authz.denied`
+    pub action: String,
+    pub target: Option<String>,
+    pub outcome: AuditOutcomeView,
+    /// 失败时的错误码
+    pub reason: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// `GET /api/v1/admin/audit` 的查询参数，结果按时间倒序
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditListQuery {
+    /// 起始时间（含），RFC 3339
+    pub from: Option<String>,
+    /// 截止时间（不含），RFC 3339
+    pub to: Option<String>,
+    pub actor: Option<Uuid>,
+    #[validate(length(max = 64))]
+    pub action: Option<String>,
+    pub outcome: Option<AuditOutcomeView>,
+    #[validate(range(min = 1, max = 500))]
+    pub limit: Option<u32>,
+    /// 上一页响应中的 `next_cursor`
+    pub cursor: Option<String>,
+}
+
+/// 管理接口看到的用户
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserAdminView {
+    pub id: Uuid,
+    pub email: String,
+    /// 如 `user`、`admin`，按名称排序
+    pub roles: Vec<String>,
+    pub disabled: bool,
+    pub created_at: String,
+}
+
+/// `GET /api/v1/admin/users` 的查询参数，结果按注册时间倒序
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminUserListQuery {
+    /// 邮箱子串，大小写不敏感
+    #[validate(length(max = 200))]
+    pub q: Option<String>,
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<u32>,
+    /// 上一页响应中的 `next_cursor`
+    pub cursor: Option<String>,
+}
+
+/// `/.well-known/jwks.json` 中的一把公钥（RFC 7517）；`crv`/`x` 用于 OKP，`n`/`e` 用于 RSA
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Jwk {
+    pub kty: String,
+    pub kid: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub use_: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JwkSet { pub keys: Vec<Jwk> }
+
+/// 统一错误响应体：`code` 稳定、供客户端分支判断；`message` 面向人阅读；
+/// `request_id` 与响应头 `x-request-id` 一致，用于对照服务端日志
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus { Ok, Fail }
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CheckView {
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// `/readyz` 响应：任一依赖检查失败则整体为 `fail`，状态码 503
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessView {
+    pub status: CheckStatus,
+    pub checks: std::collections::BTreeMap<String, CheckView>,
+}
+
+/// `/api/v1/todos/stream` 推送的消息，每条是一个 WebSocket 文本帧
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TodoStreamMsg {
+    /// 一次变更；`event_id` 全局唯一，重复收到时按它去重
+    Event { event_id: Uuid, event_type: String, todo: TodoView },
+    /// 连接落后太多，服务端跳过了 `missed` 条事件（含其他用户的）：客户端应重新拉取列表
+    Resync { missed: u64 },
+}
+
+pub fn fmt_time(ts: OffsetDateTime) -> String {
+    ts.format(&format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]Z")).unwrap()
+}
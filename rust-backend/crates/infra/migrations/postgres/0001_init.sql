@@ -0,0 +1,14 @@
+create table if not exists users (
+  id uuid primary key,
+  email text unique not null,
+  password_hash text not null,
+  created_at timestamptz not null default now()
+);
+
+create table if not exists todos (
+  id uuid primary key,
+  user_id uuid not null references users(id),
+  title text not null,
+  done boolean not null default false,
+  created_at timestamptz not null default now()
+);
@@ -0,0 +1,9 @@
+-- 同一用户下 todo 标题唯一。先给历史重复数据（保留最早的一条）追加 id 后缀，避免建索引失败。
+update todos t
+set title = t.title || ' (' || t.id || ')'
+where exists (
+  select 1 from todos o
+  where o.user_id = t.user_id and o.title = t.title and (o.created_at, o.id) < (t.created_at, t.id)
+);
+
+create unique index if not exists todos_user_title_uniq on todos (user_id, title);
@@ -0,0 +1,2 @@
+-- 列表按 (created_at, id) 键集分页，正序倒序都能用同一个索引扫描
+create index if not exists todos_user_created_idx on todos (user_id, created_at, id);
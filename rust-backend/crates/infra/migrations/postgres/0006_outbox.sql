@@ -0,0 +1,18 @@
+-- 事务性 outbox：业务写入与事件在同一事务提交，dispatcher 异步投递（至少一次）。
+-- status: pending 待投递 → dispatched 已投递；超过重试上限转 dead（死信）。
+-- created_at 由应用写入，同一事务里的多条事件也有先后。
+create table if not exists outbox (
+  id uuid primary key,
+  aggregate_type text not null,
+  aggregate_id uuid not null,
+  user_id uuid not null,
+  event_type text not null,
+  payload jsonb not null,
+  status text not null default 'pending' check (status in ('pending', 'dispatched', 'dead')),
+  attempts integer not null default 0,
+  next_attempt_at timestamptz not null default now(),
+  last_error text,
+  created_at timestamptz not null,
+  dispatched_at timestamptz
+);
+create index if not exists outbox_pending_idx on outbox (next_attempt_at) where status = 'pending';
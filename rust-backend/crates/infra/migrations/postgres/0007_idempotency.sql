@@ -0,0 +1,14 @@
+-- Idempotency-Key：同一用户的同一个键只执行一次，之后的重试重放 status/headers/body。
+-- status 为空表示第一次请求还在执行；expires_at 到期后键可以重新使用。
+create table if not exists idempotency_keys (
+  user_id uuid not null references users(id) on delete cascade,
+  key text not null,
+  fingerprint text not null,
+  status smallint,
+  headers jsonb,
+  body bytea,
+  expires_at timestamptz not null,
+  created_at timestamptz not null default now(),
+  primary key (user_id, key)
+);
+create index if not exists idempotency_keys_expires_idx on idempotency_keys (expires_at);
@@ -0,0 +1,27 @@
+-- 安全审计日志，只追加。actor 不设外键：用户删除后审计记录仍要保留。
+-- outcome: success / failure；失败时 reason 为对外的错误码。
+create table if not exists audit_events (
+  id uuid primary key,
+  occurred_at timestamptz not null,
+  actor uuid,
+  action text not null,
+  target text,
+  outcome text not null check (outcome in ('success', 'failure')),
+  reason text,
+  ip text,
+  user_agent text,
+  request_id text
+);
+create index if not exists audit_events_occurred_idx on audit_events (occurred_at desc, id desc);
+create index if not exists audit_events_actor_idx on audit_events (actor, occurred_at desc);
+
+-- 应用账号误操作也改不了历史；确需归档时由 DBA 临时禁用触发器
+create or replace function audit_events_append_only() returns trigger as $$
+begin
+  raise exception 'audit_events is append-only';
+end;
+$$ language plpgsql;
+
+drop trigger if exists audit_events_no_update on audit_events;
+create trigger audit_events_no_update before update or delete on audit_events
+  for each row execute function audit_events_append_only();
@@ -0,0 +1,36 @@
+-- 角色与权限。权限签发时展开进访问令牌；user 角色没有额外权限，只能访问自己的数据。
+-- users.session_epoch：强制下线时加一，令牌里的值与之不同即失效；disabled_at 非空表示已停用。
+alter table users add column if not exists disabled_at timestamptz;
+alter table users add column if not exists session_epoch bigint not null default 0;
+
+create table if not exists roles (
+  name text primary key,
+  description text not null
+);
+
+create table if not exists role_permissions (
+  role text not null references roles(name) on delete cascade,
+  permission text not null,
+  primary key (role, permission)
+);
+
+create table if not exists user_roles (
+  user_id uuid not null references users(id) on delete cascade,
+  role text not null references roles(name) on delete cascade,
+  granted_at timestamptz not null default now(),
+  primary key (user_id, role)
+);
+
+insert into roles (name, description) values
+  ('user', 'regular account, own todos only'),
+  ('admin', 'user administration and audit')
+on conflict do nothing;
+
+insert into role_permissions (role, permission) values
+  ('admin', 'audit:read'),
+  ('admin', 'users:read'),
+  ('admin', 'users:manage'),
+  ('admin', 'todos:admin')
+on conflict do nothing;
+
+insert into user_roles (user_id, role) select id, 'user' from users on conflict do nothing;
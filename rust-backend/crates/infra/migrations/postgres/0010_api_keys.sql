@@ -0,0 +1,16 @@
+-- 个人访问令牌：只存 SHA-256 摘要，prefix 是令牌开头的明文，列表里用来辨认。
+-- 吊销即删除；last_used_* 同一 IP 一分钟内不重复更新。
+create table if not exists api_keys (
+  id uuid primary key,
+  user_id uuid not null references users(id) on delete cascade,
+  name text not null,
+  prefix text not null,
+  token_hash text not null unique,
+  scopes text[] not null,
+  expires_at timestamptz not null,
+  last_used_at timestamptz,
+  last_used_ip text,
+  created_at timestamptz not null default now(),
+  unique (user_id, name)
+);
+create index if not exists api_keys_expires_idx on api_keys (expires_at);
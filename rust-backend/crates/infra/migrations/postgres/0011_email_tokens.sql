@@ -0,0 +1,14 @@
+-- 邮箱验证与重置密码。email_verified_at 为空表示未验证；本迁移之前注册的账号视为已验证，
+-- 不因上线验证流程被限制。email_tokens 只记令牌 ID（令牌本身由服务端签名），used_at 保证只能用一次。
+alter table users add column if not exists email_verified_at timestamptz;
+update users set email_verified_at = created_at where email_verified_at is null;
+
+create table if not exists email_tokens (
+  id uuid primary key,
+  user_id uuid not null references users(id) on delete cascade,
+  purpose text not null,
+  expires_at timestamptz not null,
+  used_at timestamptz,
+  created_at timestamptz not null default now()
+);
+create index if not exists email_tokens_user_idx on email_tokens (user_id, purpose);
@@ -0,0 +1,19 @@
+-- OIDC 登录。oidc_logins 是跳转到身份提供方之前记下的一次性状态，回调时取出即删除；
+-- user_identities 把外部身份（iss + sub）绑定到本地用户，一个用户可以绑定多个身份。
+create table if not exists oidc_logins (
+  state text primary key,
+  code_verifier text not null,
+  nonce text not null,
+  expires_at timestamptz not null,
+  created_at timestamptz not null default now()
+);
+
+create table if not exists user_identities (
+  issuer text not null,
+  subject text not null,
+  user_id uuid not null references users(id) on delete cascade,
+  email text not null,
+  created_at timestamptz not null default now(),
+  primary key (issuer, subject)
+);
+create index if not exists user_identities_user_idx on user_identities (user_id);
@@ -0,0 +1,15 @@
+-- SQLite 版本：uuid 存 16 字节 blob，时间存 Unix 微秒整数，由应用写入
+create table if not exists users (
+  id blob primary key,
+  email text unique not null,
+  password_hash text not null,
+  created_at integer not null
+);
+
+create table if not exists todos (
+  id blob primary key,
+  user_id blob not null references users(id),
+  title text not null,
+  done boolean not null default false,
+  created_at integer not null
+);
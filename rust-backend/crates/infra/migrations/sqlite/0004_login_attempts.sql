@@ -0,0 +1,7 @@
+-- 登录失败计数：key 为 `account:<email>` 或 `ip:<addr>`，锁定到 locked_until
+create table if not exists login_attempts (
+  key text primary key,
+  failures integer not null default 0,
+  last_failure_at integer not null,
+  locked_until integer
+);
@@ -0,0 +1,19 @@
+-- 刷新令牌：只存 SHA-256 摘要。同一次登录的令牌属于同一 family，
+-- 轮换时旧令牌记 used_at；已轮换的令牌再次出现即判定泄露，整族吊销。
+create table if not exists refresh_tokens (
+  id blob primary key,
+  user_id blob not null references users(id) on delete cascade,
+  family_id blob not null,
+  token_hash text not null unique,
+  expires_at integer not null,
+  used_at integer,
+  revoked_at integer,
+  created_at integer not null
+);
+create index if not exists refresh_tokens_family_idx on refresh_tokens (family_id);
+
+-- 主动吊销（登出）的访问令牌，保留到令牌本身过期
+create table if not exists revoked_jtis (
+  jti blob primary key,
+  expires_at integer not null
+);
@@ -0,0 +1,18 @@
+-- 事务性 outbox：业务写入与事件在同一事务提交，dispatcher 异步投递（至少一次）。
+-- status: pending 待投递 → dispatched 已投递；超过重试上限转 dead（死信）。
+-- payload 为 JSON 文本。
+create table if not exists outbox (
+  id blob primary key,
+  aggregate_type text not null,
+  aggregate_id blob not null,
+  user_id blob not null,
+  event_type text not null,
+  payload text not null,
+  status text not null default 'pending' check (status in ('pending', 'dispatched', 'dead')),
+  attempts integer not null default 0,
+  next_attempt_at integer not null,
+  last_error text,
+  created_at integer not null,
+  dispatched_at integer
+);
+create index if not exists outbox_pending_idx on outbox (next_attempt_at) where status = 'pending';
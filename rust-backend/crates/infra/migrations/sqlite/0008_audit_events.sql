@@ -0,0 +1,25 @@
+-- 安全审计日志，只追加。actor 不设外键：用户删除后审计记录仍要保留。
+-- outcome: success / failure；失败时 reason 为对外的错误码。
+create table if not exists audit_events (
+  id blob primary key,
+  occurred_at integer not null,
+  actor blob,
+  action text not null,
+  target text,
+  outcome text not null check (outcome in ('success', 'failure')),
+  reason text,
+  ip text,
+  user_agent text,
+  request_id text
+);
+create index if not exists audit_events_occurred_idx on audit_events (occurred_at desc, id desc);
+create index if not exists audit_events_actor_idx on audit_events (actor, occurred_at desc);
+
+create trigger if not exists audit_events_no_update before update on audit_events
+begin
+  select raise(abort, 'audit_events is append-only');
+end;
+create trigger if not exists audit_events_no_delete before delete on audit_events
+begin
+  select raise(abort, 'audit_events is append-only');
+end;
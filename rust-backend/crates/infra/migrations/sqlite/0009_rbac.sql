@@ -0,0 +1,35 @@
+-- 角色与权限。权限签发时展开进访问令牌；user 角色没有额外权限，只能访问自己的数据。
+-- users.session_epoch：强制下线时加一，令牌里的值与之不同即失效；disabled_at 非空表示已停用。
+alter table users add column disabled_at integer;
+alter table users add column session_epoch integer not null default 0;
+
+create table if not exists roles (
+  name text primary key,
+  description text not null
+);
+
+create table if not exists role_permissions (
+  role text not null references roles(name) on delete cascade,
+  permission text not null,
+  primary key (role, permission)
+);
+
+create table if not exists user_roles (
+  user_id blob not null references users(id) on delete cascade,
+  role text not null references roles(name) on delete cascade,
+  granted_at integer not null,
+  primary key (user_id, role)
+);
+
+insert or ignore into roles (name, description) values
+  ('user', 'regular account, own todos only'),
+  ('admin', 'user administration and audit');
+
+insert or ignore into role_permissions (role, permission) values
+  ('admin', 'audit:read'),
+  ('admin', 'users:read'),
+  ('admin', 'users:manage'),
+  ('admin', 'todos:admin');
+
+insert or ignore into user_roles (user_id, role, granted_at)
+  select id, 'user', cast(strftime('%s', 'now') as integer) * 1000000 from users;
@@ -0,0 +1,23 @@
+-- 截止时间、提醒与重复规则。recurrence 存规范化的 RRULE 文本（如 FREQ=WEEKLY;BYDAY=MO,TH;COUNT=5）。
+alter table todos add column due_at integer;
+alter table todos add column remind_at integer;
+alter table todos add column recurrence text;
+
+-- 持久化的任务队列：提醒（kind = 'todo.remind'，key 为 todo id）与周期任务（如 maintenance）。
+-- 领取即把 run_at 推后一个租约并换上新的 lease，只有持有当前 lease 的执行者能确认完成。
+-- status: pending 待执行 → done 已结束；超过重试上限转 dead。payload 为 JSON 文本。
+create table if not exists jobs (
+  id blob primary key,
+  kind text not null,
+  key text not null,
+  payload text not null default '{}',
+  status text not null default 'pending' check (status in ('pending', 'done', 'dead')),
+  run_at integer not null,
+  attempts integer not null default 0,
+  lease blob,
+  last_error text,
+  created_at integer not null,
+  finished_at integer,
+  unique (kind, key)
+);
+create index if not exists jobs_due_idx on jobs (run_at) where status = 'pending';
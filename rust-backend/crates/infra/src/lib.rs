@@ -0,0 +1,68 @@
+//! 仓储的 sqlx 实现。后端由互斥的 `postgres`/`sqlite` 特性选择（见第 13.2 节），
+//! 两个后端对外提供同样的 `Db`、`connect`、`migrate` 与 `repos`，api 不需要区分。
+
+#[cfg(all(feature = "postgres", feature = "sqlite"))]
+compile_error!("features `postgres` and `sqlite` are mutually exclusive; use `--no-default-features --features postgres`");
+#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
+compile_error!("enable one database backend: `postgres` or `sqlite`");
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "postgres")]
+pub use postgres::{connect, connect_lazy, migrate, migration_status, ping, repos, Db};
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{connect, connect_lazy, migrate, migration_status, ping, repos, Db};
+
+use std::sync::Arc;
+
+use app_core::{ApiKeyRepo, AuditRepo, EmailTokenRepo, IdempotencyStore, JobRepo, LoginAttemptRepo, OidcRepo, OutboxRepo, RepoError, SessionRepo, TodoRepo, UserRepo};
+
+/// 当前后端的全部仓储
+#[derive(Clone)]
+pub struct Repos {
+    pub users: Arc<dyn UserRepo>,
+    pub audit: Arc<dyn AuditRepo>,
+    pub api_keys: Arc<dyn ApiKeyRepo>,
+    pub email_tokens: Arc<dyn EmailTokenRepo>,
+    pub todos: Arc<dyn TodoRepo>,
+    pub sessions: Arc<dyn SessionRepo>,
+    pub login_attempts: Arc<dyn LoginAttemptRepo>,
+    pub oidc: Arc<dyn OidcRepo>,
+    pub outbox: Arc<dyn OutboxRepo>,
+    pub jobs: Arc<dyn JobRepo>,
+    pub idempotency: Arc<dyn IdempotencyStore>,
+}
+
+/// 库里已应用的最高迁移版本与本二进制内嵌的最高版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub applied: Option<i64>,
+    pub expected: i64,
+}
+
+fn db_err(e: sqlx::Error) -> RepoError {
+    RepoError::Db(Box::new(e))
+}
+
+/// 唯一约束冲突翻译为带错误码的 `Conflict`，其余错误归为 `Db`
+fn conflict_or_db(code: &'static str) -> impl FnOnce(sqlx::Error) -> RepoError {
+    move |e| match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => RepoError::Conflict(code),
+        _ => db_err(e),
+    }
+}
+
+/// 转义 LIKE 通配符，`q` 只按字面子串匹配
+fn escape_like(q: &str) -> String {
+    let mut out = String::with_capacity(q.len());
+    for c in q.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,56 @@
+use app_core::{EmailTokenPurpose, EmailTokenRepo, RepoResult};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::db_err;
+
+pub struct PgEmailTokenRepo { pool: PgPool }
+
+impl PgEmailTokenRepo {
+    pub fn new(pool: PgPool) -> Self { Self { pool } }
+}
+
+#[async_trait::async_trait]
+impl EmailTokenRepo for PgEmailTokenRepo {
+    async fn issue(&self, id: Uuid, user_id: Uuid, purpose: EmailTokenPurpose, expires_at: OffsetDateTime) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        sqlx::query("update email_tokens set used_at = now() where user_id = $1 and purpose = $2 and used_at is null")
+            .bind(user_id)
+            .bind(purpose.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        sqlx::query("insert into email_tokens (id, user_id, purpose, expires_at) values ($1, $2, $3, $4)")
+            .bind(id)
+            .bind(user_id)
+            .bind(purpose.as_str())
+            .bind(expires_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        tx.commit().await.map_err(db_err)
+    }
+
+    async fn consume(&self, id: Uuid, purpose: EmailTokenPurpose) -> RepoResult<Option<Uuid>> {
+        let row: Option<(Uuid,)> = sqlx::query_as(
+            r#"update email_tokens set used_at = now()
+               where id = $1 and purpose = $2 and used_at is null and expires_at > now()
+               returning user_id"#,
+        )
+        .bind(id)
+        .bind(purpose.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(row.map(|(user_id,)| user_id))
+    }
+
+    async fn purge_expired(&self) -> RepoResult<u64> {
+        let res = sqlx::query("delete from email_tokens where expires_at <= now()")
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
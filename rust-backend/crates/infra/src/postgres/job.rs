@@ -0,0 +1,155 @@
+use app_core::{Job, JobRepo, RepoError, RepoResult, Reminder, Todo};
+use sqlx::{PgConnection, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::db_err;
+
+pub struct PgJobRepo { pool: PgPool }
+
+impl PgJobRepo {
+    pub fn new(pool: PgPool) -> Self { Self { pool } }
+}
+
+/// 由 todo 的写操作在自己的事务里调用：按 `Todo::reminder` 登记或改期提醒，没有提醒时取消尚未执行的那条
+pub(super) async fn sync_reminder(conn: &mut PgConnection, todo: &Todo) -> RepoResult<()> {
+    let Some(reminder) = todo.reminder() else { return cancel_reminder(conn, todo.id).await };
+    let payload = serde_json::to_string(&reminder).map_err(|e| RepoError::Db(Box::new(e)))?;
+    // 改期会让正在执行旧提醒的实例丢掉 lease，它的确认不再生效
+    sqlx::query(
+        r#"insert into jobs (id, kind, key, payload, run_at) values ($1, $2, $3, $4::jsonb, $5)
+           on conflict (kind, key) do update set payload = excluded.payload, run_at = excluded.run_at,
+             status = 'pending', attempts = 0, lease = null, last_error = null, finished_at = null"#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(Reminder::JOB)
+    .bind(todo.id.to_string())
+    .bind(payload)
+    .bind(reminder.remind_at)
+    .execute(conn)
+    .await
+    .map_err(db_err)?;
+    Ok(())
+}
+
+pub(super) async fn cancel_reminder(conn: &mut PgConnection, todo_id: Uuid) -> RepoResult<()> {
+    sqlx::query("delete from jobs where kind = $1 and key = $2 and status = 'pending'")
+        .bind(Reminder::JOB)
+        .bind(todo_id.to_string())
+        .execute(conn)
+        .await
+        .map_err(db_err)?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct JobRow {
+    id: Uuid,
+    kind: String,
+    key: String,
+    payload: String,
+    attempts: i32,
+    lease: Uuid,
+    run_at: OffsetDateTime,
+}
+
+impl TryFrom<JobRow> for Job {
+    type Error = RepoError;
+
+    fn try_from(r: JobRow) -> RepoResult<Self> {
+        Ok(Job {
+            id: r.id,
+            kind: r.kind,
+            key: r.key,
+            payload: serde_json::from_str(&r.payload).map_err(|e| RepoError::Db(Box::new(e)))?,
+            attempts: r.attempts,
+            lease: r.lease,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl JobRepo for PgJobRepo {
+    async fn ensure(&self, kind: &str, key: &str, run_at: OffsetDateTime) -> RepoResult<()> {
+        sqlx::query("insert into jobs (id, kind, key, run_at) values ($1, $2, $3, $4) on conflict (kind, key) do nothing")
+            .bind(Uuid::new_v4())
+            .bind(kind)
+            .bind(key)
+            .bind(run_at)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn claim(&self, limit: u32, lease: time::Duration) -> RepoResult<Vec<Job>> {
+        // 返回的 run_at 已是租约到期时间，只用来排序
+        let mut rows: Vec<JobRow> = sqlx::query_as(
+            r#"update jobs set attempts = attempts + 1, lease = $3, run_at = now() + $2 * interval '1 second'
+               where id in (
+                 select id from jobs where status = 'pending' and run_at <= now()
+                 order by run_at, id limit $1
+                 for update skip locked)
+               returning id, kind, key, payload::text as payload, attempts, lease, run_at"#,
+        )
+        .bind(i64::from(limit))
+        .bind(lease.whole_seconds())
+        .bind(Uuid::new_v4())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        rows.sort_by_key(|r| (r.run_at, r.id));
+        rows.into_iter().map(Job::try_from).collect()
+    }
+
+    async fn finish(&self, job: &Job, next: Option<OffsetDateTime>) -> RepoResult<()> {
+        sqlx::query(
+            r#"update jobs set lease = null, last_error = null, attempts = 0,
+                 status = case when $3::timestamptz is null then 'done' else 'pending' end,
+                 run_at = coalesce($3, run_at),
+                 finished_at = case when $3::timestamptz is null then now() end
+               where id = $1 and lease = $2"#,
+        )
+        .bind(job.id)
+        .bind(job.lease)
+        .bind(next)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn retry_later(&self, job: &Job, error: &str, retry_at: OffsetDateTime) -> RepoResult<()> {
+        sqlx::query("update jobs set lease = null, run_at = $3, last_error = $4 where id = $1 and lease = $2")
+            .bind(job.id)
+            .bind(job.lease)
+            .bind(retry_at)
+            .bind(error)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn mark_dead(&self, job: &Job, error: &str) -> RepoResult<()> {
+        sqlx::query(
+            "update jobs set status = 'dead', lease = null, last_error = $3, finished_at = now() where id = $1 and lease = $2",
+        )
+        .bind(job.id)
+        .bind(job.lease)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn purge_finished(&self, before: OffsetDateTime) -> RepoResult<u64> {
+        let res = sqlx::query("delete from jobs where status in ('done', 'dead') and finished_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
@@ -0,0 +1,67 @@
+use app_core::{LoginAttemptRepo, RepoResult};
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+
+use crate::db_err;
+
+pub struct PgLoginAttemptRepo { pool: PgPool }
+
+impl PgLoginAttemptRepo {
+    pub fn new(pool: PgPool) -> Self { Self { pool } }
+}
+
+#[async_trait::async_trait]
+impl LoginAttemptRepo for PgLoginAttemptRepo {
+    async fn locked_until(&self, key: &str) -> RepoResult<Option<OffsetDateTime>> {
+        let row: Option<(Option<OffsetDateTime>,)> = sqlx::query_as("select locked_until from login_attempts where key = $1")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(row.and_then(|(until,)| until))
+    }
+
+    async fn record_failure(&self, key: &str, window: Duration) -> RepoResult<u32> {
+        // 单条 upsert 完成计数，多实例并发失败也不会丢
+        let (failures,): (i32,) = sqlx::query_as(
+            r#"insert into login_attempts (key, failures, last_failure_at) values ($1, 1, now())
+               on conflict (key) do update set
+                 failures = case when login_attempts.last_failure_at < now() - $2 * interval '1 second'
+                                 then 1 else login_attempts.failures + 1 end,
+                 last_failure_at = now()
+               returning failures"#,
+        )
+        .bind(key)
+        .bind(window.whole_seconds())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(failures as u32)
+    }
+
+    async fn lock(&self, key: &str, until: OffsetDateTime) -> RepoResult<()> {
+        sqlx::query("update login_attempts set locked_until = $2 where key = $1")
+            .bind(key)
+            .bind(until)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> RepoResult<()> {
+        sqlx::query("delete from login_attempts where key = $1").bind(key).execute(&self.pool).await.map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn purge_expired(&self, before: OffsetDateTime) -> RepoResult<u64> {
+        let res = sqlx::query(
+            "delete from login_attempts where last_failure_at < $1 and (locked_until is null or locked_until < now())",
+        )
+        .bind(before)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
@@ -0,0 +1,77 @@
+//! Postgres 后端：生产部署使用。
+
+mod api_key;
+mod audit;
+mod email_token;
+mod idempotency;
+mod job;
+mod login_attempt;
+mod oidc;
+mod outbox;
+mod session;
+mod todo;
+mod user;
+
+pub use api_key::PgApiKeyRepo;
+pub use audit::PgAuditRepo;
+pub use email_token::PgEmailTokenRepo;
+pub use idempotency::PgIdempotencyStore;
+pub use job::PgJobRepo;
+pub use login_attempt::PgLoginAttemptRepo;
+pub use oidc::PgOidcRepo;
+pub use outbox::PgOutboxRepo;
+pub use session::PgSessionRepo;
+pub use todo::PgTodoRepo;
+pub use user::PgUserRepo;
+
+use std::sync::Arc;
+
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::PgPool;
+
+use crate::{MigrationStatus, Repos};
+
+pub type Db = PgPool;
+
+pub async fn connect(url: &str) -> sqlx::Result<Db> {
+    PgPool::connect(url).await
+}
+
+/// 不立即建连，第一次查询时才连接；测试里组装不访问数据库的路由用
+pub fn connect_lazy(url: &str) -> sqlx::Result<Db> {
+    PgPool::connect_lazy(url)
+}
+
+/// migrations/postgres 在编译期嵌入二进制
+static MIGRATOR: Migrator = sqlx::migrate!("migrations/postgres");
+
+pub async fn migrate(db: &Db) -> Result<(), MigrateError> {
+    MIGRATOR.run(db).await
+}
+
+pub async fn ping(db: &Db) -> sqlx::Result<()> {
+    sqlx::query("select 1").execute(db).await.map(|_| ())
+}
+
+pub async fn migration_status(db: &Db) -> sqlx::Result<MigrationStatus> {
+    let (applied,): (Option<i64>,) = sqlx::query_as("select max(version) from _sqlx_migrations where success")
+        .fetch_one(db)
+        .await?;
+    Ok(MigrationStatus { applied, expected: MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0) })
+}
+
+pub fn repos(db: &Db) -> Repos {
+    Repos {
+        users: Arc::new(PgUserRepo::new(db.clone())),
+        audit: Arc::new(PgAuditRepo::new(db.clone())),
+        api_keys: Arc::new(PgApiKeyRepo::new(db.clone())),
+        email_tokens: Arc::new(PgEmailTokenRepo::new(db.clone())),
+        todos: Arc::new(PgTodoRepo::new(db.clone())),
+        sessions: Arc::new(PgSessionRepo::new(db.clone())),
+        login_attempts: Arc::new(PgLoginAttemptRepo::new(db.clone())),
+        oidc: Arc::new(PgOidcRepo::new(db.clone())),
+        outbox: Arc::new(PgOutboxRepo::new(db.clone())),
+        jobs: Arc::new(PgJobRepo::new(db.clone())),
+        idempotency: Arc::new(PgIdempotencyStore::new(db.clone())),
+    }
+}
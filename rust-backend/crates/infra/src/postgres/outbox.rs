@@ -0,0 +1,133 @@
+use app_core::{OutboxEvent, OutboxRepo, OutboxStats, RepoError, RepoResult, TodoEvent};
+use sqlx::{PgConnection, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::db_err;
+
+pub struct PgOutboxRepo { pool: PgPool }
+
+impl PgOutboxRepo {
+    pub fn new(pool: PgPool) -> Self { Self { pool } }
+}
+
+/// 由仓储的写操作在自己的事务里调用
+pub(super) async fn insert_event(conn: &mut PgConnection, event: &TodoEvent) -> RepoResult<()> {
+    let todo = event.todo();
+    sqlx::query(
+        r#"insert into outbox (id, aggregate_type, aggregate_id, user_id, event_type, payload, created_at)
+           values ($1, $2, $3, $4, $5, $6::jsonb, $7)"#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(TodoEvent::AGGREGATE)
+    .bind(todo.id)
+    .bind(todo.user_id)
+    .bind(event.event_type())
+    .bind(event.payload().to_string())
+    .bind(OffsetDateTime::now_utc())
+    .execute(conn)
+    .await
+    .map_err(db_err)?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct OutboxRow {
+    id: Uuid,
+    aggregate_type: String,
+    aggregate_id: Uuid,
+    user_id: Uuid,
+    event_type: String,
+    payload: String,
+    attempts: i32,
+    created_at: OffsetDateTime,
+}
+
+impl TryFrom<OutboxRow> for OutboxEvent {
+    type Error = RepoError;
+
+    fn try_from(r: OutboxRow) -> RepoResult<Self> {
+        Ok(OutboxEvent {
+            id: r.id,
+            aggregate_type: r.aggregate_type,
+            aggregate_id: r.aggregate_id,
+            user_id: r.user_id,
+            event_type: r.event_type,
+            payload: serde_json::from_str(&r.payload).map_err(|e| RepoError::Db(Box::new(e)))?,
+            attempts: r.attempts,
+            created_at: r.created_at,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl OutboxRepo for PgOutboxRepo {
+    async fn claim(&self, limit: u32, lease: time::Duration) -> RepoResult<Vec<OutboxEvent>> {
+        // skip locked：并发的 dispatcher 跳过别人正在领取的行，而不是排队等锁
+        let mut rows: Vec<OutboxRow> = sqlx::query_as(
+            r#"update outbox set attempts = attempts + 1, next_attempt_at = now() + $2 * interval '1 second'
+               where id in (
+                 select id from outbox where status = 'pending' and next_attempt_at <= now()
+                 order by created_at, id limit $1
+                 for update skip locked)
+               returning id, aggregate_type, aggregate_id, user_id, event_type, payload::text as payload, attempts, created_at"#,
+        )
+        .bind(i64::from(limit))
+        .bind(lease.whole_seconds())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        // returning 不保证顺序
+        rows.sort_by_key(|r| (r.created_at, r.id));
+        rows.into_iter().map(OutboxEvent::try_from).collect()
+    }
+
+    async fn mark_dispatched(&self, id: Uuid) -> RepoResult<()> {
+        sqlx::query("update outbox set status = 'dispatched', dispatched_at = now(), last_error = null where id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn retry_later(&self, id: Uuid, error: &str, retry_at: OffsetDateTime) -> RepoResult<()> {
+        sqlx::query("update outbox set next_attempt_at = $2, last_error = $3 where id = $1")
+            .bind(id)
+            .bind(retry_at)
+            .bind(error)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn mark_dead(&self, id: Uuid, error: &str) -> RepoResult<()> {
+        sqlx::query("update outbox set status = 'dead', last_error = $2 where id = $1")
+            .bind(id)
+            .bind(error)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn stats(&self) -> RepoResult<OutboxStats> {
+        let (pending, dead): (i64, i64) = sqlx::query_as(
+            r#"select count(*) filter (where status = 'pending'), count(*) filter (where status = 'dead') from outbox"#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(OutboxStats { pending, dead })
+    }
+
+    async fn purge_dispatched(&self, before: OffsetDateTime) -> RepoResult<u64> {
+        let res = sqlx::query("delete from outbox where status = 'dispatched' and dispatched_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
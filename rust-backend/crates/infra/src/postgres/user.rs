@@ -0,0 +1,145 @@
+use app_core::{RepoError, RepoResult, UserAccess, UserCursor, UserListQuery, UserPage, UserRepo, UserSummary, DEFAULT_ROLE};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{conflict_or_db, db_err, escape_like};
+
+pub struct PgUserRepo { pool: PgPool }
+
+impl PgUserRepo {
+    pub fn new(pool: PgPool) -> Self { Self { pool } }
+}
+
+/// 角色用子查询聚合成数组，一次查询拿到整行
+const SUMMARY_COLUMNS: &str =
+    "u.id, u.email, array(select role from user_roles where user_id = u.id order by role) as roles, u.disabled_at, u.created_at";
+
+#[derive(sqlx::FromRow)]
+struct SummaryRow {
+    id: Uuid,
+    email: String,
+    roles: Vec<String>,
+    disabled_at: Option<OffsetDateTime>,
+    created_at: OffsetDateTime,
+}
+
+impl From<SummaryRow> for UserSummary {
+    fn from(r: SummaryRow) -> Self {
+        UserSummary { id: r.id, email: r.email, roles: r.roles, disabled_at: r.disabled_at, created_at: r.created_at }
+    }
+}
+
+#[async_trait::async_trait]
+impl UserRepo for PgUserRepo {
+    async fn create(&self, email: &str, password_hash: &str) -> RepoResult<Uuid> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        sqlx::query("insert into users (id, email, password_hash) values ($1, $2, $3)")
+            .bind(id)
+            .bind(email)
+            .bind(password_hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(conflict_or_db("email_taken"))?;
+        sqlx::query("insert into user_roles (user_id, role) values ($1, $2)")
+            .bind(id)
+            .bind(DEFAULT_ROLE)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        tx.commit().await.map_err(db_err)?;
+        Ok(id)
+    }
+
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<(Uuid, String)>> {
+        sqlx::query_as("select id, password_hash from users where email = $1")
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_err)
+    }
+
+    async fn set_password_hash(&self, id: Uuid, password_hash: &str) -> RepoResult<()> {
+        sqlx::query("update users set password_hash = $2 where id = $1")
+            .bind(id)
+            .bind(password_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn access(&self, id: Uuid) -> RepoResult<Option<UserAccess>> {
+        let row: Option<(String, bool, i64, bool, Vec<String>, Vec<String>)> = sqlx::query_as(
+            r#"select u.email, u.disabled_at is not null, u.session_epoch, u.email_verified_at is not null,
+                      array(select role from user_roles where user_id = u.id order by role),
+                      array(select distinct rp.permission from user_roles ur
+                            join role_permissions rp on rp.role = ur.role
+                            where ur.user_id = u.id order by rp.permission)
+               from users u where u.id = $1"#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(row.map(|(email, disabled, session_epoch, email_verified, roles, permissions)| UserAccess {
+            email,
+            roles,
+            permissions,
+            session_epoch,
+            disabled,
+            email_verified,
+        }))
+    }
+
+    async fn mark_email_verified(&self, id: Uuid) -> RepoResult<()> {
+        sqlx::query("update users set email_verified_at = coalesce(email_verified_at, now()) where id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn grant_role(&self, id: Uuid, role: &str) -> RepoResult<()> {
+        sqlx::query("insert into user_roles (user_id, role) values ($1, $2) on conflict do nothing")
+            .bind(id)
+            .bind(role)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn list(&self, query: &UserListQuery) -> RepoResult<UserPage> {
+        let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(format!("select {SUMMARY_COLUMNS} from users u where true"));
+        if let Some(q) = query.q.as_deref().filter(|q| !q.is_empty()) {
+            qb.push(" and u.email ilike ").push_bind(format!("%{}%", escape_like(q))).push(r" escape '\'");
+        }
+        if let Some(after) = query.after {
+            qb.push(" and (u.created_at, u.id) < (").push_bind(after.created_at).push(", ").push_bind(after.id).push(")");
+        }
+        qb.push(" order by u.created_at desc, u.id desc limit ").push_bind(i64::from(query.limit) + 1);
+
+        let mut rows: Vec<SummaryRow> = qb.build_query_as().fetch_all(&self.pool).await.map_err(db_err)?;
+        let has_more = rows.len() > query.limit as usize;
+        rows.truncate(query.limit as usize);
+        let next = if has_more { rows.last().map(|r| UserCursor { created_at: r.created_at, id: r.id }) } else { None };
+        Ok(UserPage { items: rows.into_iter().map(UserSummary::from).collect(), next })
+    }
+
+    async fn set_disabled(&self, id: Uuid, disabled: bool) -> RepoResult<UserSummary> {
+        // 已停用的账号再次停用时保留最初的停用时间
+        let row: Option<SummaryRow> = sqlx::query_as(&format!(
+            r#"update users u set disabled_at = case when $2 then coalesce(u.disabled_at, now()) end
+               where u.id = $1 returning {SUMMARY_COLUMNS}"#
+        ))
+        .bind(id)
+        .bind(disabled)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        row.map(UserSummary::from).ok_or(RepoError::NotFound("user_not_found"))
+    }
+}
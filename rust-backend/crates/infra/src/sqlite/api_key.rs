@@ -0,0 +1,126 @@
+use app_core::{ApiKey, ApiKeyRepo, NewApiKey, RepoError, RepoResult};
+use sqlx::SqlitePool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{from_micros, micros, now_micros};
+use crate::{conflict_or_db, db_err};
+
+pub struct SqliteApiKeyRepo { pool: SqlitePool }
+
+impl SqliteApiKeyRepo {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+const COLUMNS: &str = "id, user_id, name, prefix, scopes, expires_at, last_used_at, last_used_ip, created_at";
+
+/// 同一 IP 在这段时间内的重复使用不更新 `last_used_*`
+const TOUCH_INTERVAL_MICROS: i64 = 60 * 1_000_000;
+
+#[derive(sqlx::FromRow)]
+struct ApiKeyRow {
+    id: Uuid,
+    user_id: Uuid,
+    name: String,
+    prefix: String,
+    /// 空格分隔
+    scopes: String,
+    expires_at: i64,
+    last_used_at: Option<i64>,
+    last_used_ip: Option<String>,
+    created_at: i64,
+}
+
+impl From<ApiKeyRow> for ApiKey {
+    fn from(r: ApiKeyRow) -> Self {
+        ApiKey {
+            id: r.id,
+            user_id: r.user_id,
+            name: r.name,
+            prefix: r.prefix,
+            scopes: r.scopes.split_whitespace().map(str::to_string).collect(),
+            expires_at: from_micros(r.expires_at),
+            last_used_at: r.last_used_at.map(from_micros),
+            last_used_ip: r.last_used_ip,
+            created_at: from_micros(r.created_at),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyRepo for SqliteApiKeyRepo {
+    async fn create(&self, user_id: Uuid, key: &NewApiKey<'_>) -> RepoResult<ApiKey> {
+        let row: ApiKeyRow = sqlx::query_as(&format!(
+            r#"insert into api_keys (id, user_id, name, prefix, token_hash, scopes, expires_at, created_at)
+               values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) returning {COLUMNS}"#
+        ))
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(key.name)
+        .bind(key.prefix)
+        .bind(key.token_hash)
+        .bind(key.scopes.join(" "))
+        .bind(micros(key.expires_at))
+        .bind(now_micros())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(conflict_or_db("api_key_name_taken"))?;
+        Ok(row.into())
+    }
+
+    async fn list(&self, user_id: Uuid) -> RepoResult<Vec<ApiKey>> {
+        let rows: Vec<ApiKeyRow> =
+            sqlx::query_as(&format!("select {COLUMNS} from api_keys where user_id = ?1 order by created_at desc, id desc"))
+                .bind(user_id)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(db_err)?;
+        Ok(rows.into_iter().map(ApiKey::from).collect())
+    }
+
+    async fn revoke(&self, user_id: Uuid, id: Uuid) -> RepoResult<()> {
+        let res = sqlx::query("delete from api_keys where id = ?1 and user_id = ?2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        if res.rows_affected() == 0 {
+            return Err(RepoError::NotFound("api_key_not_found"));
+        }
+        Ok(())
+    }
+
+    async fn authenticate(&self, token_hash: &str, ip: Option<&str>) -> RepoResult<Option<ApiKey>> {
+        let now = now_micros();
+        let row: Option<ApiKeyRow> =
+            sqlx::query_as(&format!("select {COLUMNS} from api_keys where token_hash = ?1 and expires_at > ?2"))
+                .bind(token_hash)
+                .bind(now)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(db_err)?;
+        let Some(row) = row else { return Ok(None) };
+        sqlx::query(
+            r#"update api_keys set last_used_at = ?2, last_used_ip = coalesce(?3, last_used_ip)
+               where id = ?1 and (last_used_at is null or last_used_at < ?4 or (?3 is not null and last_used_ip is not ?3))"#,
+        )
+        .bind(row.id)
+        .bind(now)
+        .bind(ip)
+        .bind(now - TOUCH_INTERVAL_MICROS)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(Some(row.into()))
+    }
+
+    async fn purge_expired(&self, before: OffsetDateTime) -> RepoResult<u64> {
+        let res = sqlx::query("delete from api_keys where expires_at <= ?1")
+            .bind(micros(before))
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
@@ -0,0 +1,102 @@
+use app_core::{AuditCursor, AuditEvent, AuditOutcome, AuditPage, AuditQuery, AuditRepo, RepoError, RepoResult};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+use super::{from_micros, micros};
+use crate::db_err;
+
+pub struct SqliteAuditRepo { pool: SqlitePool }
+
+impl SqliteAuditRepo {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    id: Uuid,
+    occurred_at: i64,
+    actor: Option<Uuid>,
+    action: String,
+    target: Option<String>,
+    outcome: String,
+    reason: Option<String>,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    request_id: Option<String>,
+}
+
+impl TryFrom<AuditRow> for AuditEvent {
+    type Error = RepoError;
+
+    fn try_from(r: AuditRow) -> RepoResult<Self> {
+        let outcome = AuditOutcome::parse(&r.outcome)
+            .ok_or_else(|| RepoError::Db(format!("unknown audit outcome {:?}", r.outcome).into()))?;
+        Ok(AuditEvent {
+            id: r.id,
+            occurred_at: from_micros(r.occurred_at),
+            actor: r.actor,
+            action: r.action,
+            target: r.target,
+            outcome,
+            reason: r.reason,
+            ip: r.ip,
+            user_agent: r.user_agent,
+            request_id: r.request_id,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditRepo for SqliteAuditRepo {
+    async fn append(&self, e: &AuditEvent) -> RepoResult<()> {
+        sqlx::query(
+            r#"insert into audit_events (id, occurred_at, actor, action, target, outcome, reason, ip, user_agent, request_id)
+               values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"#,
+        )
+        .bind(e.id)
+        .bind(micros(e.occurred_at))
+        .bind(e.actor)
+        .bind(&e.action)
+        .bind(&e.target)
+        .bind(e.outcome.as_str())
+        .bind(&e.reason)
+        .bind(&e.ip)
+        .bind(&e.user_agent)
+        .bind(&e.request_id)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn list(&self, query: &AuditQuery) -> RepoResult<AuditPage> {
+        let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "select id, occurred_at, actor, action, target, outcome, reason, ip, user_agent, request_id from audit_events where true",
+        );
+        if let Some(from) = query.from {
+            qb.push(" and occurred_at >= ").push_bind(micros(from));
+        }
+        if let Some(to) = query.to {
+            qb.push(" and occurred_at < ").push_bind(micros(to));
+        }
+        if let Some(actor) = query.actor {
+            qb.push(" and actor = ").push_bind(actor);
+        }
+        if let Some(action) = &query.action {
+            qb.push(" and action = ").push_bind(action.clone());
+        }
+        if let Some(outcome) = query.outcome {
+            qb.push(" and outcome = ").push_bind(outcome.as_str());
+        }
+        if let Some(before) = query.before {
+            qb.push(" and (occurred_at, id) < (").push_bind(micros(before.occurred_at)).push(", ").push_bind(before.id).push(")");
+        }
+        qb.push(" order by occurred_at desc, id desc limit ").push_bind(i64::from(query.limit) + 1);
+
+        let mut rows: Vec<AuditRow> = qb.build_query_as().fetch_all(&self.pool).await.map_err(db_err)?;
+        let has_more = rows.len() > query.limit as usize;
+        rows.truncate(query.limit as usize);
+        let next = if has_more { rows.last().map(|r| AuditCursor { occurred_at: from_micros(r.occurred_at), id: r.id }) } else { None };
+        Ok(AuditPage { items: rows.into_iter().map(AuditEvent::try_from).collect::<RepoResult<_>>()?, next })
+    }
+}
@@ -0,0 +1,63 @@
+use app_core::{EmailTokenPurpose, EmailTokenRepo, RepoResult};
+use sqlx::SqlitePool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{micros, now_micros};
+use crate::db_err;
+
+pub struct SqliteEmailTokenRepo { pool: SqlitePool }
+
+impl SqliteEmailTokenRepo {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+#[async_trait::async_trait]
+impl EmailTokenRepo for SqliteEmailTokenRepo {
+    async fn issue(&self, id: Uuid, user_id: Uuid, purpose: EmailTokenPurpose, expires_at: OffsetDateTime) -> RepoResult<()> {
+        let now = now_micros();
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        sqlx::query("update email_tokens set used_at = ?3 where user_id = ?1 and purpose = ?2 and used_at is null")
+            .bind(user_id)
+            .bind(purpose.as_str())
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        sqlx::query("insert into email_tokens (id, user_id, purpose, expires_at, created_at) values (?1, ?2, ?3, ?4, ?5)")
+            .bind(id)
+            .bind(user_id)
+            .bind(purpose.as_str())
+            .bind(micros(expires_at))
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        tx.commit().await.map_err(db_err)
+    }
+
+    async fn consume(&self, id: Uuid, purpose: EmailTokenPurpose) -> RepoResult<Option<Uuid>> {
+        let now = now_micros();
+        let row: Option<(Uuid,)> = sqlx::query_as(
+            r#"update email_tokens set used_at = ?3
+               where id = ?1 and purpose = ?2 and used_at is null and expires_at > ?3
+               returning user_id"#,
+        )
+        .bind(id)
+        .bind(purpose.as_str())
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(row.map(|(user_id,)| user_id))
+    }
+
+    async fn purge_expired(&self) -> RepoResult<u64> {
+        let res = sqlx::query("delete from email_tokens where expires_at <= ?1")
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
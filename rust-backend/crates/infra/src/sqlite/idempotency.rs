@@ -0,0 +1,114 @@
+use app_core::{IdempotencyBegin, IdempotencyRecord, IdempotencyStore, RepoError, RepoResult, StoredResponse};
+use sqlx::SqlitePool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{micros, now_micros};
+use crate::db_err;
+
+pub struct SqliteIdempotencyStore { pool: SqlitePool }
+
+impl SqliteIdempotencyStore {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+#[derive(sqlx::FromRow)]
+struct KeyRow {
+    fingerprint: String,
+    status: Option<i64>,
+    headers: Option<String>,
+    body: Option<Vec<u8>>,
+}
+
+impl TryFrom<KeyRow> for IdempotencyRecord {
+    type Error = RepoError;
+
+    fn try_from(r: KeyRow) -> RepoResult<Self> {
+        let response = match r.status {
+            Some(status) => Some(StoredResponse {
+                status: status as u16,
+                headers: serde_json::from_str(r.headers.as_deref().unwrap_or("[]")).map_err(|e| RepoError::Db(Box::new(e)))?,
+                body: r.body.unwrap_or_default(),
+            }),
+            None => None,
+        };
+        Ok(IdempotencyRecord { fingerprint: r.fingerprint, response })
+    }
+}
+
+#[async_trait::async_trait]
+impl IdempotencyStore for SqliteIdempotencyStore {
+    async fn begin(&self, user_id: Uuid, key: &str, fingerprint: &str, locked_until: OffsetDateTime) -> RepoResult<IdempotencyBegin> {
+        // 新键直接插入；已过期的键就地覆盖；未过期的键不动，再读出来交给调用方判断
+        let claimed = sqlx::query(
+            r#"insert into idempotency_keys (user_id, key, fingerprint, expires_at, created_at) values (?1, ?2, ?3, ?4, ?5)
+               on conflict (user_id, key) do update
+                 set fingerprint = excluded.fingerprint, status = null, headers = null, body = null,
+                     expires_at = excluded.expires_at, created_at = excluded.created_at
+                 where idempotency_keys.expires_at <= ?5
+               returning 1"#,
+        )
+        .bind(user_id)
+        .bind(key)
+        .bind(fingerprint)
+        .bind(micros(locked_until))
+        .bind(now_micros())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        if claimed.is_some() {
+            return Ok(IdempotencyBegin::Started);
+        }
+        let row: Option<KeyRow> = sqlx::query_as(
+            "select fingerprint, status, headers, body from idempotency_keys where user_id = ?1 and key = ?2",
+        )
+        .bind(user_id)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        // 两条语句之间被清理掉了：按执行中处理，客户端稍后重试即可
+        let record = match row {
+            Some(row) => row.try_into()?,
+            None => IdempotencyRecord { fingerprint: fingerprint.to_string(), response: None },
+        };
+        Ok(IdempotencyBegin::Existing(record))
+    }
+
+    async fn complete(&self, user_id: Uuid, key: &str, response: &StoredResponse, expires_at: OffsetDateTime) -> RepoResult<()> {
+        let headers = serde_json::to_string(&response.headers).map_err(|e| RepoError::Db(Box::new(e)))?;
+        sqlx::query(
+            r#"update idempotency_keys set status = ?3, headers = ?4, body = ?5, expires_at = ?6
+               where user_id = ?1 and key = ?2"#,
+        )
+        .bind(user_id)
+        .bind(key)
+        .bind(i64::from(response.status))
+        .bind(headers)
+        .bind(&response.body)
+        .bind(micros(expires_at))
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn release(&self, user_id: Uuid, key: &str) -> RepoResult<()> {
+        sqlx::query("delete from idempotency_keys where user_id = ?1 and key = ?2 and status is null")
+            .bind(user_id)
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> RepoResult<u64> {
+        let res = sqlx::query("delete from idempotency_keys where expires_at <= ?1")
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
@@ -0,0 +1,70 @@
+use app_core::{LoginAttemptRepo, RepoResult};
+use sqlx::SqlitePool;
+use time::{Duration, OffsetDateTime};
+
+use super::{from_micros, micros, now_micros};
+use crate::db_err;
+
+pub struct SqliteLoginAttemptRepo { pool: SqlitePool }
+
+impl SqliteLoginAttemptRepo {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+#[async_trait::async_trait]
+impl LoginAttemptRepo for SqliteLoginAttemptRepo {
+    async fn locked_until(&self, key: &str) -> RepoResult<Option<OffsetDateTime>> {
+        let row: Option<(Option<i64>,)> = sqlx::query_as("select locked_until from login_attempts where key = ?1")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(row.and_then(|(until,)| until).map(from_micros))
+    }
+
+    async fn record_failure(&self, key: &str, window: Duration) -> RepoResult<u32> {
+        let now = now_micros();
+        let (failures,): (i32,) = sqlx::query_as(
+            r#"insert into login_attempts (key, failures, last_failure_at) values (?1, 1, ?2)
+               on conflict (key) do update set
+                 failures = case when login_attempts.last_failure_at < ?3
+                                 then 1 else login_attempts.failures + 1 end,
+                 last_failure_at = ?2
+               returning failures"#,
+        )
+        .bind(key)
+        .bind(now)
+        .bind(now - window.whole_microseconds() as i64)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(failures as u32)
+    }
+
+    async fn lock(&self, key: &str, until: OffsetDateTime) -> RepoResult<()> {
+        sqlx::query("update login_attempts set locked_until = ?2 where key = ?1")
+            .bind(key)
+            .bind(micros(until))
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> RepoResult<()> {
+        sqlx::query("delete from login_attempts where key = ?1").bind(key).execute(&self.pool).await.map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn purge_expired(&self, before: OffsetDateTime) -> RepoResult<u64> {
+        let res = sqlx::query(
+            "delete from login_attempts where last_failure_at < ?1 and (locked_until is null or locked_until < ?2)",
+        )
+        .bind(micros(before))
+        .bind(now_micros())
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
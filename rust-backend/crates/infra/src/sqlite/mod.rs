@@ -0,0 +1,107 @@
+//! SQLite 后端：本地开发与测试用，`sqlite::memory:` 不需要任何外部服务。
+//!
+//! 时间列存 Unix 微秒整数（与 timestamptz 精度一致）：文本时间戳按字典序比较并不总是等于
+//! 时间先后，整数则没有这个问题。`now` 一律由应用传入，不依赖 SQL 函数。
+
+mod api_key;
+mod audit;
+mod email_token;
+mod idempotency;
+mod job;
+mod login_attempt;
+mod oidc;
+mod outbox;
+mod session;
+mod todo;
+mod user;
+
+pub use api_key::SqliteApiKeyRepo;
+pub use audit::SqliteAuditRepo;
+pub use email_token::SqliteEmailTokenRepo;
+pub use idempotency::SqliteIdempotencyStore;
+pub use job::SqliteJobRepo;
+pub use login_attempt::SqliteLoginAttemptRepo;
+pub use oidc::SqliteOidcRepo;
+pub use outbox::SqliteOutboxRepo;
+pub use session::SqliteSessionRepo;
+pub use todo::SqliteTodoRepo;
+pub use user::SqliteUserRepo;
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use time::OffsetDateTime;
+
+use crate::{MigrationStatus, Repos};
+
+pub type Db = SqlitePool;
+
+fn pool_options(url: &str) -> sqlx::Result<(SqlitePoolOptions, SqliteConnectOptions)> {
+    let opts = SqliteConnectOptions::from_str(url)?.create_if_missing(true).foreign_keys(true);
+    let pool = SqlitePoolOptions::new();
+    // 内存库只活在单个连接里：固定一个连接且永不回收，否则每个连接看到的都是空库
+    if url.contains(":memory:") || url.contains("mode=memory") {
+        return Ok((pool.max_connections(1).idle_timeout(None).max_lifetime(None), opts));
+    }
+    Ok((pool, opts.journal_mode(SqliteJournalMode::Wal)))
+}
+
+pub async fn connect(url: &str) -> sqlx::Result<Db> {
+    let (pool, opts) = pool_options(url)?;
+    pool.connect_with(opts).await
+}
+
+/// 不立即建连，第一次查询时才连接；测试里组装不访问数据库的路由用
+pub fn connect_lazy(url: &str) -> sqlx::Result<Db> {
+    let (pool, opts) = pool_options(url)?;
+    Ok(pool.connect_lazy_with(opts))
+}
+
+/// migrations/sqlite 在编译期嵌入二进制，版本号与 Postgres 一侧一一对应
+static MIGRATOR: Migrator = sqlx::migrate!("migrations/sqlite");
+
+pub async fn migrate(db: &Db) -> Result<(), MigrateError> {
+    MIGRATOR.run(db).await
+}
+
+pub async fn ping(db: &Db) -> sqlx::Result<()> {
+    sqlx::query("select 1").execute(db).await.map(|_| ())
+}
+
+pub async fn migration_status(db: &Db) -> sqlx::Result<MigrationStatus> {
+    let (applied,): (Option<i64>,) = sqlx::query_as("select max(version) from _sqlx_migrations where success")
+        .fetch_one(db)
+        .await?;
+    Ok(MigrationStatus { applied, expected: MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0) })
+}
+
+pub fn repos(db: &Db) -> Repos {
+    Repos {
+        users: Arc::new(SqliteUserRepo::new(db.clone())),
+        audit: Arc::new(SqliteAuditRepo::new(db.clone())),
+        api_keys: Arc::new(SqliteApiKeyRepo::new(db.clone())),
+        email_tokens: Arc::new(SqliteEmailTokenRepo::new(db.clone())),
+        todos: Arc::new(SqliteTodoRepo::new(db.clone())),
+        sessions: Arc::new(SqliteSessionRepo::new(db.clone())),
+        login_attempts: Arc::new(SqliteLoginAttemptRepo::new(db.clone())),
+        oidc: Arc::new(SqliteOidcRepo::new(db.clone())),
+        outbox: Arc::new(SqliteOutboxRepo::new(db.clone())),
+        jobs: Arc::new(SqliteJobRepo::new(db.clone())),
+        idempotency: Arc::new(SqliteIdempotencyStore::new(db.clone())),
+    }
+}
+
+fn micros(t: OffsetDateTime) -> i64 {
+    (t.unix_timestamp_nanos() / 1_000) as i64
+}
+
+fn from_micros(us: i64) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(us) * 1_000).expect("stored timestamps are in range")
+}
+
+fn now_micros() -> i64 {
+    micros(OffsetDateTime::now_utc())
+}
@@ -0,0 +1,78 @@
+use app_core::{OidcLogin, OidcRepo, RepoResult};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::{from_micros, micros, now_micros};
+use crate::{conflict_or_db, db_err};
+
+pub struct SqliteOidcRepo { pool: SqlitePool }
+
+impl SqliteOidcRepo {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+#[async_trait::async_trait]
+impl OidcRepo for SqliteOidcRepo {
+    async fn begin_login(&self, login: &OidcLogin) -> RepoResult<()> {
+        sqlx::query("insert into oidc_logins (state, code_verifier, nonce, expires_at, created_at) values (?1, ?2, ?3, ?4, ?5)")
+            .bind(&login.state)
+            .bind(&login.code_verifier)
+            .bind(&login.nonce)
+            .bind(micros(login.expires_at))
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn take_login(&self, state: &str) -> RepoResult<Option<OidcLogin>> {
+        let row: Option<(String, String, String, i64)> = sqlx::query_as(
+            r#"delete from oidc_logins where state = ?1 and expires_at > ?2
+               returning state, code_verifier, nonce, expires_at"#,
+        )
+        .bind(state)
+        .bind(now_micros())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(row.map(|(state, code_verifier, nonce, expires_at)| OidcLogin {
+            state,
+            code_verifier,
+            nonce,
+            expires_at: from_micros(expires_at),
+        }))
+    }
+
+    async fn find_identity(&self, issuer: &str, subject: &str) -> RepoResult<Option<Uuid>> {
+        let row: Option<(Uuid,)> = sqlx::query_as("select user_id from user_identities where issuer = ?1 and subject = ?2")
+            .bind(issuer)
+            .bind(subject)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(row.map(|(user_id,)| user_id))
+    }
+
+    async fn link_identity(&self, user_id: Uuid, issuer: &str, subject: &str, email: &str) -> RepoResult<()> {
+        sqlx::query("insert into user_identities (issuer, subject, user_id, email, created_at) values (?1, ?2, ?3, ?4, ?5)")
+            .bind(issuer)
+            .bind(subject)
+            .bind(user_id)
+            .bind(email)
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(conflict_or_db("identity_linked"))?;
+        Ok(())
+    }
+
+    async fn purge_expired_logins(&self) -> RepoResult<u64> {
+        let res = sqlx::query("delete from oidc_logins where expires_at <= ?1")
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
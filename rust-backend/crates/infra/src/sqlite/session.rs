@@ -0,0 +1,177 @@
+use app_core::{RefreshOutcome, RepoError, RepoResult, SessionRepo};
+use sqlx::SqlitePool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{micros, now_micros};
+use crate::db_err;
+
+pub struct SqliteSessionRepo { pool: SqlitePool }
+
+impl SqliteSessionRepo {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+#[derive(sqlx::FromRow)]
+struct RefreshRow {
+    id: Uuid,
+    user_id: Uuid,
+    family_id: Uuid,
+    expires_at: i64,
+    used_at: Option<i64>,
+    revoked_at: Option<i64>,
+}
+
+#[async_trait::async_trait]
+impl SessionRepo for SqliteSessionRepo {
+    async fn create_refresh(&self, user_id: Uuid, token_hash: &str, expires_at: OffsetDateTime) -> RepoResult<()> {
+        sqlx::query(
+            r#"insert into refresh_tokens (id, user_id, family_id, token_hash, expires_at, created_at)
+               values (?1, ?2, ?3, ?4, ?5, ?6)"#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(Uuid::new_v4())
+        .bind(token_hash)
+        .bind(micros(expires_at))
+        .bind(now_micros())
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn rotate_refresh(&self, token_hash: &str, new_hash: &str, expires_at: OffsetDateTime) -> RepoResult<RefreshOutcome> {
+        let now = now_micros();
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        let row: Option<RefreshRow> = sqlx::query_as(
+            r#"select id, user_id, family_id, expires_at, used_at, revoked_at
+               from refresh_tokens where token_hash = ?1"#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?;
+        let Some(row) = row else { return Ok(RefreshOutcome::Invalid) };
+        if row.revoked_at.is_some() || row.expires_at <= now {
+            return Ok(RefreshOutcome::Invalid);
+        }
+        // SQLite 没有行锁：用带 `used_at is null` 条件的更新抢占，没抢到的一方按重放处理
+        let claimed = match row.used_at {
+            Some(_) => false,
+            None => {
+                sqlx::query("update refresh_tokens set used_at = ?2 where id = ?1 and used_at is null")
+                    .bind(row.id)
+                    .bind(now)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(db_err)?
+                    .rows_affected()
+                    == 1
+            }
+        };
+        if !claimed {
+            sqlx::query("update refresh_tokens set revoked_at = ?2 where family_id = ?1 and revoked_at is null")
+                .bind(row.family_id)
+                .bind(now)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+            tx.commit().await.map_err(db_err)?;
+            return Ok(RefreshOutcome::Reused { user_id: row.user_id });
+        }
+        sqlx::query(
+            r#"insert into refresh_tokens (id, user_id, family_id, token_hash, expires_at, created_at)
+               values (?1, ?2, ?3, ?4, ?5, ?6)"#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(row.user_id)
+        .bind(row.family_id)
+        .bind(new_hash)
+        .bind(micros(expires_at))
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+        tx.commit().await.map_err(db_err)?;
+        Ok(RefreshOutcome::Rotated { user_id: row.user_id })
+    }
+
+    async fn revoke_family(&self, user_id: Uuid, token_hash: &str) -> RepoResult<()> {
+        sqlx::query(
+            r#"update refresh_tokens set revoked_at = ?3
+               where family_id = (select family_id from refresh_tokens where token_hash = ?1 and user_id = ?2)
+                 and revoked_at is null"#,
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(now_micros())
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn deny_jti(&self, jti: Uuid, expires_at: OffsetDateTime) -> RepoResult<()> {
+        sqlx::query("insert into revoked_jtis (jti, expires_at) values (?1, ?2) on conflict (jti) do nothing")
+            .bind(jti)
+            .bind(micros(expires_at))
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn is_revoked(&self, user_id: Uuid, jti: Uuid, session_epoch: i64) -> RepoResult<bool> {
+        // 每个请求都要查一次，合成一条语句；用户已删除同样视为吊销
+        let (valid,): (bool,) = sqlx::query_as(
+            r#"select not exists(select 1 from revoked_jtis where jti = ?2 and expires_at > ?4)
+                      and exists(select 1 from users where id = ?1 and disabled_at is null and session_epoch = ?3)"#,
+        )
+        .bind(user_id)
+        .bind(jti)
+        .bind(session_epoch)
+        .bind(now_micros())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(!valid)
+    }
+
+    async fn revoke_user(&self, user_id: Uuid) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        let res = sqlx::query("update users set session_epoch = session_epoch + 1 where id = ?1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        if res.rows_affected() == 0 {
+            return Err(RepoError::NotFound("user_not_found"));
+        }
+        sqlx::query("update refresh_tokens set revoked_at = ?2 where user_id = ?1 and revoked_at is null")
+            .bind(user_id)
+            .bind(now_micros())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        // 个人访问令牌同样是这个用户的凭据，吊销即删除
+        sqlx::query("delete from api_keys where user_id = ?1").bind(user_id).execute(&mut *tx).await.map_err(db_err)?;
+        tx.commit().await.map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> RepoResult<u64> {
+        let now = now_micros();
+        let jtis = sqlx::query("delete from revoked_jtis where expires_at <= ?1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        let tokens = sqlx::query("delete from refresh_tokens where expires_at <= ?1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(jtis.rows_affected() + tokens.rows_affected())
+    }
+}
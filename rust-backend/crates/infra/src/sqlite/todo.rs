@@ -0,0 +1,193 @@
+use app_core::{NewTodo, RepoError, RepoResult, Todo, TodoChanges, TodoCursor, TodoEvent, TodoPage, TodoQuery, TodoRepo, TodoSort};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{from_micros, micros, now_micros};
+use super::job::{cancel_reminder, sync_reminder};
+use super::outbox::insert_event;
+use crate::{conflict_or_db, db_err, escape_like};
+
+pub struct SqliteTodoRepo { pool: SqlitePool }
+
+impl SqliteTodoRepo {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+#[derive(sqlx::FromRow)]
+struct TodoRow {
+    id: Uuid,
+    user_id: Uuid,
+    title: String,
+    done: bool,
+    due_at: Option<i64>,
+    remind_at: Option<i64>,
+    recurrence: Option<String>,
+    created_at: i64,
+}
+
+impl TryFrom<TodoRow> for Todo {
+    type Error = RepoError;
+
+    fn try_from(r: TodoRow) -> RepoResult<Self> {
+        Ok(Todo {
+            id: r.id,
+            user_id: r.user_id,
+            title: r.title,
+            done: r.done,
+            due_at: r.due_at.map(from_micros),
+            remind_at: r.remind_at.map(from_micros),
+            recurrence: r.recurrence.map(|s| s.parse()).transpose().map_err(|e| RepoError::Db(Box::new(e)))?,
+            created_at: from_micros(r.created_at),
+        })
+    }
+}
+
+/// 新建一条并写入事件、登记提醒；创建接口与重复 todo 生成下一次共用
+async fn insert(conn: &mut SqliteConnection, user_id: Uuid, new: &NewTodo) -> RepoResult<Todo> {
+    let row: TodoRow = sqlx::query_as(
+        r#"insert into todos (id, user_id, title, done, due_at, remind_at, recurrence, created_at)
+           values (?1, ?2, ?3, false, ?4, ?5, ?6, ?7)
+           returning id, user_id, title, done, due_at, remind_at, recurrence, created_at"#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(&new.title)
+    .bind(new.due_at.map(micros))
+    .bind(new.remind_at.map(micros))
+    .bind(new.recurrence.as_ref().map(ToString::to_string))
+    .bind(now_micros())
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(conflict_or_db("todo_title_conflict"))?;
+    let todo = Todo::try_from(row)?;
+    insert_event(conn, &TodoEvent::Created(todo.clone())).await?;
+    if todo.reminder().is_some() {
+        sync_reminder(conn, &todo).await?;
+    }
+    Ok(todo)
+}
+
+#[async_trait::async_trait]
+impl TodoRepo for SqliteTodoRepo {
+    async fn create(&self, user_id: Uuid, new: &NewTodo) -> RepoResult<Todo> {
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        let todo = insert(&mut tx, user_id, new).await?;
+        tx.commit().await.map_err(db_err)?;
+        Ok(todo)
+    }
+
+    async fn get(&self, user_id: Uuid, id: Uuid) -> RepoResult<Option<Todo>> {
+        let row: Option<TodoRow> = sqlx::query_as(
+            r#"select id, user_id, title, done, due_at, remind_at, recurrence, created_at from todos
+               where id = ?1 and user_id = ?2"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        row.map(Todo::try_from).transpose()
+    }
+
+    async fn list_by_user(&self, user_id: Uuid, query: &TodoQuery) -> RepoResult<TodoPage> {
+        let mut qb: QueryBuilder<Sqlite> =
+            QueryBuilder::new("select id, user_id, title, done, due_at, remind_at, recurrence, created_at from todos where user_id = ");
+        qb.push_bind(user_id);
+        if let Some(done) = query.done {
+            qb.push(" and done = ").push_bind(done);
+        }
+        // SQLite 的 like 只对 ASCII 忽略大小写
+        if let Some(q) = query.q.as_deref().filter(|q| !q.is_empty()) {
+            qb.push(" and title like ").push_bind(format!("%{}%", escape_like(q))).push(r" escape '\'");
+        }
+        let (cmp, order) = match query.sort {
+            TodoSort::CreatedDesc => ("<", "desc"),
+            TodoSort::CreatedAsc => (">", "asc"),
+        };
+        if let Some(after) = query.after {
+            qb.push(format!(" and (created_at, id) {cmp} ("))
+                .push_bind(micros(after.created_at))
+                .push(", ")
+                .push_bind(after.id)
+                .push(")");
+        }
+        qb.push(format!(" order by created_at {order}, id {order} limit "))
+            .push_bind(i64::from(query.limit) + 1);
+
+        let mut rows: Vec<TodoRow> = qb.build_query_as().fetch_all(&self.pool).await.map_err(db_err)?;
+        let has_more = rows.len() > query.limit as usize;
+        rows.truncate(query.limit as usize);
+        let next = if has_more {
+            rows.last().map(|r| TodoCursor { created_at: from_micros(r.created_at), id: r.id })
+        } else {
+            None
+        };
+        Ok(TodoPage { items: rows.into_iter().map(Todo::try_from).collect::<RepoResult<_>>()?, next })
+    }
+
+    async fn update(&self, user_id: Uuid, id: Uuid, changes: &TodoChanges) -> RepoResult<Todo> {
+        // 立即取得写锁：读旧值与写回之间不能插进别的写事务，否则同一次完成可能生成两个下一次
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await.map_err(db_err)?;
+        let before: Option<TodoRow> = sqlx::query_as(
+            r#"select id, user_id, title, done, due_at, remind_at, recurrence, created_at from todos
+               where id = ?1 and user_id = ?2"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?;
+        let before = Todo::try_from(before.ok_or(RepoError::NotFound("todo_not_found"))?)?;
+        let mut after = changes.apply(&before);
+        let next = if after.done && !before.done { after.complete_occurrence(OffsetDateTime::now_utc()) } else { None };
+        let row: TodoRow = sqlx::query_as(
+            r#"update todos set title = ?3, done = ?4, due_at = ?5, remind_at = ?6, recurrence = ?7
+               where id = ?1 and user_id = ?2
+               returning id, user_id, title, done, due_at, remind_at, recurrence, created_at"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(&after.title)
+        .bind(after.done)
+        .bind(after.due_at.map(micros))
+        .bind(after.remind_at.map(micros))
+        .bind(after.recurrence.as_ref().map(ToString::to_string))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(conflict_or_db("todo_title_conflict"))?;
+        let todo = Todo::try_from(row)?;
+        insert_event(&mut tx, &TodoEvent::Updated(todo.clone())).await?;
+        if todo.reminder_changed(&before) {
+            sync_reminder(&mut tx, &todo).await?;
+        }
+        if let Some(next) = next {
+            insert(&mut tx, user_id, &next).await?;
+        }
+        tx.commit().await.map_err(db_err)?;
+        Ok(todo)
+    }
+
+    async fn delete(&self, user_id: Uuid, id: Uuid) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        let row: Option<TodoRow> = sqlx::query_as(
+            r#"delete from todos where id = ?1 and user_id = ?2
+               returning id, user_id, title, done, due_at, remind_at, recurrence, created_at"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?;
+        let todo = Todo::try_from(row.ok_or(RepoError::NotFound("todo_not_found"))?)?;
+        cancel_reminder(&mut tx, todo.id).await?;
+        insert_event(&mut tx, &TodoEvent::Deleted(todo)).await?;
+        tx.commit().await.map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn purge_blank(&self) -> RepoResult<u64> {
+        let res = sqlx::query("delete from todos where title = ''").execute(&self.pool).await.map_err(db_err)?;
+        Ok(res.rows_affected())
+    }
+}
@@ -0,0 +1,179 @@
+use app_core::{RepoError, RepoResult, UserAccess, UserCursor, UserListQuery, UserPage, UserRepo, UserSummary, DEFAULT_ROLE};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+use super::{from_micros, micros, now_micros};
+use crate::{conflict_or_db, db_err, escape_like};
+
+pub struct SqliteUserRepo { pool: SqlitePool }
+
+impl SqliteUserRepo {
+    pub fn new(pool: SqlitePool) -> Self { Self { pool } }
+}
+
+/// SQLite 没有数组类型，角色用 group_concat 拼成逗号分隔的文本
+const SUMMARY_COLUMNS: &str =
+    "u.id, u.email, (select group_concat(role) from user_roles where user_id = u.id) as roles, u.disabled_at, u.created_at";
+
+#[derive(sqlx::FromRow)]
+struct SummaryRow {
+    id: Uuid,
+    email: String,
+    roles: Option<String>,
+    disabled_at: Option<i64>,
+    created_at: i64,
+}
+
+impl From<SummaryRow> for UserSummary {
+    fn from(r: SummaryRow) -> Self {
+        UserSummary {
+            id: r.id,
+            email: r.email,
+            roles: split_sorted(r.roles),
+            disabled_at: r.disabled_at.map(from_micros),
+            created_at: from_micros(r.created_at),
+        }
+    }
+}
+
+/// group_concat 不保证顺序，排序后与 Postgres 版一致
+fn split_sorted(joined: Option<String>) -> Vec<String> {
+    let mut items: Vec<String> = joined.iter().flat_map(|s| s.split(',')).map(str::to_string).collect();
+    items.sort_unstable();
+    items
+}
+
+#[async_trait::async_trait]
+impl UserRepo for SqliteUserRepo {
+    async fn create(&self, email: &str, password_hash: &str) -> RepoResult<Uuid> {
+        let id = Uuid::new_v4();
+        let now = now_micros();
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        sqlx::query("insert into users (id, email, password_hash, created_at) values (?1, ?2, ?3, ?4)")
+            .bind(id)
+            .bind(email)
+            .bind(password_hash)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(conflict_or_db("email_taken"))?;
+        sqlx::query("insert into user_roles (user_id, role, granted_at) values (?1, ?2, ?3)")
+            .bind(id)
+            .bind(DEFAULT_ROLE)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        tx.commit().await.map_err(db_err)?;
+        Ok(id)
+    }
+
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<(Uuid, String)>> {
+        sqlx::query_as("select id, password_hash from users where email = ?1")
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_err)
+    }
+
+    async fn set_password_hash(&self, id: Uuid, password_hash: &str) -> RepoResult<()> {
+        sqlx::query("update users set password_hash = ?2 where id = ?1")
+            .bind(id)
+            .bind(password_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn access(&self, id: Uuid) -> RepoResult<Option<UserAccess>> {
+        let row: Option<(String, bool, i64, bool, Option<String>, Option<String>)> = sqlx::query_as(
+            r#"select u.email, u.disabled_at is not null, u.session_epoch, u.email_verified_at is not null,
+                      (select group_concat(role) from user_roles where user_id = u.id),
+                      (select group_concat(distinct rp.permission) from user_roles ur
+                       join role_permissions rp on rp.role = ur.role
+                       where ur.user_id = u.id)
+               from users u where u.id = ?1"#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(row.map(|(email, disabled, session_epoch, email_verified, roles, permissions)| UserAccess {
+            email,
+            roles: split_sorted(roles),
+            permissions: split_sorted(permissions),
+            session_epoch,
+            disabled,
+            email_verified,
+        }))
+    }
+
+    async fn mark_email_verified(&self, id: Uuid) -> RepoResult<()> {
+        sqlx::query("update users set email_verified_at = coalesce(email_verified_at, ?2) where id = ?1")
+            .bind(id)
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn grant_role(&self, id: Uuid, role: &str) -> RepoResult<()> {
+        sqlx::query("insert or ignore into user_roles (user_id, role, granted_at) values (?1, ?2, ?3)")
+            .bind(id)
+            .bind(role)
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn list(&self, query: &UserListQuery) -> RepoResult<UserPage> {
+        let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(format!("select {SUMMARY_COLUMNS} from users u where true"));
+        // SQLite 的 like 只对 ASCII 忽略大小写
+        if let Some(q) = query.q.as_deref().filter(|q| !q.is_empty()) {
+            qb.push(" and u.email like ").push_bind(format!("%{}%", escape_like(q))).push(r" escape '\'");
+        }
+        if let Some(after) = query.after {
+            qb.push(" and (u.created_at, u.id) < (")
+                .push_bind(micros(after.created_at))
+                .push(", ")
+                .push_bind(after.id)
+                .push(")");
+        }
+        qb.push(" order by u.created_at desc, u.id desc limit ").push_bind(i64::from(query.limit) + 1);
+
+        let mut rows: Vec<SummaryRow> = qb.build_query_as().fetch_all(&self.pool).await.map_err(db_err)?;
+        let has_more = rows.len() > query.limit as usize;
+        rows.truncate(query.limit as usize);
+        let next = if has_more {
+            rows.last().map(|r| UserCursor { created_at: from_micros(r.created_at), id: r.id })
+        } else {
+            None
+        };
+        Ok(UserPage { items: rows.into_iter().map(UserSummary::from).collect(), next })
+    }
+
+    async fn set_disabled(&self, id: Uuid, disabled: bool) -> RepoResult<UserSummary> {
+        // 已停用的账号再次停用时保留最初的停用时间。SQLite 的 returning 里不能用表别名，
+        // 角色子查询放到随后的查询里
+        let res = sqlx::query("update users set disabled_at = case when ?2 then coalesce(disabled_at, ?3) end where id = ?1")
+            .bind(id)
+            .bind(disabled)
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        if res.rows_affected() == 0 {
+            return Err(RepoError::NotFound("user_not_found"));
+        }
+        let row: SummaryRow = sqlx::query_as(&format!("select {SUMMARY_COLUMNS} from users u where u.id = ?1"))
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(row.into())
+    }
+}
@@ -0,0 +1,6 @@
+// 用 protoc-bin-vendored 提供的 protoc 编译 proto，构建机不需要另装 protobuf
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::compile_protos("proto/todo/v1/todo.proto")?;
+    Ok(())
+}
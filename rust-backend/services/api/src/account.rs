@@ -0,0 +1,188 @@
+//! 邮箱验证与重置密码。邮件里的令牌是 `base64url(json).base64url(hmac)`，签名覆盖用途、令牌 ID 与过期时间；
+//! 库里的 `email_tokens` 只负责“用一次就作废”。重发与申请重置总是返回 202，不暴露邮箱是否注册过。
+
+use app_core::EmailTokenPurpose;
+use axum::extract::State;
+use axum::http::StatusCode;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use dto::{EmailReq, ErrorBody, PasswordResetConfirm, VerifyEmailReq};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::audit::AuditCtx;
+use crate::auth;
+use crate::error::{AppError, AppJson, AppResult};
+use crate::mailer::Email;
+use crate::state::AppState;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    /// 用途，见 `EmailTokenPurpose::as_str`；验证邮箱的令牌不能拿来重置密码
+    p: String,
+    id: Uuid,
+    /// 过期时间，Unix 秒
+    exp: i64,
+}
+
+fn mac(secret: &str, payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(payload);
+    mac
+}
+
+fn sign(secret: &str, purpose: EmailTokenPurpose, id: Uuid, exp: OffsetDateTime) -> String {
+    let payload = Payload { p: purpose.as_str().into(), id, exp: exp.unix_timestamp() };
+    let payload = serde_json::to_vec(&payload).expect("token payload serializes");
+    let sig = mac(secret, &payload).finalize().into_bytes();
+    format!("{}.{}", URL_SAFE_NO_PAD.encode(&payload), URL_SAFE_NO_PAD.encode(sig))
+}
+
+/// 校验签名、用途与过期时间，返回令牌 ID；任何不符都是 400 `invalid_email_token`
+fn verify(secret: &str, purpose: EmailTokenPurpose, raw: &str) -> AppResult<Uuid> {
+    let (payload, sig) = raw.split_once('.').ok_or_else(invalid_token)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid_token())?;
+    let sig = URL_SAFE_NO_PAD.decode(sig).map_err(|_| invalid_token())?;
+    mac(secret, &payload).verify_slice(&sig).map_err(|_| invalid_token())?;
+    let p: Payload = serde_json::from_slice(&payload).map_err(|_| invalid_token())?;
+    if p.p != purpose.as_str() || p.exp < OffsetDateTime::now_utc().unix_timestamp() {
+        return Err(invalid_token());
+    }
+    Ok(p.id)
+}
+
+fn invalid_token() -> AppError {
+    AppError::BadRequest { code: "invalid_email_token", message: "link is invalid, expired or already used".into() }
+}
+
+/// 签发令牌、入库并发信。发送失败只记日志和指标：注册、申请重置本身不因此失败，用户可以再点“重发”
+pub(crate) async fn send_link(st: &AppState, user_id: Uuid, email: &str, purpose: EmailTokenPurpose) -> AppResult<()> {
+    let account = &st.cfg.account;
+    let (ttl, page, subject) = match purpose {
+        EmailTokenPurpose::VerifyEmail => (Duration::hours(account.verify_ttl_hours), "verify-email", "Verify your email address"),
+        EmailTokenPurpose::ResetPassword => {
+            (Duration::minutes(account.reset_ttl_minutes), "reset-password", "Reset your password")
+        }
+    };
+    let id = Uuid::new_v4();
+    let exp = OffsetDateTime::now_utc() + ttl;
+    st.email_tokens.issue(id, user_id, purpose, exp).await?;
+    let link = format!("{}/{page}?token={}", account.public_url.trim_end_matches('/'), sign(&account.token_secret, purpose, id, exp));
+    let minutes = ttl.whole_minutes();
+    let body = format!("Open the link below within {minutes} minutes:\n\n{link}\n\nIf you did not request this, ignore this email.\n");
+    let mail = Email { to: email.to_string(), subject: subject.into(), body };
+    if let Err(e) = st.mailer.send(&mail).await {
+        tracing::warn!(err = ?e, %user_id, purpose = purpose.as_str(), "sending email failed");
+        metrics::counter!("mail_send_failures_total", "purpose" => purpose.as_str()).increment(1);
+    }
+    Ok(())
+}
+
+/// 验证后新签发的访问令牌才不再受 `[account] unverified` 限制，客户端应随即刷新令牌
+#[utoipa::path(post, path = "/api/v1/auth/verify-email", tag = "auth", request_body = VerifyEmailReq, responses(
+    (status = 204, description = "邮箱已验证"),
+    (status = 400, description = "`invalid_email_token`：链接无效、过期或已使用", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn verify_email(
+    State(st): State<AppState>,
+    audit: AuditCtx,
+    AppJson(req): AppJson<VerifyEmailReq>,
+) -> AppResult<StatusCode> {
+    let verified: AppResult<Uuid> = async {
+        let id = verify(&st.cfg.account.token_secret, EmailTokenPurpose::VerifyEmail, &req.token)?;
+        let user_id = st.email_tokens.consume(id, EmailTokenPurpose::VerifyEmail).await?.ok_or_else(invalid_token)?;
+        st.users.mark_email_verified(user_id).await?;
+        auth::grant_bootstrap_admin(&st, user_id).await?;
+        Ok(user_id)
+    }
+    .await;
+    let target = verified.as_ref().ok().map(Uuid::to_string);
+    let audit = match &verified {
+        Ok(user_id) => audit.with_actor(*user_id),
+        Err(_) => audit,
+    };
+    audit.record(&st, "account.verify_email", target.as_deref(), &verified).await;
+    verified?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 已验证或未注册的邮箱不发信，响应相同
+#[utoipa::path(post, path = "/api/v1/auth/verify-email/resend", tag = "auth", request_body = EmailReq, responses(
+    (status = 202, description = "如果邮箱已注册且未验证，验证邮件已发出"),
+    (status = 400, description = "`validation_failed`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn resend_verification(State(st): State<AppState>, AppJson(req): AppJson<EmailReq>) -> AppResult<StatusCode> {
+    req.validate()?;
+    if let Some((user_id, _)) = st.users.find_by_email(&req.email).await? {
+        let access = st.users.access(user_id).await?;
+        if access.is_some_and(|a| !a.email_verified && !a.disabled) {
+            send_link(&st, user_id, &req.email, EmailTokenPurpose::VerifyEmail).await?;
+        }
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// 只有最近一封重置邮件里的链接有效
+#[utoipa::path(post, path = "/api/v1/auth/password-reset", tag = "auth", request_body = EmailReq, responses(
+    (status = 202, description = "如果邮箱已注册，重置邮件已发出"),
+    (status = 400, description = "`validation_failed`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn request_password_reset(
+    State(st): State<AppState>,
+    audit: AuditCtx,
+    AppJson(req): AppJson<EmailReq>,
+) -> AppResult<StatusCode> {
+    req.validate()?;
+    if let Some((user_id, _)) = st.users.find_by_email(&req.email).await? {
+        if st.users.access(user_id).await?.is_some_and(|a| !a.disabled) {
+            let sent = send_link(&st, user_id, &req.email, EmailTokenPurpose::ResetPassword).await;
+            audit.record(&st, "account.password_reset_requested", Some(&user_id.to_string()), &sent).await;
+            sent?;
+        }
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// 设置新密码后吊销该用户的全部会话与个人访问令牌；能收到邮件也就证明了邮箱，顺带标记为已验证
+#[utoipa::path(post, path = "/api/v1/auth/password-reset/confirm", tag = "auth", request_body = PasswordResetConfirm, responses(
+    (status = 204, description = "密码已重置，需要重新登录"),
+    (status = 400, description = "`invalid_email_token`、`password_too_short`、`password_breached` 等", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn confirm_password_reset(
+    State(st): State<AppState>,
+    audit: AuditCtx,
+    AppJson(req): AppJson<PasswordResetConfirm>,
+) -> AppResult<StatusCode> {
+    let reset: AppResult<Uuid> = async {
+        let id = verify(&st.cfg.account.token_secret, EmailTokenPurpose::ResetPassword, &req.token)?;
+        // 先检查新密码，不合格时令牌还能再用
+        req.validate()?;
+        st.passwords.check_policy(&req.password)?;
+        let hash = st.passwords.hash(&req.password)?;
+        let user_id = st.email_tokens.consume(id, EmailTokenPurpose::ResetPassword).await?.ok_or_else(invalid_token)?;
+        st.users.set_password_hash(user_id, &hash).await?;
+        st.users.mark_email_verified(user_id).await?;
+        auth::grant_bootstrap_admin(&st, user_id).await?;
+        st.sessions.revoke_user(user_id).await?;
+        Ok(user_id)
+    }
+    .await;
+    let target = reset.as_ref().ok().map(Uuid::to_string);
+    let audit = match &reset {
+        Ok(user_id) => audit.with_actor(*user_id),
+        Err(_) => audit,
+    };
+    audit.record(&st, "account.password_reset", target.as_deref(), &reset).await;
+    reset?;
+    Ok(StatusCode::NO_CONTENT)
+}
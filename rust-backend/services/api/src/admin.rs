@@ -0,0 +1,203 @@
+//! 管理接口 `/api/v1/admin/*`：每个处理器用 `RequirePermission<P>` 声明所需权限（见
+//! `app_core::rbac`），缺权限的请求 403 `forbidden` 并记一条 `authz.denied`。
+//! 改变账号状态的操作都写审计。
+
+use app_core::rbac::{AuditRead, TodosAdmin, UsersManage, UsersRead};
+use app_core::{AuditEvent, AuditOutcome, AuditQuery, UserListQuery, UserSummary};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use dto::{AdminUserListQuery, AuditEventView, AuditListQuery, AuditOutcomeView, ErrorBody, Page, TodoListQuery, TodoView, UserAdminView};
+use time::format_description::well_known::Rfc3339;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::audit::AuditCtx;
+use crate::authz::RequirePermission;
+use crate::cursor;
+use crate::error::{parse_time, AppError, AppPath, AppQuery, AppResult};
+use crate::routes::todo_page;
+use crate::state::AppState;
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const DEFAULT_USER_PAGE_SIZE: u32 = 20;
+
+/// 查询本身也会记一条 `admin.audit.list`
+#[utoipa::path(get, path = "/api/v1/admin/audit", tag = "admin", params(AuditListQuery), security(("bearer" = [])), responses(
+    (status = 200, body = Page<AuditEventView>),
+    (status = 400, description = "`invalid_cursor`、`invalid_query` 或 `validation_failed`", body = ErrorBody),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`forbidden`：缺少 `audit:read`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn list_audit(
+    State(st): State<AppState>,
+    perm: RequirePermission<AuditRead>,
+    audit: AuditCtx,
+    AppQuery(req): AppQuery<AuditListQuery>,
+) -> AppResult<Json<Page<AuditEventView>>> {
+    let sub = perm.claims.sub;
+    req.validate()?;
+    let secret = &st.cfg.pagination.cursor_secret;
+    let query = AuditQuery {
+        from: req.from.as_deref().map(|t| parse_time("from", t)).transpose()?,
+        to: req.to.as_deref().map(|t| parse_time("to", t)).transpose()?,
+        actor: req.actor,
+        action: req.action.clone(),
+        outcome: req.outcome.map(|o| match o {
+            AuditOutcomeView::Success => AuditOutcome::Success,
+            AuditOutcomeView::Failure => AuditOutcome::Failure,
+        }),
+        limit: req.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+        before: req.cursor.as_deref().map(|c| cursor::decode_audit(secret, sub, c)).transpose()?,
+    };
+    let page = st.audit.query(&query).await.map_err(AppError::from);
+    audit.record(&st, "admin.audit.list", None, &page).await;
+    let page = page?;
+    Ok(Json(Page {
+        items: page.items.into_iter().map(view).collect(),
+        next_cursor: page.next.map(|c| cursor::encode_audit(secret, sub, c)),
+    }))
+}
+
+#[utoipa::path(get, path = "/api/v1/admin/users", tag = "admin", params(AdminUserListQuery), security(("bearer" = [])), responses(
+    (status = 200, body = Page<UserAdminView>),
+    (status = 400, description = "`invalid_cursor` 或 `validation_failed`", body = ErrorBody),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`forbidden`：缺少 `users:read`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn list_users(
+    State(st): State<AppState>,
+    perm: RequirePermission<UsersRead>,
+    AppQuery(req): AppQuery<AdminUserListQuery>,
+) -> AppResult<Json<Page<UserAdminView>>> {
+    let sub = perm.claims.sub;
+    req.validate()?;
+    let secret = &st.cfg.pagination.cursor_secret;
+    let query = UserListQuery {
+        q: req.q,
+        limit: req.limit.unwrap_or(DEFAULT_USER_PAGE_SIZE),
+        after: req.cursor.as_deref().map(|c| cursor::decode_users(secret, sub, c)).transpose()?,
+    };
+    let page = st.users.list(&query).await?;
+    Ok(Json(Page {
+        items: page.items.into_iter().map(user_view).collect(),
+        next_cursor: page.next.map(|c| cursor::encode_users(secret, sub, c)),
+    }))
+}
+
+/// 停用后立即失效：刷新令牌全部吊销，已签出的访问令牌在下一次请求时被拒
+#[utoipa::path(post, path = "/api/v1/admin/users/{id}/disable", tag = "admin", params(("id" = Uuid, Path)), security(("bearer" = [])), responses(
+    (status = 200, body = UserAdminView),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`forbidden`：缺少 `users:manage`", body = ErrorBody),
+    (status = 404, description = "`user_not_found`", body = ErrorBody),
+    (status = 422, description = "`cannot_disable_self`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn disable_user(
+    State(st): State<AppState>,
+    perm: RequirePermission<UsersManage>,
+    audit: AuditCtx,
+    AppPath(id): AppPath<Uuid>,
+) -> AppResult<Json<UserAdminView>> {
+    let user: AppResult<UserSummary> = async {
+        // 防止唯一的管理员把自己锁在外面
+        if id == perm.claims.sub {
+            return Err(AppError::Unprocessable { code: "cannot_disable_self" });
+        }
+        let user = st.users.set_disabled(id, true).await?;
+        st.sessions.revoke_user(id).await?;
+        Ok(user)
+    }
+    .await;
+    audit.record(&st, "admin.user.disable", Some(&id.to_string()), &user).await;
+    Ok(Json(user_view(user?)))
+}
+
+/// 启用后需要重新登录，停用前的会话不会恢复
+#[utoipa::path(post, path = "/api/v1/admin/users/{id}/enable", tag = "admin", params(("id" = Uuid, Path)), security(("bearer" = [])), responses(
+    (status = 200, body = UserAdminView),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`forbidden`：缺少 `users:manage`", body = ErrorBody),
+    (status = 404, description = "`user_not_found`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn enable_user(
+    State(st): State<AppState>,
+    _perm: RequirePermission<UsersManage>,
+    audit: AuditCtx,
+    AppPath(id): AppPath<Uuid>,
+) -> AppResult<Json<UserAdminView>> {
+    let user = st.users.set_disabled(id, false).await.map_err(AppError::from);
+    audit.record(&st, "admin.user.enable", Some(&id.to_string()), &user).await;
+    Ok(Json(user_view(user?)))
+}
+
+/// 强制下线：吊销该用户全部刷新令牌、已签出的访问令牌与个人访问令牌，账号本身不受影响
+#[utoipa::path(post, path = "/api/v1/admin/users/{id}/revoke-sessions", tag = "admin", params(("id" = Uuid, Path)), security(("bearer" = [])), responses(
+    (status = 204),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`forbidden`：缺少 `users:manage`", body = ErrorBody),
+    (status = 404, description = "`user_not_found`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn revoke_sessions(
+    State(st): State<AppState>,
+    _perm: RequirePermission<UsersManage>,
+    audit: AuditCtx,
+    AppPath(id): AppPath<Uuid>,
+) -> AppResult<StatusCode> {
+    let revoked = st.sessions.revoke_user(id).await.map_err(AppError::from);
+    audit.record(&st, "admin.user.revoke_sessions", Some(&id.to_string()), &revoked).await;
+    revoked?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 查询参数与 `GET /api/v1/todos` 相同
+#[utoipa::path(get, path = "/api/v1/admin/users/{id}/todos", tag = "admin", params(("id" = Uuid, Path), TodoListQuery), security(("bearer" = [])), responses(
+    (status = 200, body = Page<TodoView>),
+    (status = 400, description = "`invalid_cursor`、`invalid_query` 或 `validation_failed`", body = ErrorBody),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`forbidden`：缺少 `todos:admin`", body = ErrorBody),
+    (status = 404, description = "`user_not_found`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn list_user_todos(
+    State(st): State<AppState>,
+    _perm: RequirePermission<TodosAdmin>,
+    AppPath(id): AppPath<Uuid>,
+    AppQuery(req): AppQuery<TodoListQuery>,
+) -> AppResult<Json<Page<TodoView>>> {
+    st.users.access(id).await?.ok_or(AppError::NotFound { code: "user_not_found" })?;
+    Ok(Json(todo_page(&st, id, req).await?))
+}
+
+fn user_view(u: UserSummary) -> UserAdminView {
+    UserAdminView {
+        id: u.id,
+        email: u.email,
+        roles: u.roles,
+        disabled: u.disabled_at.is_some(),
+        created_at: dto::fmt_time(u.created_at),
+    }
+}
+
+fn view(e: AuditEvent) -> AuditEventView {
+    AuditEventView {
+        id: e.id,
+        occurred_at: e.occurred_at.format(&Rfc3339).expect("timestamps format as RFC 3339"),
+        actor: e.actor,
+        action: e.action,
+        target: e.target,
+        outcome: match e.outcome {
+            AuditOutcome::Success => AuditOutcomeView::Success,
+            AuditOutcome::Failure => AuditOutcomeView::Failure,
+        },
+        reason: e.reason,
+        ip: e.ip,
+        user_agent: e.user_agent,
+        request_id: e.request_id,
+    }
+}
@@ -0,0 +1,147 @@
+//! 个人访问令牌：用户在登录会话里创建、列出、吊销自己的 key，CI 与脚本用
+//! `Authorization: Bearer pat_...` 调用 REST 接口（gRPC 仍只接受 JWT）。
+//! `auth_mw` 对每个请求按摘要查库，吊销与账号停用立即生效。强制下线（`SessionRepo::revoke_user`：
+//! 管理员踢下线、重置密码、OIDC 接管未验证账号）连同用户的全部 key 一起删除。
+
+use std::net::IpAddr;
+
+use app_core::{ApiKey, NewApiKey, SCOPE_TODOS_READ, SCOPE_TODOS_WRITE};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use dto::{ApiKeyCreate, ApiKeyCreated, ApiKeyView, ErrorBody};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::audit::AuditCtx;
+use crate::auth::{hash_token, random_token, Claims};
+use crate::error::{AppError, AppJson, AppPath, AppResult};
+use crate::idempotency::IDEMPOTENCY_KEY;
+use crate::state::AppState;
+
+pub const TOKEN_PREFIX: &str = "pat_";
+/// 入库的明文前缀长度（含 `pat_`），足够辨认又远不足以猜出令牌
+const VISIBLE_PREFIX_LEN: usize = 12;
+const DEFAULT_TTL_DAYS: u32 = 90;
+
+/// 令牌只在这个响应里出现一次。不支持 `Idempotency-Key`：重放要么把明文令牌存下来，要么给不出令牌，
+/// 所以带这个头直接拒绝，免得客户端以为重试是安全的；重试前先列出 key 确认是否已经建好
+#[utoipa::path(post, path = "/api/v1/api-keys", tag = "auth", params(("Idempotency-Key" = Option<String>, Header, description = "不支持；带上时 400 `idempotency_not_supported`")), request_body = ApiKeyCreate, security(("bearer" = [])), responses(
+    (status = 200, body = ApiKeyCreated),
+    (status = 400, description = "`invalid_scope`、`validation_failed` 或 `idempotency_not_supported`", body = ErrorBody),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`session_required`：用访问令牌调用", body = ErrorBody),
+    (status = 409, description = "`api_key_name_taken`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn create_api_key(
+    State(st): State<AppState>,
+    claims: Claims,
+    audit: AuditCtx,
+    headers: HeaderMap,
+    AppJson(req): AppJson<ApiKeyCreate>,
+) -> AppResult<Json<ApiKeyCreated>> {
+    claims.require_session()?;
+    if headers.contains_key(IDEMPOTENCY_KEY) {
+        return Err(AppError::BadRequest {
+            code: "idempotency_not_supported",
+            message: "creating an API key cannot be replayed; list keys before retrying".into(),
+        });
+    }
+    req.validate()?;
+    let scopes = check_scopes(&claims, req.scopes)?;
+    let token = format!("{TOKEN_PREFIX}{}", random_token());
+    let hash = hash_token(&token);
+    let ttl = Duration::days(i64::from(req.expires_in_days.unwrap_or(DEFAULT_TTL_DAYS)));
+    let new = NewApiKey {
+        name: &req.name,
+        prefix: &token[..VISIBLE_PREFIX_LEN],
+        token_hash: &hash,
+        scopes: &scopes,
+        expires_at: OffsetDateTime::now_utc() + ttl,
+    };
+    let key = st.api_keys.create(claims.sub, &new).await.map_err(AppError::from);
+    let target = key.as_ref().ok().map(|k| k.id.to_string());
+    audit.record(&st, "api_key.create", target.as_deref(), &key).await;
+    Ok(Json(ApiKeyCreated { token, key: view(key?) }))
+}
+
+/// 包括已过期、尚未被维护任务清理的 key
+#[utoipa::path(get, path = "/api/v1/api-keys", tag = "auth", security(("bearer" = [])), responses(
+    (status = 200, body = Vec<ApiKeyView>),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`session_required`：用访问令牌调用", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn list_api_keys(State(st): State<AppState>, claims: Claims) -> AppResult<Json<Vec<ApiKeyView>>> {
+    claims.require_session()?;
+    let keys = st.api_keys.list(claims.sub).await?;
+    Ok(Json(keys.into_iter().map(view).collect()))
+}
+
+#[utoipa::path(delete, path = "/api/v1/api-keys/{id}", tag = "auth", params(("id" = Uuid, Path)), security(("bearer" = [])), responses(
+    (status = 204, description = "已吊销"),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`session_required`：用访问令牌调用", body = ErrorBody),
+    (status = 404, description = "`api_key_not_found`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn revoke_api_key(
+    State(st): State<AppState>,
+    claims: Claims,
+    audit: AuditCtx,
+    AppPath(id): AppPath<Uuid>,
+) -> AppResult<StatusCode> {
+    claims.require_session()?;
+    let revoked = st.api_keys.revoke(claims.sub, id).await.map_err(AppError::from);
+    audit.record(&st, "api_key.revoke", Some(&id.to_string()), &revoked).await;
+    revoked?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 由 `auth_mw` 调用：把 key 换成等价的 `Claims`。权限取用户当前权限与 key scope 的交集，
+/// 降级后的用户不会因为旧 key 保留管理权限
+pub(crate) async fn authenticate(st: &AppState, token: &str, ip: Option<IpAddr>) -> AppResult<Claims> {
+    let ip = ip.map(|ip| ip.to_string());
+    let key = st.api_keys.authenticate(&hash_token(token), ip.as_deref()).await?;
+    let key = key.ok_or_else(|| AppError::unauthorized("invalid_token"))?;
+    let access = st.users.access(key.user_id).await?.filter(|a| !a.disabled);
+    let access = access.ok_or_else(|| AppError::unauthorized("token_revoked"))?;
+    Ok(Claims {
+        sub: key.user_id,
+        exp: key.expires_at.unix_timestamp(),
+        jti: key.id,
+        roles: access.roles,
+        permissions: access.permissions.into_iter().filter(|p| key.scopes.contains(p)).collect(),
+        epoch: access.session_epoch,
+        scopes: Some(key.scopes),
+        email_unverified: !access.email_verified,
+    })
+}
+
+/// 只能授予 todo 读写与自己当前拥有的权限；去重并排序
+fn check_scopes(claims: &Claims, mut scopes: Vec<String>) -> AppResult<Vec<String>> {
+    if let Some(bad) = scopes
+        .iter()
+        .find(|s| *s != SCOPE_TODOS_READ && *s != SCOPE_TODOS_WRITE && !claims.permissions.contains(s))
+    {
+        return Err(AppError::BadRequest { code: "invalid_scope", message: format!("scope `{bad}` is unknown or not granted to you") });
+    }
+    scopes.sort_unstable();
+    scopes.dedup();
+    Ok(scopes)
+}
+
+fn view(k: ApiKey) -> ApiKeyView {
+    ApiKeyView {
+        id: k.id,
+        name: k.name,
+        prefix: k.prefix,
+        scopes: k.scopes,
+        expires_at: dto::fmt_time(k.expires_at),
+        last_used_at: k.last_used_at.map(dto::fmt_time),
+        last_used_ip: k.last_used_ip,
+        created_at: dto::fmt_time(k.created_at),
+    }
+}
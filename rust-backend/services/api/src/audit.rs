@@ -0,0 +1,77 @@
+//! 审计日志的协议层一侧：从请求里取出来源（登录用户、客户端 IP、User-Agent、请求 ID），
+//! 交给 core 的 `Auditor` 写入。写入失败只记日志与指标，不影响请求本身。
+
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+
+use app_core::AuditContext;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::{header, request::Parts, Extensions, HeaderMap};
+use uuid::Uuid;
+
+use crate::auth::Claims;
+use crate::error::AppResult;
+use crate::request_id::current_request_id;
+use crate::state::AppState;
+
+/// 超长的 User-Agent 截断后再入库
+const MAX_USER_AGENT: usize = 512;
+
+pub struct AuditCtx(pub AuditContext);
+
+impl AuditCtx {
+    pub fn from_request(st: &AppState, headers: &HeaderMap, extensions: &Extensions) -> Self {
+        let ip = client_ip(st, headers, extensions).map(|ip| ip.to_string());
+        let user_agent = headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|ua| ua.chars().take(MAX_USER_AGENT).collect());
+        Self(AuditContext {
+            actor: extensions.get::<Claims>().map(|c| c.sub),
+            ip,
+            user_agent,
+            request_id: current_request_id(),
+        })
+    }
+
+    /// 注册、登录成功后才知道是谁
+    pub fn with_actor(mut self, actor: Uuid) -> Self {
+        self.0.actor = Some(actor);
+        self
+    }
+
+    /// 按 `result` 记成功或失败；失败原因是响应里的错误码
+    pub async fn record<T>(&self, st: &AppState, action: &str, target: Option<&str>, result: &AppResult<T>) {
+        let result = result.as_ref().map(|_| ()).map_err(|e| e.code());
+        if let Err(e) = st.audit.record(&self.0, action, target, result).await {
+            metrics::counter!("audit_write_failures_total").increment(1);
+            tracing::error!(err = ?e, action, "writing audit event failed");
+        }
+    }
+}
+
+/// 客户端 IP 与限流用同一套 `trusted_proxies` 规则；未经 `ConnectInfo` 启动（如测试）时为空
+pub fn client_ip(st: &AppState, headers: &HeaderMap, extensions: &Extensions) -> Option<IpAddr> {
+    extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(peer)| st.rate_limits.client_ip(peer.ip(), headers))
+}
+
+/// 处理器里要用客户端 IP 时的提取器，规则同 `client_ip`
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, st: &AppState) -> Result<Self, Self::Rejection> {
+        Ok(Self(client_ip(st, &parts.headers, &parts.extensions)))
+    }
+}
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for AuditCtx {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, st: &AppState) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_request(st, &parts.headers, &parts.extensions))
+    }
+}
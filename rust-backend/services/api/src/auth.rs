@@ -0,0 +1,294 @@
+use std::net::IpAddr;
+use axum::{extract::{FromRequestParts, State}, Json};
+use axum::body::Body;
+use axum::http::{request::Parts, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::{Serialize, Deserialize};
+use time::{OffsetDateTime, Duration};
+use validator::Validate;
+use crate::{account, api_keys, audit::{client_ip, AuditCtx, ClientIp}, error::{AppError, AppJson, AppResult}, state::AppState, stream};
+use dto::{AuthResp, ErrorBody, LoginReq, LogoutReq, RefreshReq, RegisterReq};
+use app_core::{EmailTokenPurpose, RefreshOutcome, ADMIN_ROLE};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Claims {
+    pub sub: uuid::Uuid,
+    pub exp: i64,
+    /// 令牌 ID，登出时写入黑名单
+    pub jti: uuid::Uuid,
+    /// 签发时的角色与权限（见 `app_core::rbac`）；调整角色后要等令牌刷新才生效
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    /// 签发时用户的 `session_epoch`，强制下线后与库里不同，令牌随之失效
+    #[serde(default)]
+    pub epoch: i64,
+    /// 只有个人访问令牌（`pat_...`）的请求有值，见 api_keys.rs；JWT 会话不受 scope 限制
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+    /// 签发时邮箱尚未验证，按 `[account] unverified` 限制（见 authz.rs）；缺省视为已验证
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub email_unverified: bool,
+}
+
+impl Claims {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.as_ref().is_none_or(|s| s.iter().any(|g| g == scope))
+    }
+
+    /// 登出、管理访问令牌等操作不接受访问令牌本身，防止一把泄露的 key 给自己续命
+    pub fn require_session(&self) -> AppResult<()> {
+        match self.scopes {
+            Some(_) => Err(AppError::Forbidden { code: "session_required" }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 注册后立即登录，同时发出验证邮件；邮箱验证之前的权限见 `[account] unverified`
+#[utoipa::path(post, path = "/api/v1/auth/register", tag = "auth", request_body = RegisterReq, responses(
+    (status = 200, body = AuthResp),
+    (status = 400, description = "`validation_failed`、`password_too_short`、`password_breached` 等", body = ErrorBody),
+    (status = 409, description = "`email_taken`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn register(
+    State(st): State<AppState>,
+    audit: AuditCtx,
+    AppJson(req): AppJson<RegisterReq>,
+) -> AppResult<Json<AuthResp>> {
+    let session: AppResult<_> = async {
+        let user_id = create_user(&st, &req).await?;
+        // 验证邮件发不出去不影响注册，用户可以之后重发
+        if let Err(e) = account::send_link(&st, user_id, &req.email, EmailTokenPurpose::VerifyEmail).await {
+            tracing::warn!(err = ?e, %user_id, "issuing verification email failed");
+        }
+        Ok((user_id, start_session(&st, user_id).await?))
+    }
+    .await;
+    let audit = match &session {
+        Ok((user_id, _)) => audit.with_actor(*user_id),
+        Err(_) => audit,
+    };
+    audit.record(&st, "auth.register", Some(&req.email), &session).await;
+    Ok(Json(session?.1))
+}
+
+async fn create_user(st: &AppState, req: &RegisterReq) -> AppResult<uuid::Uuid> {
+    req.validate()?;
+    st.passwords.check_policy(&req.password)?;
+    let hash = st.passwords.hash(&req.password)?;
+    Ok(st.users.create(&req.email, &hash).await?)
+}
+
+/// 失败计数按账号与客户端 IP（经可信代理时取 `X-Forwarded-For` 里的真实地址）分别累计，超过阈值返回 429；
+/// 未经 `ConnectInfo` 启动（如测试）时只按账号计数
+#[utoipa::path(post, path = "/api/v1/auth/login", tag = "auth", request_body = LoginReq, responses(
+    (status = 200, body = AuthResp),
+    (status = 401, description = "`invalid_credentials`", body = ErrorBody),
+    (status = 403, description = "`account_disabled`", body = ErrorBody),
+    (status = 429, description = "`account_locked`、`ip_locked` 或 `rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn login(
+    State(st): State<AppState>,
+    ClientIp(ip): ClientIp,
+    audit: AuditCtx,
+    AppJson(req): AppJson<LoginReq>,
+) -> AppResult<Json<AuthResp>> {
+    // 停用的账号在签发令牌时才被拒绝（403 `account_disabled`），审计里同样记为登录失败
+    let session: AppResult<_> = async {
+        let user_id = check_password(&st, ip, &req).await?;
+        Ok((user_id, start_session(&st, user_id).await?))
+    }
+    .await;
+    let audit = match &session {
+        Ok((user_id, _)) => audit.with_actor(*user_id),
+        Err(_) => audit,
+    };
+    audit.record(&st, "auth.login", Some(&req.email), &session).await;
+    Ok(Json(session?.1))
+}
+
+async fn check_password(st: &AppState, ip: Option<IpAddr>, req: &LoginReq) -> AppResult<uuid::Uuid> {
+    let rec = st.users.find_by_email(&req.email).await?;
+    let stored = rec.as_ref().map(|(_, hash)| hash.as_str());
+    let rehashed = st.passwords.verify_login(&req.email, ip, stored, &req.password).await?;
+    let Some((user_id, _)) = rec else { return Err(AppError::unauthorized("invalid_credentials")) };
+    if let Some(hash) = rehashed {
+        // 写回失败不影响本次登录，下次还会再试
+        if let Err(e) = st.users.set_password_hash(user_id, &hash).await {
+            tracing::warn!(err = ?e, %user_id, "password rehash failed");
+        }
+    }
+    Ok(user_id)
+}
+
+/// 用刷新令牌换一对新令牌。旧令牌立即作废；已作废的令牌再次出现说明被盗用，整族吊销
+#[utoipa::path(post, path = "/api/v1/auth/refresh", tag = "auth", request_body = RefreshReq, responses(
+    (status = 200, body = AuthResp),
+    (status = 401, description = "`invalid_refresh_token` 或 `refresh_token_reused`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn refresh(State(st): State<AppState>, AppJson(req): AppJson<RefreshReq>) -> AppResult<Json<AuthResp>> {
+    let (refresh_token, new_hash) = new_refresh_token();
+    let outcome = st.sessions.rotate_refresh(&hash_token(&req.refresh_token), &new_hash, refresh_expiry(&st)).await?;
+    match outcome {
+        RefreshOutcome::Rotated { user_id } => {
+            let (token, expires_in) = issue_jwt(&st, user_id).await?;
+            Ok(Json(AuthResp { token, refresh_token, expires_in }))
+        }
+        RefreshOutcome::Reused { user_id } => {
+            tracing::warn!(%user_id, "refresh token reuse detected, token family revoked");
+            Err(AppError::unauthorized("refresh_token_reused"))
+        }
+        RefreshOutcome::Invalid => Err(AppError::unauthorized("invalid_refresh_token")),
+    }
+}
+
+/// 需要访问令牌：当前令牌的 jti 进黑名单直到过期；请求体可选带上刷新令牌一并吊销
+#[utoipa::path(post, path = "/api/v1/auth/logout", tag = "auth", request_body(content = Option<LogoutReq>), security(("bearer" = [])), responses(
+    (status = 204, description = "已登出"),
+    (status = 401, body = ErrorBody),
+    (status = 403, description = "`session_required`：用访问令牌调用", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn logout(
+    State(st): State<AppState>,
+    claims: Claims,
+    req: Option<AppJson<LogoutReq>>,
+) -> AppResult<StatusCode> {
+    claims.require_session()?;
+    let exp = OffsetDateTime::from_unix_timestamp(claims.exp).map_err(|e| AppError::Internal(e.into()))?;
+    st.sessions.deny_jti(claims.jti, exp).await?;
+    if let Some(refresh_token) = req.and_then(|AppJson(r)| r.refresh_token) {
+        st.sessions.revoke_family(claims.sub, &hash_token(&refresh_token)).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 先签访问令牌再写刷新令牌：账号已停用时不留下刷新令牌
+pub(crate) async fn start_session(st: &AppState, user_id: uuid::Uuid) -> AppResult<AuthResp> {
+    grant_bootstrap_admin(st, user_id).await?;
+    let (token, expires_in) = issue_jwt(st, user_id).await?;
+    let (refresh_token, hash) = new_refresh_token();
+    st.sessions.create_refresh(user_id, &hash, refresh_expiry(st)).await?;
+    Ok(AuthResp { token, refresh_token, expires_in })
+}
+
+/// `[admin] emails` 中的账号在邮箱验证之后补上管理员角色，登录与验证邮箱时调用。
+/// 只看注册不看验证的话，谁先注册这个地址谁就是管理员
+pub(crate) async fn grant_bootstrap_admin(st: &AppState, user_id: uuid::Uuid) -> AppResult<()> {
+    let Some(access) = st.users.access(user_id).await? else { return Ok(()) };
+    if access.email_verified && st.cfg.admin.emails.iter().any(|e| e.eq_ignore_ascii_case(&access.email)) {
+        st.users.grant_role(user_id, ADMIN_ROLE).await?;
+    }
+    Ok(())
+}
+
+/// 用当前签发密钥签出访问令牌，返回令牌及其有效秒数。角色与权限在此刻从库里读出
+async fn issue_jwt(st: &AppState, uid: uuid::Uuid) -> AppResult<(String, i64)> {
+    let access = st.users.access(uid).await?.ok_or_else(|| AppError::unauthorized("invalid_credentials"))?;
+    if access.disabled {
+        return Err(AppError::Forbidden { code: "account_disabled" });
+    }
+    let ttl = Duration::minutes(st.cfg.jwt.exp_minutes);
+    let exp = (OffsetDateTime::now_utc() + ttl).unix_timestamp();
+    let claims = Claims {
+        sub: uid,
+        exp,
+        jti: uuid::Uuid::new_v4(),
+        roles: access.roles,
+        permissions: access.permissions,
+        epoch: access.session_epoch,
+        scopes: None,
+        email_unverified: !access.email_verified,
+    };
+    Ok((st.jwt.sign(&claims)?, ttl.whole_seconds()))
+}
+
+fn refresh_expiry(st: &AppState) -> OffsetDateTime {
+    OffsetDateTime::now_utc() + Duration::days(st.cfg.jwt.refresh_days)
+}
+
+fn new_refresh_token() -> (String, String) {
+    let token = random_token();
+    let hash = hash_token(&token);
+    (token, hash)
+}
+
+/// 256 位随机数，刷新令牌与个人访问令牌共用；库里只存摘要：高熵令牌不需要加盐慢哈希
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub async fn auth_mw(State(st): State<AppState>, mut req: Request<Body>, next: Next) -> AppResult<Response> {
+    // 开放路由直接放行；登出需要知道吊销哪个令牌，仍要鉴权
+    let path = req.uri().path();
+    if (path.starts_with("/api/v1/auth/") && path != "/api/v1/auth/logout")
+        || path == "/healthz"
+        || path == "/readyz"
+        || path == "/metrics"
+        || path == "/.well-known/jwks.json"
+        || path == "/api/v1/openapi.json"
+        || path.starts_with("/api/v1/docs")
+    {
+        return Ok(next.run(req).await);
+    }
+    let ip = client_ip(&st, req.headers(), req.extensions());
+    let claims = verify_bearer(&st, bearer_token(&req), ip).await;
+    // 全部计数，审计每个 IP 每分钟只记一条
+    if claims.is_err() {
+        metrics::counter!("auth_tokens_rejected_total").increment(1);
+        if st.rate_limits.audit_rejection(ip) {
+            let audit = AuditCtx::from_request(&st, req.headers(), req.extensions());
+            audit.record(&st, "auth.token_rejected", Some(req.uri().path()), &claims).await;
+        }
+    }
+    // 将 Claims 注入扩展，路由处理器提取
+    req.extensions_mut().insert(claims?);
+    Ok(next.run(req).await)
+}
+
+/// 解析 Authorization: Bearer；WebSocket 推送接口另外接受子协议或查询参数里的令牌
+fn bearer_token(req: &Request<Body>) -> Option<String> {
+    let header = req.headers().get(axum::http::header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+    match header {
+        Some(auth) => auth.strip_prefix("Bearer ").map(str::to_string),
+        None if req.uri().path() == stream::STREAM_PATH => stream::upgrade_token(req),
+        None => None,
+    }
+}
+
+/// `pat_` 开头的是个人访问令牌，其余按 JWT 校验
+async fn verify_bearer(st: &AppState, token: Option<String>, ip: Option<IpAddr>) -> AppResult<Claims> {
+    let token = token.ok_or_else(|| AppError::unauthorized("missing_token"))?;
+    if token.starts_with(api_keys::TOKEN_PREFIX) {
+        return api_keys::authenticate(st, &token, ip).await;
+    }
+    let claims: Claims = st.jwt.verify(&token)?;
+    if st.sessions.is_revoked(claims.sub, claims.jti, claims.epoch).await? {
+        return Err(AppError::unauthorized("token_revoked"));
+    }
+    Ok(claims)
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Claims
+where S: Send + Sync {
+    type Rejection = AppError;
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Claims>().cloned().ok_or_else(|| AppError::unauthorized("missing_token"))
+    }
+}
@@ -0,0 +1,339 @@
+use std::path::Path;
+
+use figment::providers::{Env, Format, Toml};
+use ipnet::IpNet;
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ServerCfg {
+    pub addr: String,
+    /// 收到 SIGTERM 后先让 /readyz 失败，等这么久让负载均衡摘除本实例，再停止接受新连接
+    pub drain_delay_secs: u64,
+    /// 停止接受连接后等待在途请求完成的上限，超时直接退出
+    pub shutdown_timeout_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DbCfg { pub url: String }
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum JwtAlg { RS256, EdDSA }
+
+/// 一把签名密钥；文件为 PEM（私钥 PKCS#8，公钥 SPKI）
+#[derive(Debug, Deserialize, Clone)]
+pub struct JwtKeyCfg {
+    pub kid: String,
+    pub alg: JwtAlg,
+    /// 只保留验签能力的旧密钥可以不配
+    pub private_key: Option<String>,
+    pub public_key: String,
+    /// 从此时起可用于签发；不配表示立即可用
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub not_before: Option<OffsetDateTime>,
+    /// 到此时停止验签并从 JWKS 中移除
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub retire_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct JwtCfg {
+    pub keys: Vec<JwtKeyCfg>,
+    /// 访问令牌有效期
+    pub exp_minutes: i64,
+    /// 刷新令牌有效期
+    pub refresh_days: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LogCfg {
+    pub json: bool,
+    pub level: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PaginationCfg {
+    /// 列表游标的 HMAC 密钥；更换后已发出的游标全部失效
+    pub cursor_secret: String,
+}
+
+/// Argon2id 参数；调整后老用户在下次登录时自动重哈希
+#[derive(Debug, Deserialize, Clone)]
+pub struct Argon2Cfg {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LockoutCfg {
+    pub account_threshold: u32,
+    pub ip_threshold: u32,
+    pub base_secs: i64,
+    pub max_secs: i64,
+    pub window_secs: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PasswordCfg {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// 泄露密码表文件（每行一个）；不配置则不检查
+    pub breached_list: Option<String>,
+    pub argon2: Argon2Cfg,
+    pub lockout: LockoutCfg,
+}
+
+/// 一个路由组的配额（GCRA）：平均每分钟 `per_minute` 个请求，空闲后最多连续放行 `burst` 个
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitRule {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitCfg {
+    /// 反向代理的地址段；只有直连对端落在其中时才采信 `X-Forwarded-For`
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpNet>,
+    /// `/api/v1/auth/*`，按客户端 IP
+    pub auth: RateLimitRule,
+    /// 其余需要登录的接口，按用户
+    pub api: RateLimitRule,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PublisherKind {
+    /// 只留在进程内存里，测试与本地调试用
+    Memory,
+    /// 逐行追加 JSON 到 `jsonl_path`
+    Jsonl,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct OutboxCfg {
+    pub publisher: PublisherKind,
+    pub jsonl_path: String,
+    pub poll_interval_ms: u64,
+    pub batch_size: u32,
+    /// 领取后多久未确认就允许再次领取；应大于发布一批的最长耗时
+    pub lease_secs: i64,
+    /// 含首次投递；用完后转入死信
+    pub max_attempts: i32,
+    /// 第 n 次失败后等待 `retry_base_secs * 2^(n-1)`，不超过 `retry_max_secs`
+    pub retry_base_secs: i64,
+    pub retry_max_secs: i64,
+    /// 待投递事件超过这么多时 /readyz 失败
+    pub max_pending: i64,
+    /// 已投递事件保留天数，之后由计划任务删除
+    pub retention_days: i64,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifierKind {
+    /// 只留在进程内存里，测试用
+    Memory,
+    /// 只写一条日志
+    Log,
+    /// 经 `[mail]` 发邮件给 todo 的主人
+    Mail,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SchedulerCfg {
+    pub notifier: NotifierKind,
+    pub poll_interval_ms: u64,
+    pub batch_size: u32,
+    /// 领取后多久未确认就允许再次领取；应大于执行一批的最长耗时，否则同一个提醒可能发两次
+    pub lease_secs: i64,
+    /// 含首次执行；用完后转入 dead，不再执行
+    pub max_attempts: i32,
+    /// 执行失败后等这么久再试
+    pub retry_secs: i64,
+    /// 周期清理（见 tasks.rs）的间隔；数据库清理全局一个实例执行，内存清理每个实例各自执行
+    pub maintenance_interval_secs: i64,
+    /// 已完成与 dead 的任务保留天数，之后由周期清理删除
+    pub retention_days: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StreamCfg {
+    /// 推送通道的容量；连接消费跟不上、落后超过这么多条时收到 `resync`
+    pub buffer: usize,
+    /// 心跳间隔，防止空闲连接被代理断开
+    pub ping_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdempotencyStoreKind {
+    /// 与业务数据同库，多实例共享
+    Db,
+    /// 进程内，只适合单实例与测试
+    Memory,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct IdempotencyCfg {
+    pub store: IdempotencyStoreKind,
+    /// 完成后的响应保留多久，期间用同一个键重试都会得到同一个响应
+    pub ttl_secs: i64,
+    /// 执行中的键最多占用多久；进程中途退出时，过了这段时间客户端才能用同一个键重试
+    pub lock_secs: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GrpcCfg {
+    /// 与 HTTP 分开监听，gRPC 流量可以单独做负载均衡与网络策略
+    pub addr: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AdminCfg {
+    /// 引导管理员：这些邮箱注册或登录时被授予 `admin` 角色，比较时忽略大小写
+    pub emails: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailerKind {
+    /// 只留在进程内存里，测试用
+    Memory,
+    /// 每封邮件写成 `file_dir` 下的一个 .eml 文件，本地开发直接打开查看
+    File,
+    Smtp,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// 明文，只用于本机的 Mailpit/MailHog 之类
+    None,
+    /// 先明文连接再升级（通常是 587 端口）
+    Starttls,
+    /// 直接 TLS（通常是 465 端口）
+    Tls,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SmtpCfg {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MailCfg {
+    pub mailer: MailerKind,
+    /// 发件人，如 `Todo <no-reply@example.com>`
+    pub from: String,
+    pub file_dir: String,
+    pub smtp: SmtpCfg,
+}
+
+/// 未验证邮箱的账号能做什么
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnverifiedAccess {
+    /// 不限制
+    Full,
+    /// 只能读，写操作 403 `email_unverified`
+    ReadOnly,
+    /// 能登录，但 `/api/v1/*` 的业务接口一律 403 `email_unverified`
+    None,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AccountCfg {
+    /// 邮件链接里令牌的 HMAC 密钥；更换后尚未使用的链接全部失效
+    pub token_secret: String,
+    /// 邮件里链接的前缀，前端在 `/verify-email`、`/reset-password` 页面取出 `token` 再调用 API
+    pub public_url: String,
+    pub verify_ttl_hours: i64,
+    pub reset_ttl_minutes: i64,
+    pub unverified: UnverifiedAccess,
+}
+
+/// 授权码 + PKCE 登录；`issuer` 为空时关闭，相关接口返回 404 `oidc_disabled`
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcCfg {
+    /// 发现文档在 `{issuer}/.well-known/openid-configuration`，ID 令牌的 `iss` 必须与之完全一致
+    pub issuer: Option<String>,
+    pub client_id: String,
+    /// 机密客户端才有；公开客户端只靠 PKCE
+    pub client_secret: Option<String>,
+    /// 身份提供方跳回的前端页面，由它把 `code` 与 `state` 交给 `/api/v1/auth/oidc/callback`
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    /// 从跳转到回调最多允许多久
+    pub login_ttl_secs: i64,
+}
+
+/// 运行环境；镜像里设为 `prod`（`APP_PROFILE=prod`）
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// 本地开发与测试，可以用仓库里的开发密钥
+    Dev,
+    /// 部署环境：配置了开发密钥时拒绝启动
+    Prod,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AppCfg {
+    pub profile: Profile,
+    pub server: ServerCfg,
+    pub db: DbCfg,
+    pub jwt: JwtCfg,
+    pub log: LogCfg,
+    pub pagination: PaginationCfg,
+    pub password: PasswordCfg,
+    pub rate_limit: RateLimitCfg,
+    pub outbox: OutboxCfg,
+    pub scheduler: SchedulerCfg,
+    pub stream: StreamCfg,
+    pub grpc: GrpcCfg,
+    pub idempotency: IdempotencyCfg,
+    pub admin: AdminCfg,
+    pub mail: MailCfg,
+    pub account: AccountCfg,
+    pub oidc: OidcCfg,
+}
+
+/// 开发密钥所在目录，随仓库公开
+const DEV_KEY_DIR: &str = "config/keys";
+
+impl AppCfg {
+    /// `prod` 下拒绝 `dev-` 开头的 kid 与 `config/keys/` 下的密钥文件：
+    /// 它们的私钥是公开的，谁都能用来伪造访问令牌
+    pub fn check_profile(&self) -> anyhow::Result<()> {
+        if self.profile != Profile::Prod {
+            return Ok(());
+        }
+        let is_dev_file = |path: &str| Path::new(path).parent().is_some_and(|dir| dir.ends_with(DEV_KEY_DIR));
+        for key in &self.jwt.keys {
+            let mut files = key.private_key.iter().chain([&key.public_key]);
+            if key.kid.starts_with("dev-") || files.any(|f| is_dev_file(f)) {
+                anyhow::bail!(
+                    "JWT key `{}` is a development key (kid `dev-*` or a file under {DEV_KEY_DIR}/); configure production keys, see config/keys/README.md",
+                    key.kid
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn load() -> anyhow::Result<AppCfg> {
+    dotenvy::dotenv().ok();
+    let cfg: AppCfg = figment::Figment::new()
+        .merge(Toml::file("config/default.toml"))
+        .merge(Env::prefixed("APP_").split("__"))
+        .extract()?;
+    cfg.check_profile()?;
+    Ok(cfg)
+}
@@ -0,0 +1,93 @@
+//! 列表分页游标（todo 列表、审计日志与用户列表）：`base64url(json).base64url(hmac)`。对客户端不透明，
+//! 签名同时覆盖用户 ID，改动内容或拿别人的游标都会被拒绝。
+
+use app_core::{AuditCursor, TodoCursor, TodoSort, UserCursor};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const AUDIT_TAG: char = 'u';
+const USERS_TAG: char = 'r';
+
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    /// 排序键的时间（todo 与用户的 created_at、审计的 occurred_at），微秒（与 timestamptz 精度一致）
+    t: i64,
+    id: Uuid,
+    /// 列表与排序方向：todo 的 `d` 倒序、`a` 正序，审计日志 `u`、用户列表 `r`；换了列表或排序的游标没有意义
+    s: char,
+}
+
+fn mac(secret: &str, user_id: Uuid, payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(user_id.as_bytes());
+    mac.update(payload);
+    mac
+}
+
+fn sort_tag(sort: TodoSort) -> char {
+    match sort {
+        TodoSort::CreatedDesc => 'd',
+        TodoSort::CreatedAsc => 'a',
+    }
+}
+
+pub fn encode(secret: &str, user_id: Uuid, sort: TodoSort, cursor: TodoCursor) -> String {
+    encode_raw(secret, user_id, sort_tag(sort), cursor.created_at, cursor.id)
+}
+
+/// 校验签名并还原游标；任何不符都返回 400 `invalid_cursor`，不区分具体原因
+pub fn decode(secret: &str, user_id: Uuid, sort: TodoSort, raw: &str) -> Result<TodoCursor, AppError> {
+    let (created_at, id) = decode_raw(secret, user_id, sort_tag(sort), raw)?;
+    Ok(TodoCursor { created_at, id })
+}
+
+/// 审计日志查询（时间倒序）的游标，绑定发起查询的管理员
+pub fn encode_audit(secret: &str, admin_id: Uuid, cursor: AuditCursor) -> String {
+    encode_raw(secret, admin_id, AUDIT_TAG, cursor.occurred_at, cursor.id)
+}
+
+pub fn decode_audit(secret: &str, admin_id: Uuid, raw: &str) -> Result<AuditCursor, AppError> {
+    let (occurred_at, id) = decode_raw(secret, admin_id, AUDIT_TAG, raw)?;
+    Ok(AuditCursor { occurred_at, id })
+}
+
+/// 管理端用户列表（注册时间倒序）的游标，绑定发起查询的管理员
+pub fn encode_users(secret: &str, admin_id: Uuid, cursor: UserCursor) -> String {
+    encode_raw(secret, admin_id, USERS_TAG, cursor.created_at, cursor.id)
+}
+
+pub fn decode_users(secret: &str, admin_id: Uuid, raw: &str) -> Result<UserCursor, AppError> {
+    let (created_at, id) = decode_raw(secret, admin_id, USERS_TAG, raw)?;
+    Ok(UserCursor { created_at, id })
+}
+
+fn encode_raw(secret: &str, user_id: Uuid, tag: char, t: OffsetDateTime, id: Uuid) -> String {
+    let micros = (t.unix_timestamp_nanos() / 1_000) as i64;
+    let payload = serde_json::to_vec(&Payload { t: micros, id, s: tag }).expect("cursor payload serializes");
+    let sig = mac(secret, user_id, &payload).finalize().into_bytes();
+    format!("{}.{}", URL_SAFE_NO_PAD.encode(&payload), URL_SAFE_NO_PAD.encode(sig))
+}
+
+fn decode_raw(secret: &str, user_id: Uuid, tag: char, raw: &str) -> Result<(OffsetDateTime, Uuid), AppError> {
+    let invalid = || AppError::BadRequest { code: "invalid_cursor", message: "cursor is malformed or expired".into() };
+    let (payload, sig) = raw.split_once('.').ok_or_else(invalid)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+    let sig = URL_SAFE_NO_PAD.decode(sig).map_err(|_| invalid())?;
+    // verify_slice 是常量时间比较
+    mac(secret, user_id, &payload).verify_slice(&sig).map_err(|_| invalid())?;
+    let p: Payload = serde_json::from_slice(&payload).map_err(|_| invalid())?;
+    if p.s != tag {
+        return Err(invalid());
+    }
+    let t = OffsetDateTime::from_unix_timestamp_nanos(i128::from(p.t) * 1_000).map_err(|_| invalid())?;
+    Ok((t, p.id))
+}
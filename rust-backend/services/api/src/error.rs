@@ -0,0 +1,224 @@
+//! HTTP 层的错误边界：所有处理器、提取器与中间件的错误都汇总到 [`AppError`]，
+//! 统一渲染为 [`ErrorBody`]。内部错误只记日志，响应中只有 `internal` 错误码。
+
+use app_core::{PasswordError, RepoError};
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use dto::ErrorBody;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::request_id::current_request_id;
+
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest { code: &'static str, message: String },
+    Unauthorized { code: &'static str, message: &'static str },
+    /// 403：已登录但没有权限
+    Forbidden { code: &'static str },
+    NotFound { code: &'static str },
+    Conflict { code: &'static str },
+    /// 422：请求格式正确但与已有状态矛盾
+    Unprocessable { code: &'static str },
+    /// 429，带 `Retry-After`（秒）
+    TooManyRequests { code: &'static str, retry_after_secs: u64 },
+    Internal(anyhow::Error),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn unauthorized(code: &'static str) -> Self {
+        let message = match code {
+            "invalid_credentials" => "invalid email or password",
+            "missing_token" => "missing bearer token",
+            "token_revoked" => "token has been revoked",
+            "invalid_refresh_token" => "invalid or expired refresh token",
+            "refresh_token_reused" => "refresh token was already used; session revoked",
+            "oidc_code_rejected" => "identity provider rejected the authorization code",
+            "invalid_id_token" => "identity provider returned an invalid ID token",
+            _ => "invalid or expired token",
+        };
+        AppError::Unauthorized { code, message }
+    }
+
+    /// 对外的错误码，审计日志记作失败原因
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest { code, .. }
+            | AppError::Unauthorized { code, .. }
+            | AppError::Forbidden { code }
+            | AppError::NotFound { code }
+            | AppError::Conflict { code }
+            | AppError::Unprocessable { code }
+            | AppError::TooManyRequests { code, .. } => code,
+            AppError::Internal(_) => "internal",
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let request_id = current_request_id();
+        let mut retry_after = None;
+        let (status, code, message) = match self {
+            AppError::BadRequest { code, message } => (StatusCode::BAD_REQUEST, code, message),
+            AppError::Unauthorized { code, message } => (StatusCode::UNAUTHORIZED, code, message.to_string()),
+            AppError::Forbidden { code } => {
+                let message = match code {
+                    "email_unverified" => "verify your email address first",
+                    "oidc_email_unverified" => "identity provider has not verified this email address",
+                    _ => "insufficient permissions",
+                };
+                (StatusCode::FORBIDDEN, code, message.to_string())
+            }
+            AppError::NotFound { code } => (StatusCode::NOT_FOUND, code, "resource not found".to_string()),
+            AppError::Conflict { code } => {
+                let message = match code {
+                    "idempotency_request_in_progress" => "a request with this idempotency key is still in progress",
+                    _ => "resource already exists",
+                };
+                (StatusCode::CONFLICT, code, message.to_string())
+            }
+            AppError::Unprocessable { code } => {
+                let message = match code {
+                    "idempotency_key_reused" => "idempotency key was already used for a different request",
+                    "cannot_disable_self" => "administrators cannot disable their own account",
+                    _ => "request conflicts with existing state",
+                };
+                (StatusCode::UNPROCESSABLE_ENTITY, code, message.to_string())
+            }
+            AppError::TooManyRequests { code, retry_after_secs } => {
+                retry_after = Some(retry_after_secs);
+                (StatusCode::TOO_MANY_REQUESTS, code, format!("too many attempts, retry after {retry_after_secs}s"))
+            }
+            AppError::Internal(e) => {
+                tracing::error!(request_id = request_id.as_deref(), err = ?e, "internal error");
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal", "internal error".to_string())
+            }
+        };
+        let mut resp = (status, Json(ErrorBody { code: code.into(), message, request_id })).into_response();
+        if let Some(secs) = retry_after {
+            resp.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        resp
+    }
+}
+
+impl From<RepoError> for AppError {
+    fn from(e: RepoError) -> Self {
+        match e {
+            RepoError::NotFound(code) => AppError::NotFound { code },
+            RepoError::Conflict(code) => AppError::Conflict { code },
+            e @ RepoError::Db(_) => AppError::Internal(e.into()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        AppError::Internal(e)
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        AppError::Internal(e.into())
+    }
+}
+
+impl From<validator::ValidationErrors> for AppError {
+    fn from(e: validator::ValidationErrors) -> Self {
+        // 只回显字段名，规则细节留给文档
+        let mut fields: Vec<_> = e.field_errors().into_keys().collect();
+        fields.sort_unstable();
+        AppError::BadRequest { code: "validation_failed", message: format!("invalid fields: {}", fields.join(", ")) }
+    }
+}
+
+/// 解析请求体或查询参数里的 RFC 3339 时间；格式不对与其他字段校验失败一样报 `validation_failed`
+pub(crate) fn parse_time(field: &str, value: &str) -> AppResult<OffsetDateTime> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|_| AppError::BadRequest { code: "validation_failed", message: format!("invalid fields: {field}") })
+}
+
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+        match e.kind() {
+            ErrorKind::InvalidToken
+            | ErrorKind::InvalidSignature
+            | ErrorKind::ExpiredSignature
+            | ErrorKind::ImmatureSignature
+            | ErrorKind::InvalidAlgorithm
+            | ErrorKind::InvalidAudience
+            | ErrorKind::InvalidIssuer
+            | ErrorKind::InvalidSubject
+            | ErrorKind::MissingRequiredClaim(_)
+            | ErrorKind::Base64(_)
+            | ErrorKind::Json(_)
+            | ErrorKind::Utf8(_) => AppError::unauthorized("invalid_token"),
+            // 密钥格式、签名失败等属于服务端问题
+            _ => AppError::Internal(e.into()),
+        }
+    }
+}
+
+impl From<PasswordError> for AppError {
+    fn from(e: PasswordError) -> Self {
+        match e {
+            PasswordError::Policy(code) => {
+                let message = match code {
+                    "password_too_short" => "password is too short",
+                    "password_too_long" => "password is too long",
+                    _ => "password appears in a list of breached passwords",
+                };
+                AppError::BadRequest { code, message: message.into() }
+            }
+            PasswordError::InvalidCredentials => AppError::unauthorized("invalid_credentials"),
+            PasswordError::Locked { code, retry_after } => {
+                // 向上取整，客户端按 Retry-After 重试时锁定一定已经解除
+                let secs = retry_after.whole_seconds() + i64::from(retry_after.subsec_nanoseconds() > 0);
+                AppError::TooManyRequests { code, retry_after_secs: secs.max(1) as u64 }
+            }
+            PasswordError::Repo(e) => e.into(),
+            e => AppError::Internal(e.into()),
+        }
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(e: JsonRejection) -> Self {
+        AppError::BadRequest { code: "invalid_json", message: e.body_text() }
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(e: PathRejection) -> Self {
+        AppError::BadRequest { code: "invalid_path", message: e.body_text() }
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(e: QueryRejection) -> Self {
+        AppError::BadRequest { code: "invalid_query", message: e.body_text() }
+    }
+}
+
+/// 替代 `axum::Json` 的提取器：请求体解析失败时同样返回统一错误体
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(AppError))]
+pub struct AppJson<T>(pub T);
+
+/// 替代 `axum::extract::Path` 的提取器，理由同上
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct AppPath<T>(pub T);
+
+/// 替代 `axum::extract::Query` 的提取器，理由同上
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(AppError))]
+pub struct AppQuery<T>(pub T);
@@ -0,0 +1,211 @@
+//! gRPC 入口（proto/todo/v1/todo.proto），在 `[grpc] addr` 上单独监听。
+//!
+//! 与 REST 共用 `AppState`：用例走同一个 `TodoService`，令牌由同一套 `JwtKeys` 校验，
+//! 按用户的限流与 `/api/v1/todos` 共用配额。错误的 status message 就是 REST 的错误码。
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use app_core::{AuditContext, Todo, TodoQuery, TodoService as Todos, TodoSort};
+use dto::TodoCreate;
+use futures_util::{stream, Stream, StreamExt, TryStreamExt};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tonic::metadata::MetadataValue;
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::audit::AuditCtx;
+use crate::auth::Claims;
+use crate::authz::check_email_verified;
+use crate::error::AppError;
+use crate::jwt::JwtKeys;
+use crate::routes::new_todo;
+use crate::state::AppState;
+
+pub mod pb {
+    tonic::include_proto!("todo.v1");
+}
+
+use pb::todo_service_server::{TodoService, TodoServiceServer};
+
+/// 服务端流式 `List` 每次从仓储读取的条数
+const LIST_BATCH: u32 = 100;
+
+/// 运行 gRPC 服务直到 `cancel` 取消，随后等在途调用结束
+pub async fn serve(listener: TcpListener, st: AppState, cancel: CancellationToken) {
+    let incoming = match TcpIncoming::from_listener(listener, true, None) {
+        Ok(incoming) => incoming,
+        Err(e) => {
+            tracing::error!(err = %e, "grpc listener setup failed");
+            return;
+        }
+    };
+    let res = tonic::transport::Server::builder()
+        .add_service(service(st))
+        .serve_with_incoming_shutdown(incoming, cancel.cancelled_owned())
+        .await;
+    match res {
+        Ok(()) => tracing::info!("grpc server stopped"),
+        Err(e) => tracing::error!(err = %e, "grpc server failed"),
+    }
+}
+
+/// 带鉴权拦截器的服务，测试可以直接挂到自己的 `Server` 上
+pub fn service(st: AppState) -> InterceptedService<TodoServiceServer<TodoGrpc>, JwtInterceptor> {
+    let interceptor = JwtInterceptor { jwt: st.jwt.clone() };
+    TodoServiceServer::with_interceptor(TodoGrpc { st }, interceptor)
+}
+
+/// 校验 metadata 中的 `authorization: Bearer <token>`，把 `Claims` 放进请求扩展。
+/// 拦截器是同步的，吊销检查（查库）留到 `TodoGrpc::caller` 里做
+#[derive(Clone)]
+pub struct JwtInterceptor {
+    jwt: Arc<JwtKeys>,
+}
+
+impl Interceptor for JwtInterceptor {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        let token = req
+            .metadata()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or_else(|| AppError::unauthorized("missing_token"))?;
+        let claims: Claims = self.jwt.verify(token)?;
+        req.extensions_mut().insert(claims);
+        Ok(req)
+    }
+}
+
+pub struct TodoGrpc {
+    st: AppState,
+}
+
+impl TodoGrpc {
+    /// 与 `auth_mw` 相同的吊销检查与未验证邮箱限制（`write` 表示写操作），再按用户限流，返回调用者
+    async fn caller<T>(&self, req: &Request<T>, write: bool) -> Result<Uuid, Status> {
+        let claims = req.extensions().get::<Claims>().ok_or_else(|| AppError::unauthorized("missing_token"))?;
+        if self.st.sessions.is_revoked(claims.sub, claims.jti, claims.epoch).await.map_err(AppError::from)? {
+            return Err(AppError::unauthorized("token_revoked").into());
+        }
+        check_email_verified(self.st.cfg.account.unverified, claims, write)?;
+        if let Err(retry_after_secs) = self.st.rate_limits.api.try_acquire(&claims.sub) {
+            return Err(AppError::TooManyRequests { code: "rate_limited", retry_after_secs }.into());
+        }
+        Ok(claims.sub)
+    }
+
+    /// 审计来源：对端地址（gRPC 一般经四层负载均衡，不解析转发头）与 metadata 里的 UA、请求 ID
+    fn audit<T>(&self, req: &Request<T>, user_id: Uuid) -> AuditCtx {
+        let meta = |key| req.metadata().get(key).and_then(|v| v.to_str().ok()).map(str::to_string);
+        AuditCtx(AuditContext {
+            actor: Some(user_id),
+            ip: req.remote_addr().map(|addr| addr.ip().to_string()),
+            user_agent: meta("user-agent"),
+            request_id: meta("x-request-id"),
+        })
+    }
+}
+
+type TodoStream = Pin<Box<dyn Stream<Item = Result<pb::Todo, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl TodoService for TodoGrpc {
+    async fn create(&self, req: Request<pb::CreateTodoRequest>) -> Result<Response<pb::Todo>, Status> {
+        let user_id = self.caller(&req, true).await?;
+        let audit = self.audit(&req, user_id);
+        let r = req.into_inner();
+        let new = new_todo(TodoCreate { title: r.title, due_at: r.due_at, remind_at: r.remind_at, recurrence: r.recurrence });
+        let todo = match new {
+            Ok(new) => self.st.todos.create(user_id, &new).await.map_err(AppError::from),
+            Err(e) => Err(e),
+        };
+        let target = todo.as_ref().ok().map(|t| t.id.to_string());
+        audit.record(&self.st, "todo.create", target.as_deref(), &todo).await;
+        Ok(Response::new(message(todo?)))
+    }
+
+    type ListStream = TodoStream;
+
+    /// 读完一页、发完再读下一页：客户端读得慢时背压一路传到这里，不会把全部数据读进内存
+    async fn list(&self, req: Request<pb::ListTodosRequest>) -> Result<Response<Self::ListStream>, Status> {
+        let user_id = self.caller(&req, false).await?;
+        let r = req.into_inner();
+        let sort = if r.oldest_first { TodoSort::CreatedAsc } else { TodoSort::CreatedDesc };
+        let query = TodoQuery { done: r.done, q: Some(r.q).filter(|q| !q.is_empty()), sort, limit: LIST_BATCH, after: None };
+        let todos: Arc<Todos> = self.st.todos.clone();
+        let pages = stream::try_unfold(Some(query), move |query| {
+            let todos = todos.clone();
+            async move {
+                let Some(query) = query else { return Ok(None) };
+                let page = todos.list(user_id, &query).await.map_err(AppError::from)?;
+                let next = page.next.map(|after| TodoQuery { after: Some(after), ..query });
+                Ok::<_, Status>(Some((page.items, next)))
+            }
+        });
+        let items = pages.map_ok(|items| stream::iter(items).map(message).map(Ok)).try_flatten();
+        Ok(Response::new(items.boxed()))
+    }
+
+    async fn complete(&self, req: Request<pb::TodoId>) -> Result<Response<pb::Todo>, Status> {
+        let user_id = self.caller(&req, true).await?;
+        let audit = self.audit(&req, user_id);
+        let id = parse_id(&req.into_inner().id)?;
+        let todo = self.st.todos.complete(user_id, id).await.map_err(AppError::from);
+        audit.record(&self.st, "todo.complete", Some(&id.to_string()), &todo).await;
+        Ok(Response::new(message(todo?)))
+    }
+
+    async fn delete(&self, req: Request<pb::TodoId>) -> Result<Response<pb::DeleteTodoResponse>, Status> {
+        let user_id = self.caller(&req, true).await?;
+        let audit = self.audit(&req, user_id);
+        let id = parse_id(&req.into_inner().id)?;
+        let deleted = self.st.todos.delete(user_id, id).await.map_err(AppError::from);
+        audit.record(&self.st, "todo.delete", Some(&id.to_string()), &deleted).await;
+        deleted?;
+        Ok(Response::new(pb::DeleteTodoResponse {}))
+    }
+}
+
+fn parse_id(id: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(id).map_err(|e| AppError::BadRequest { code: "invalid_id", message: e.to_string() })
+}
+
+fn message(t: Todo) -> pb::Todo {
+    pb::Todo {
+        id: t.id.to_string(),
+        title: t.title,
+        done: t.done,
+        created_at: dto::fmt_time(t.created_at),
+        due_at: t.due_at.map(dto::fmt_time),
+        remind_at: t.remind_at.map(dto::fmt_time),
+        recurrence: t.recurrence.map(|r| r.to_string()),
+    }
+}
+
+/// HTTP 状态码到 gRPC 状态码的对应关系见 gRPC 文档 `http-grpc-status-mapping`
+impl From<AppError> for Status {
+    fn from(e: AppError) -> Self {
+        match e {
+            AppError::BadRequest { code, .. } => Status::invalid_argument(code),
+            AppError::Unauthorized { code, .. } => Status::unauthenticated(code),
+            AppError::Forbidden { code } => Status::permission_denied(code),
+            AppError::NotFound { code } => Status::not_found(code),
+            AppError::Conflict { code } => Status::already_exists(code),
+            AppError::Unprocessable { code } => Status::failed_precondition(code),
+            AppError::TooManyRequests { code, retry_after_secs } => {
+                let mut status = Status::resource_exhausted(code);
+                status.metadata_mut().insert("retry-after", MetadataValue::from(retry_after_secs));
+                status
+            }
+            AppError::Internal(e) => {
+                tracing::error!(err = ?e, "grpc internal error");
+                Status::internal("internal")
+            }
+        }
+    }
+}
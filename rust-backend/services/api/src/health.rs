@@ -0,0 +1,80 @@
+//! 探针：`/healthz` 只表示进程还活着；`/readyz` 逐项检查依赖，决定本实例是否接流量。
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use dto::{CheckStatus, CheckView, ReadinessView};
+
+use crate::state::AppState;
+
+/// 单项检查的上限；数据库卡住时探针也要及时返回
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub async fn healthz() -> &'static str { "ok" }
+
+pub async fn readyz(State(st): State<AppState>) -> (StatusCode, Json<ReadinessView>) {
+    let mut checks = BTreeMap::new();
+    // 收到 SIGTERM 后先让探针失败，负载均衡摘掉本实例后再停止接受连接（见 main.rs）
+    let shutdown = if st.shutdown.is_cancelled() { fail("shutting down") } else { ok(None) };
+    checks.insert("shutdown".to_string(), shutdown);
+    checks.insert("database".to_string(), check_database(&st.db).await);
+    checks.insert("migrations".to_string(), check_migrations(&st.db).await);
+    checks.insert("outbox".to_string(), check_outbox(&st).await);
+
+    let ready = checks.values().all(|c| c.status == CheckStatus::Ok);
+    let (code, status) = if ready { (StatusCode::OK, CheckStatus::Ok) } else { (StatusCode::SERVICE_UNAVAILABLE, CheckStatus::Fail) };
+    (code, Json(ReadinessView { status, checks }))
+}
+
+async fn check_database(db: &infra::Db) -> CheckView {
+    let start = Instant::now();
+    match timed(infra::ping(db)).await {
+        Ok(()) => ok(Some(format!("{}ms", start.elapsed().as_millis()))),
+        Err(e) => fail(&e),
+    }
+}
+
+/// 库里的迁移落后于本二进制时不接流量；领先是滚动发布中新版本已迁移，旧实例照常服务
+async fn check_migrations(db: &infra::Db) -> CheckView {
+    match timed(infra::migration_status(db)).await {
+        Ok(s) => {
+            let detail = format!("applied {}, expected {}", s.applied.unwrap_or(0), s.expected);
+            if s.applied.unwrap_or(0) >= s.expected { ok(Some(detail)) } else { fail(&detail) }
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+/// 积压说明投递跟不上或下游不可用；死信只在 detail 里展示，不影响就绪
+async fn check_outbox(st: &AppState) -> CheckView {
+    match timed(st.outbox.stats()).await {
+        Ok(s) => {
+            let detail = format!("{} pending, {} dead", s.pending, s.dead);
+            if s.pending <= st.cfg.outbox.max_pending { ok(Some(detail)) } else { fail(&detail) }
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+async fn timed<T, E: std::fmt::Display>(fut: impl Future<Output = Result<T, E>>) -> Result<T, String> {
+    match tokio::time::timeout(CHECK_TIMEOUT, fut).await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => {
+            tracing::warn!(err = %e, "readiness check failed");
+            Err("unavailable".to_string())
+        }
+        Err(_) => Err("timed out".to_string()),
+    }
+}
+
+fn ok(detail: Option<String>) -> CheckView {
+    CheckView { status: CheckStatus::Ok, detail }
+}
+
+fn fail(detail: &str) -> CheckView {
+    CheckView { status: CheckStatus::Fail, detail: Some(detail.to_string()) }
+}
@@ -0,0 +1,178 @@
+//! `Idempotency-Key`（第 15.4 节的幂等守卫落到 HTTP 层）：登录后的 POST/PATCH/DELETE 带上这个头时，
+//! 同一用户的同一个键只执行一次，之后的重试原样重放第一次的响应（附 `Idempotent-Replayed: true`）。
+//!
+//! - 同一个键换了方法、路径或请求体：422 `idempotency_key_reused`
+//! - 第一次请求还没执行完：409 `idempotency_request_in_progress`，客户端稍后重试
+//! - 5xx 不保存，重试会重新执行；4xx 与成功响应一样保存并重放
+//!
+//! 只挂在登录后的路由上，且不包括 `/api/v1/api-keys`：`/api/v1/auth/*` 与创建访问令牌的响应里是令牌，不应该落库。
+//! 创建访问令牌时带这个头会得到 400 `idempotency_not_supported`，而不是被悄悄忽略。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use app_core::{IdempotencyBegin, IdempotencyRecord, IdempotencyStore, RepoResult, StoredResponse};
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use sha2::{Digest, Sha256};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::auth::Claims;
+use crate::config::{IdempotencyCfg, IdempotencyStoreKind};
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+const MAX_KEY_LEN: usize = 255;
+/// 与 axum `Json` 提取器的默认上限相同
+const MAX_BODY: usize = 2 * 1024 * 1024;
+
+/// 进程内存储，单实例部署与测试用；多实例时各实例互不可见，应使用 `db`
+#[derive(Default)]
+pub struct InMemoryIdempotencyStore {
+    entries: Mutex<HashMap<(Uuid, String), (IdempotencyRecord, OffsetDateTime)>>,
+}
+
+#[async_trait::async_trait]
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    async fn begin(&self, user_id: Uuid, key: &str, fingerprint: &str, locked_until: OffsetDateTime) -> RepoResult<IdempotencyBegin> {
+        let mut entries = self.entries.lock().expect("idempotency mutex poisoned");
+        let now = OffsetDateTime::now_utc();
+        match entries.get(&(user_id, key.to_string())) {
+            Some((record, expires_at)) if *expires_at > now => Ok(IdempotencyBegin::Existing(record.clone())),
+            _ => {
+                let record = IdempotencyRecord { fingerprint: fingerprint.to_string(), response: None };
+                entries.insert((user_id, key.to_string()), (record, locked_until));
+                Ok(IdempotencyBegin::Started)
+            }
+        }
+    }
+
+    async fn complete(&self, user_id: Uuid, key: &str, response: &StoredResponse, expires_at: OffsetDateTime) -> RepoResult<()> {
+        let mut entries = self.entries.lock().expect("idempotency mutex poisoned");
+        if let Some(entry) = entries.get_mut(&(user_id, key.to_string())) {
+            entry.0.response = Some(response.clone());
+            entry.1 = expires_at;
+        }
+        Ok(())
+    }
+
+    async fn release(&self, user_id: Uuid, key: &str) -> RepoResult<()> {
+        let mut entries = self.entries.lock().expect("idempotency mutex poisoned");
+        let id = (user_id, key.to_string());
+        if entries.get(&id).is_some_and(|(record, _)| record.response.is_none()) {
+            entries.remove(&id);
+        }
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> RepoResult<u64> {
+        let mut entries = self.entries.lock().expect("idempotency mutex poisoned");
+        let before = entries.len();
+        let now = OffsetDateTime::now_utc();
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+        Ok((before - entries.len()) as u64)
+    }
+}
+
+/// 按配置选出存储；`db` 用 infra 提供的实现
+pub fn store(cfg: &IdempotencyCfg, db: Arc<dyn IdempotencyStore>) -> Arc<dyn IdempotencyStore> {
+    match cfg.store {
+        IdempotencyStoreKind::Db => db,
+        IdempotencyStoreKind::Memory => Arc::new(InMemoryIdempotencyStore::default()),
+    }
+}
+
+/// 须挂在 `auth_mw` 之内、限流之内：被限流拒绝的请求不占用键
+pub async fn idempotency_mw(State(st): State<AppState>, req: Request, next: Next) -> AppResult<Response> {
+    if !matches!(*req.method(), Method::POST | Method::PATCH | Method::DELETE) {
+        return Ok(next.run(req).await);
+    }
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY) else { return Ok(next.run(req).await) };
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|k| (1..=MAX_KEY_LEN).contains(&k.len()) && k.bytes().all(|b| b.is_ascii_graphic()))
+        .ok_or_else(|| AppError::BadRequest {
+            code: "invalid_idempotency_key",
+            message: format!("Idempotency-Key must be 1-{MAX_KEY_LEN} visible ASCII characters"),
+        })?
+        .to_string();
+    // 没有 Claims 的请求交给处理器按 401 处理
+    let Some(user_id) = req.extensions().get::<Claims>().map(|c| c.sub) else { return Ok(next.run(req).await) };
+
+    let (parts, body) = req.into_parts();
+    let body = to_bytes(body, MAX_BODY)
+        .await
+        .map_err(|e| AppError::BadRequest { code: "invalid_body", message: e.to_string() })?;
+    let fingerprint = fingerprint(&parts.method, parts.uri.path_and_query().map_or("", |p| p.as_str()), &body);
+
+    let cfg = &st.cfg.idempotency;
+    let now = OffsetDateTime::now_utc();
+    match st.idempotency.begin(user_id, &key, &fingerprint, now + Duration::seconds(cfg.lock_secs)).await? {
+        // 还在执行时先报 409：客户端稍后重试，届时再比对指纹
+        IdempotencyBegin::Existing(IdempotencyRecord { response: None, .. }) => {
+            Err(AppError::Conflict { code: "idempotency_request_in_progress" })
+        }
+        IdempotencyBegin::Existing(record) if record.fingerprint != fingerprint => {
+            Err(AppError::Unprocessable { code: "idempotency_key_reused" })
+        }
+        IdempotencyBegin::Existing(IdempotencyRecord { response: Some(stored), .. }) => {
+            metrics::counter!("http_idempotent_replays_total").increment(1);
+            Ok(replay(stored))
+        }
+        IdempotencyBegin::Started => {
+            let resp = next.run(Request::from_parts(parts, Body::from(body))).await;
+            if resp.status().is_server_error() {
+                st.idempotency.release(user_id, &key).await?;
+                return Ok(resp);
+            }
+            let (parts, body) = resp.into_parts();
+            let body = to_bytes(body, MAX_BODY).await.map_err(|e| AppError::Internal(e.into()))?;
+            let stored = StoredResponse {
+                status: parts.status.as_u16(),
+                headers: parts
+                    .headers
+                    .iter()
+                    .filter(|(name, _)| *name != header::CONTENT_LENGTH)
+                    .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                    .collect(),
+                body: body.to_vec(),
+            };
+            // 已经执行成功了，保存失败不能让客户端以为没执行；记日志，键在 lock_secs 后释放
+            let expires_at = OffsetDateTime::now_utc() + Duration::seconds(cfg.ttl_secs);
+            if let Err(e) = st.idempotency.complete(user_id, &key, &stored, expires_at).await {
+                tracing::error!(err = ?e, %user_id, "saving idempotent response failed");
+            }
+            Ok(Response::from_parts(parts, Body::from(body)))
+        }
+    }
+}
+
+fn fingerprint(method: &Method, path_and_query: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str().as_bytes());
+    hasher.update(b" ");
+    hasher.update(path_and_query.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hasher.finalize().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut resp = Response::new(Body::from(stored.body));
+    *resp.status_mut() = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let headers = resp.headers_mut();
+    for (name, value) in &stored.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name.as_str()), HeaderValue::try_from(value.as_str())) {
+            headers.append(name, value);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    resp
+}
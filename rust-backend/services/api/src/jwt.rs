@@ -0,0 +1,149 @@
+//! 访问令牌的签名密钥集：RS256/EdDSA，多把密钥以 `kid` 区分。
+//!
+//! 轮换按配置中的时间表进行：已生效（`not_before` 已过）且未退役的密钥里，`not_before`
+//! 最新的一把负责签发；其余未退役的密钥继续验签，并和尚未生效的新密钥一起出现在 JWKS 中，
+//! 让其他服务提前缓存。签发密钥要求在令牌过期前不会退役，所以新密钥的 `not_before`
+//! 至少要比旧密钥的 `retire_at` 早一个访问令牌有效期。
+
+use anyhow::{bail, Context};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use dto::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use simple_asn1::{oid, ASN1Block};
+use time::{Duration, OffsetDateTime};
+
+use crate::config::{JwtAlg, JwtKeyCfg};
+use crate::error::{AppError, AppResult};
+
+struct SigningKey {
+    kid: String,
+    alg: Algorithm,
+    /// 只用于验签的旧密钥可以不配私钥
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    jwk: Jwk,
+    not_before: Option<OffsetDateTime>,
+    retire_at: Option<OffsetDateTime>,
+}
+
+impl SigningKey {
+    fn retired(&self, at: OffsetDateTime) -> bool {
+        self.retire_at.is_some_and(|t| t <= at)
+    }
+}
+
+pub struct JwtKeys {
+    keys: Vec<SigningKey>,
+    ttl: Duration,
+}
+
+impl JwtKeys {
+    /// 读取密钥文件；当前没有可用的签发密钥时启动失败
+    pub fn from_cfg(keys: &[JwtKeyCfg], ttl: Duration) -> anyhow::Result<Self> {
+        let keys = keys.iter().map(load_key).collect::<anyhow::Result<Vec<_>>>()?;
+        let set = Self { keys, ttl };
+        if set.signing_key(OffsetDateTime::now_utc()).is_none() {
+            bail!("no active JWT signing key: need a key with a private key, not_before in the past and retire_at beyond one token lifetime");
+        }
+        Ok(set)
+    }
+
+    fn signing_key(&self, now: OffsetDateTime) -> Option<&SigningKey> {
+        self.keys
+            .iter()
+            .filter(|k| k.encoding.is_some())
+            .filter(|k| k.not_before.is_none_or(|t| t <= now) && !k.retired(now + self.ttl))
+            .max_by_key(|k| k.not_before)
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> AppResult<String> {
+        let key = self
+            .signing_key(OffsetDateTime::now_utc())
+            .ok_or_else(|| AppError::Internal(anyhow::anyhow!("no active JWT signing key")))?;
+        let header = Header { kid: Some(key.kid.clone()), ..Header::new(key.alg) };
+        let encoding = key.encoding.as_ref().expect("signing keys have a private key");
+        Ok(jsonwebtoken::encode(&header, claims, encoding)?)
+    }
+
+    /// 按头部的 `kid` 选密钥并只接受该密钥的算法；没有 `kid`、未知或已退役的密钥都按无效令牌处理
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> AppResult<T> {
+        let header = jsonwebtoken::decode_header(token)?;
+        let now = OffsetDateTime::now_utc();
+        let key = header
+            .kid
+            .and_then(|kid| self.keys.iter().find(|k| k.kid == kid))
+            .filter(|k| !k.retired(now))
+            .ok_or_else(|| AppError::unauthorized("invalid_token"))?;
+        Ok(jsonwebtoken::decode::<T>(token, &key.decoding, &Validation::new(key.alg))?.claims)
+    }
+
+    /// 未退役的公钥，包括尚未开始签发的新密钥
+    pub fn jwks(&self) -> JwkSet {
+        let now = OffsetDateTime::now_utc();
+        JwkSet { keys: self.keys.iter().filter(|k| !k.retired(now)).map(|k| k.jwk.clone()).collect() }
+    }
+}
+
+fn load_key(cfg: &JwtKeyCfg) -> anyhow::Result<SigningKey> {
+    let read = |path: &str| std::fs::read(path).with_context(|| format!("reading JWT key {path} (kid {})", cfg.kid));
+    let public_pem = read(&cfg.public_key)?;
+    let private_pem = cfg.private_key.as_deref().map(read).transpose()?;
+    let (alg, decoding, encoding) = match cfg.alg {
+        JwtAlg::RS256 => (
+            Algorithm::RS256,
+            DecodingKey::from_rsa_pem(&public_pem)?,
+            private_pem.map(|pem| EncodingKey::from_rsa_pem(&pem)).transpose()?,
+        ),
+        JwtAlg::EdDSA => (
+            Algorithm::EdDSA,
+            DecodingKey::from_ed_pem(&public_pem)?,
+            private_pem.map(|pem| EncodingKey::from_ed_pem(&pem)).transpose()?,
+        ),
+    };
+    let jwk = public_jwk(&cfg.kid, cfg.alg, &public_pem).with_context(|| format!("parsing public key of kid {}", cfg.kid))?;
+    Ok(SigningKey { kid: cfg.kid.clone(), alg, encoding, decoding, jwk, not_before: cfg.not_before, retire_at: cfg.retire_at })
+}
+
+/// 从 SPKI 公钥（`BEGIN PUBLIC KEY`）中取出 JWK 需要的分量：Ed25519 的 `x`，RSA 的 `n`/`e`
+fn public_jwk(kid: &str, alg: JwtAlg, pem: &[u8]) -> anyhow::Result<Jwk> {
+    let pem = pem::parse(pem)?;
+    let blocks = simple_asn1::from_der(pem.contents())?;
+    let Some(ASN1Block::Sequence(_, spki)) = blocks.first() else { bail!("not a SubjectPublicKeyInfo") };
+    let (Some(ASN1Block::Sequence(_, algo)), Some(ASN1Block::BitString(_, _, key))) = (spki.first(), spki.get(1)) else {
+        bail!("not a SubjectPublicKeyInfo");
+    };
+    let Some(ASN1Block::ObjectIdentifier(_, algo_oid)) = algo.first() else { bail!("missing algorithm identifier") };
+    let b64 = |bytes: &[u8]| URL_SAFE_NO_PAD.encode(bytes);
+    let jwk = |kty: &str, alg: &str| Jwk {
+        kty: kty.into(),
+        kid: kid.into(),
+        alg: alg.into(),
+        use_: "sig".into(),
+        crv: None,
+        x: None,
+        n: None,
+        e: None,
+    };
+    match alg {
+        JwtAlg::EdDSA => {
+            if *algo_oid != oid!(1, 3, 101, 112) || key.len() != 32 {
+                bail!("expected an Ed25519 public key");
+            }
+            Ok(Jwk { crv: Some("Ed25519".into()), x: Some(b64(key)), ..jwk("OKP", "EdDSA") })
+        }
+        JwtAlg::RS256 => {
+            if *algo_oid != oid!(1, 2, 840, 113549, 1, 1, 1) {
+                bail!("expected an RSA public key");
+            }
+            let rsa = simple_asn1::from_der(key)?;
+            let Some(ASN1Block::Sequence(_, parts)) = rsa.first() else { bail!("malformed RSA public key") };
+            let (Some(ASN1Block::Integer(_, n)), Some(ASN1Block::Integer(_, e))) = (parts.first(), parts.get(1)) else {
+                bail!("malformed RSA public key");
+            };
+            Ok(Jwk { n: Some(b64(&n.to_bytes_be().1)), e: Some(b64(&e.to_bytes_be().1)), ..jwk("RSA", "RS256") })
+        }
+    }
+}
@@ -0,0 +1,28 @@
+pub mod account;
+pub mod admin;
+pub mod api_keys;
+pub mod audit;
+pub mod auth;
+pub mod authz;
+pub mod config;
+pub mod cursor;
+pub mod error;
+pub mod grpc;
+pub mod health;
+pub mod idempotency;
+pub mod jwt;
+pub mod logging;
+pub mod mailer;
+pub mod metrics;
+pub mod notifier;
+pub mod oidc;
+pub mod openapi;
+pub mod outbox;
+pub mod rate_limit;
+pub mod request_id;
+pub mod routes;
+pub mod scheduler;
+pub mod shutdown;
+pub mod state;
+pub mod stream;
+pub mod tasks;
@@ -0,0 +1,12 @@
+use tracing_subscriber::{EnvFilter, Layer, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+
+pub fn init(json: bool, level: &str) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| level.parse().unwrap_or_else(|_| EnvFilter::new("info")));
+    let fmt_layer = if json {
+        fmt::layer().with_target(false).json().boxed()
+    } else {
+        fmt::layer().with_target(true).pretty().boxed()
+    };
+    tracing_subscriber::registry().with(filter).with(fmt_layer).init();
+}
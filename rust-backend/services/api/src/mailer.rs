@@ -0,0 +1,119 @@
+//! 发邮件：`Mailer` 只关心“把这封信交出去”，模板与链接由调用方（account.rs）准备。
+//! 生产用 SMTP；本地开发把邮件写成 .eml 文件，测试留在内存里断言。
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use time::OffsetDateTime;
+
+use crate::config::{MailCfg, MailerKind, SmtpCfg, SmtpTls};
+
+/// 纯文本邮件
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &Email) -> anyhow::Result<()>;
+}
+
+/// 留在进程内存里，测试里用来取出邮件中的链接
+#[derive(Default)]
+pub struct InMemoryMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl InMemoryMailer {
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().expect("mailer mutex poisoned").clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for InMemoryMailer {
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        self.sent.lock().expect("mailer mutex poisoned").push(email.clone());
+        Ok(())
+    }
+}
+
+/// 每封邮件一个 .eml 文件，文件名以毫秒时间戳开头，按名称排序即发送顺序
+pub struct FileMailer {
+    dir: PathBuf,
+    from: Mailbox,
+}
+
+impl FileMailer {
+    pub fn open(dir: impl Into<PathBuf>, from: Mailbox) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating mail directory {}", dir.display()))?;
+        Ok(Self { dir, from })
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        let message = message(&self.from, email)?;
+        let millis = OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000;
+        let path = self.dir.join(format!("{millis}-{}.eml", uuid::Uuid::new_v4()));
+        tokio::fs::write(&path, message.formatted()).await.with_context(|| format!("writing {}", path.display()))?;
+        Ok(())
+    }
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// 只组装连接参数，不连服务器；连接池在第一次发送时建立
+    pub fn new(cfg: &SmtpCfg, from: Mailbox) -> anyhow::Result<Self> {
+        let builder = match cfg.tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&cfg.host),
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&cfg.host)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&cfg.host)?,
+        };
+        let mut builder = builder.port(cfg.port);
+        if let (Some(user), Some(password)) = (&cfg.username, &cfg.password) {
+            builder = builder.credentials(Credentials::new(user.clone(), password.clone()));
+        }
+        Ok(Self { transport: builder.build(), from })
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        self.transport.send(message(&self.from, email)?).await?;
+        Ok(())
+    }
+}
+
+fn message(from: &Mailbox, email: &Email) -> anyhow::Result<Message> {
+    let to: Mailbox = email.to.parse().with_context(|| format!("invalid recipient {:?}", email.to))?;
+    Ok(Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(&email.subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(email.body.clone())?)
+}
+
+pub fn mailer(cfg: &MailCfg) -> anyhow::Result<Arc<dyn Mailer>> {
+    let from: Mailbox = cfg.from.parse().with_context(|| format!("invalid mail.from {:?}", cfg.from))?;
+    Ok(match cfg.mailer {
+        MailerKind::Memory => Arc::new(InMemoryMailer::default()),
+        MailerKind::File => Arc::new(FileMailer::open(&cfg.file_dir, from)?),
+        MailerKind::Smtp => Arc::new(SmtpMailer::new(&cfg.smtp, from)?),
+    })
+}
@@ -0,0 +1,29 @@
+use services_api::{config, grpc, logging, metrics, outbox, routes::router, scheduler, shutdown, state::build_state, tasks};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cfg = config::load()?;
+    logging::init(cfg.log.json, &cfg.log.level);
+    let st = build_state(cfg).await?;
+
+    // 后台任务在退出时统一取消，等它们跑完当前一轮再关连接池
+    let tasks_cancel = CancellationToken::new();
+    let mut bg = JoinSet::new();
+    bg.spawn(scheduler::run_scheduler(st.clone(), tasks_cancel.clone()));
+    bg.spawn(tasks::run_local_cleanup(st.clone(), tasks_cancel.clone()));
+    bg.spawn(outbox::run_outbox_dispatcher(st.clone(), tasks_cancel.clone()));
+    bg.spawn(metrics::run_upkeep(st.metrics.clone(), tasks_cancel.clone()));
+
+    // gRPC 单独监听；与后台任务一起在 HTTP 排空后停止
+    let grpc_listener = tokio::net::TcpListener::bind(&st.cfg.grpc.addr).await?;
+    tracing::info!(addr = %grpc_listener.local_addr()?, "grpc listening");
+    bg.spawn(grpc::serve(grpc_listener, st.clone(), tasks_cancel.clone()));
+
+    // axum 0.7 移除了 axum::Server，改为 tokio 监听 + axum::serve；
+    // 连接带上对端地址，登录失败计数按 IP 累计时使用
+    let listener = tokio::net::TcpListener::bind(&st.cfg.server.addr).await?;
+    tracing::info!(addr = %listener.local_addr()?, "listening");
+    shutdown::serve(listener, router(st.clone()), st, bg, tasks_cancel).await
+}
@@ -0,0 +1,89 @@
+//! Prometheus 指标。记录统一走 `metrics` 门面，导出器作为全局 recorder 安装，`/metrics` 渲染文本格式。
+//!
+//! - RED：`http_requests_total`、`http_request_errors_total`（5xx）、`http_request_duration_seconds`，
+//!   按 method 与匹配到的路由模板（如 `/api/v1/todos/:id`）打标签，避免把路径参数变成高基数标签；
+//! - 连接池：`db_pool_connections{state}`、`db_pool_max_connections`、`db_pool_acquire_wait_seconds`，抓取时采样；
+//! - 计划任务：`maintenance_runs_total{job,outcome}`、`maintenance_deleted_total{job}`（见 tasks.rs）。
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::state::AppState;
+
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// 抓取时借一个连接测等待时间；池耗尽时最多等这么久，算一次失败
+const ACQUIRE_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 全局 recorder 只能装一次，同一进程里的多个 `AppState`（如测试）共用
+pub fn handle() -> PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Suffix("_seconds".into()), LATENCY_BUCKETS)
+                .expect("latency buckets are not empty")
+                .install_recorder()
+                .expect("no other metrics recorder is installed")
+        })
+        .clone()
+}
+
+/// 直方图样本先缓存在 recorder 里，需要定期归并；没有抓取时也不会无限增长
+pub async fn run_upkeep(handle: PrometheusHandle, cancel: CancellationToken) {
+    let mut tick = tokio::time::interval(Duration::from_secs(5));
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tick.tick() => handle.run_upkeep(),
+        }
+    }
+}
+
+/// 须通过 `Router::layer` 挂载：路由匹配之后才拿得到 `MatchedPath`；未匹配的请求归为 `unmatched`
+pub async fn metrics_mw(matched: Option<MatchedPath>, req: Request, next: Next) -> Response {
+    let route = matched.map_or_else(|| "unmatched".to_string(), |p| p.as_str().to_string());
+    let method = req.method().to_string();
+    let start = Instant::now();
+    let resp = next.run(req).await;
+    let status = resp.status();
+
+    metrics::histogram!("http_request_duration_seconds", "method" => method.clone(), "route" => route.clone())
+        .record(start.elapsed());
+    let labels = [("method", method), ("route", route), ("status", status.as_str().to_string())];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    if status.is_server_error() {
+        metrics::counter!("http_request_errors_total", &labels).increment(1);
+    }
+    resp
+}
+
+/// 不经过鉴权（见 `auth_mw`），部署时应只对内网或抓取方开放
+pub async fn metrics_handler(State(st): State<AppState>) -> impl IntoResponse {
+    record_pool(&st.db).await;
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], st.metrics.render())
+}
+
+async fn record_pool(db: &infra::Db) {
+    let size = db.size();
+    let idle = db.num_idle() as u32;
+    metrics::gauge!("db_pool_connections", "state" => "idle").set(idle);
+    metrics::gauge!("db_pool_connections", "state" => "active").set(size.saturating_sub(idle));
+    metrics::gauge!("db_pool_max_connections").set(db.options().get_max_connections());
+
+    let start = Instant::now();
+    match tokio::time::timeout(ACQUIRE_PROBE_TIMEOUT, db.acquire()).await {
+        Ok(Ok(_conn)) => metrics::gauge!("db_pool_acquire_wait_seconds").set(start.elapsed()),
+        Ok(Err(e)) => {
+            tracing::warn!(err = %e, "pool probe failed");
+            metrics::counter!("db_pool_acquire_failures_total").increment(1);
+        }
+        Err(_) => metrics::counter!("db_pool_acquire_failures_total").increment(1),
+    }
+}
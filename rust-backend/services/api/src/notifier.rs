@@ -0,0 +1,86 @@
+//! 提醒的去向：调度器（scheduler.rs）在提醒时间到了之后调用 `Notifier`，`[scheduler] notifier` 选择实现。
+//! 推送、短信之类的渠道实现这个 trait 即可。
+
+use std::sync::{Arc, Mutex};
+
+use app_core::Todo;
+
+use crate::config::{NotifierKind, SchedulerCfg};
+use crate::mailer::{Email, Mailer};
+
+/// 一条到点的提醒；`email` 是 todo 主人的邮箱
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub email: String,
+    pub todo: Todo,
+}
+
+#[async_trait::async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, notification: &Notification) -> anyhow::Result<()>;
+}
+
+/// 留在进程内存里，测试里用来断言提醒了什么、提醒了几次
+#[derive(Default)]
+pub struct InMemoryNotifier {
+    sent: Mutex<Vec<Notification>>,
+}
+
+impl InMemoryNotifier {
+    pub fn sent(&self) -> Vec<Notification> {
+        self.sent.lock().expect("notifier mutex poisoned").clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for InMemoryNotifier {
+    async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        self.sent.lock().expect("notifier mutex poisoned").push(notification.clone());
+        Ok(())
+    }
+}
+
+/// 只写日志，本地开发时确认调度在工作
+pub struct LogNotifier;
+
+#[async_trait::async_trait]
+impl Notifier for LogNotifier {
+    async fn notify(&self, n: &Notification) -> anyhow::Result<()> {
+        tracing::info!(user_id = %n.todo.user_id, todo_id = %n.todo.id, title = n.todo.title, "todo reminder");
+        Ok(())
+    }
+}
+
+pub struct MailNotifier {
+    mailer: Arc<dyn Mailer>,
+}
+
+impl MailNotifier {
+    pub fn new(mailer: Arc<dyn Mailer>) -> Self {
+        Self { mailer }
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for MailNotifier {
+    async fn notify(&self, n: &Notification) -> anyhow::Result<()> {
+        let due = match n.todo.due_at {
+            Some(due) => format!("It is due at {}.", dto::fmt_time(due)),
+            None => "It has no due date.".to_string(),
+        };
+        let email = Email {
+            to: n.email.clone(),
+            subject: format!("Reminder: {}", n.todo.title),
+            body: format!("This is your reminder for \"{}\". {due}\n", n.todo.title),
+        };
+        self.mailer.send(&email).await
+    }
+}
+
+pub fn notifier(cfg: &SchedulerCfg, mailer: Arc<dyn Mailer>) -> Arc<dyn Notifier> {
+    match cfg.notifier {
+        NotifierKind::Memory => Arc::new(InMemoryNotifier::default()),
+        NotifierKind::Log => Arc::new(LogNotifier),
+        NotifierKind::Mail => Arc::new(MailNotifier::new(mailer)),
+    }
+}
@@ -0,0 +1,361 @@
+//! OIDC 登录：授权码 + PKCE（RFC 7636），对接一个在 `[oidc]` 里配置的身份提供方。
+//!
+//! `GET /api/v1/auth/oidc/authorize` 生成 `state`、`nonce` 与 PKCE 校验码并记入库，同时把 `state` 写进 cookie，302 跳到提供方；
+//! 提供方跳回前端的 `redirect_uri`，前端把 `code` 与 `state` 交给 `POST /api/v1/auth/oidc/callback`（同源请求，带上 cookie），
+//! 这里用校验码换取 ID 令牌、按提供方 JWKS 验签，再按 `iss` + `sub` 找到或绑定本地用户，签发普通会话。
+//! 发现文档与 JWKS 缓存在进程内，遇到未知的 `kid` 时重新拉取（提供方轮换了密钥）。
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Context;
+use app_core::OidcLogin;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use dto::{AuthResp, ErrorBody, OidcCallbackReq};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use time::{Duration, OffsetDateTime};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::audit::AuditCtx;
+use crate::auth::{random_token, start_session};
+use crate::config::OidcCfg;
+use crate::error::{AppError, AppJson, AppResult};
+use crate::state::AppState;
+
+/// 发现文档与 JWKS 的缓存时间
+const PROVIDER_TTL: Duration = Duration::hours(1);
+/// 因未知 `kid` 强制刷新的最小间隔，伪造的令牌不能让我们反复请求提供方
+const MIN_REFRESH_INTERVAL: Duration = Duration::minutes(1);
+const HTTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// 值就是 `state`。回调时对不上说明登录不是这个浏览器发起的：攻击者可以把自己的 `code`/`state`
+/// 塞给受害者的浏览器，让受害者登进攻击者的账号（登录 CSRF）
+const STATE_COOKIE: &str = "oidc_state";
+
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// 提供方 JWKS 里的一把公钥；只认签名用的 RSA、EC（P-256/P-384）与 Ed25519
+#[derive(Deserialize)]
+struct RawJwk {
+    kty: String,
+    kid: Option<String>,
+    alg: Option<String>,
+    #[serde(rename = "use")]
+    use_: Option<String>,
+    crv: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawJwkSet {
+    keys: Vec<RawJwk>,
+}
+
+struct ProviderKey {
+    kid: Option<String>,
+    alg: Algorithm,
+    key: DecodingKey,
+}
+
+struct Provider {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    keys: Vec<ProviderKey>,
+    fetched_at: OffsetDateTime,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// ID 令牌里用到的声明；`iss`、`aud`、`exp` 由 `Validation` 检查
+#[derive(Deserialize)]
+struct IdClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+pub struct OidcClient {
+    cfg: OidcCfg,
+    issuer: String,
+    http: reqwest::Client,
+    provider: RwLock<Option<Arc<Provider>>>,
+}
+
+impl OidcClient {
+    /// 未配置 `issuer` 时返回 `None`；启动时不访问提供方，第一次登录时才拉取发现文档
+    pub fn from_cfg(cfg: &OidcCfg) -> anyhow::Result<Option<Self>> {
+        let Some(issuer) = cfg.issuer.as_deref().filter(|i| !i.is_empty()) else { return Ok(None) };
+        let http = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?;
+        Ok(Some(Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            cfg: cfg.clone(),
+            http,
+            provider: RwLock::new(None),
+        }))
+    }
+
+    /// `force` 用于未知 `kid`：距上次拉取不足 `MIN_REFRESH_INTERVAL` 时仍用缓存
+    async fn provider(&self, force: bool) -> AppResult<Arc<Provider>> {
+        let now = OffsetDateTime::now_utc();
+        let fresh = |p: &Provider| {
+            let age = now - p.fetched_at;
+            age < PROVIDER_TTL && (!force || age < MIN_REFRESH_INTERVAL)
+        };
+        if let Some(p) = self.provider.read().await.as_ref().filter(|p| fresh(p)) {
+            return Ok(p.clone());
+        }
+        let mut cached = self.provider.write().await;
+        // 等写锁期间别的请求可能已经刷新过
+        if let Some(p) = cached.as_ref().filter(|p| fresh(p)) {
+            return Ok(p.clone());
+        }
+        let p = Arc::new(self.fetch_provider().await?);
+        *cached = Some(p.clone());
+        Ok(p)
+    }
+
+    async fn fetch_provider(&self) -> anyhow::Result<Provider> {
+        let url = format!("{}/.well-known/openid-configuration", self.issuer);
+        let discovery: Discovery = self.get_json(&url).await?;
+        // OIDC Discovery 要求两者完全一致，防止被引到别的提供方
+        if discovery.issuer.trim_end_matches('/') != self.issuer {
+            anyhow::bail!("discovery document issuer {:?} does not match configured issuer", discovery.issuer);
+        }
+        let jwks: RawJwkSet = self.get_json(&discovery.jwks_uri).await?;
+        let keys: Vec<_> = jwks.keys.iter().filter_map(decoding_key).collect();
+        tracing::info!(issuer = %self.issuer, keys = keys.len(), "fetched OIDC provider metadata");
+        Ok(Provider {
+            authorization_endpoint: discovery.authorization_endpoint,
+            token_endpoint: discovery.token_endpoint,
+            keys,
+            fetched_at: OffsetDateTime::now_utc(),
+        })
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> anyhow::Result<T> {
+        let resp = self.http.get(url).send().await.with_context(|| format!("fetching {url}"))?;
+        resp.error_for_status()?.json().await.with_context(|| format!("parsing {url}"))
+    }
+
+    async fn authorize_url(&self, login: &OidcLogin) -> AppResult<String> {
+        let provider = self.provider(false).await?;
+        let mut url = reqwest::Url::parse(&provider.authorization_endpoint)
+            .context("invalid authorization_endpoint")
+            .map_err(AppError::Internal)?;
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(login.code_verifier.as_bytes()));
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.cfg.client_id)
+            .append_pair("redirect_uri", &self.cfg.redirect_uri)
+            .append_pair("scope", &self.cfg.scopes.join(" "))
+            .append_pair("state", &login.state)
+            .append_pair("nonce", &login.nonce)
+            .append_pair("code_challenge", &challenge)
+            .append_pair("code_challenge_method", "S256");
+        Ok(url.into())
+    }
+
+    /// 用授权码与 PKCE 校验码换取 ID 令牌；提供方拒绝时是 401 `oidc_code_rejected`
+    async fn exchange(&self, code: &str, code_verifier: &str) -> AppResult<String> {
+        let provider = self.provider(false).await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.cfg.redirect_uri),
+            ("client_id", &self.cfg.client_id),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.cfg.client_secret {
+            form.push(("client_secret", secret));
+        }
+        let resp = self.http.post(&provider.token_endpoint).form(&form).send().await.context("calling token endpoint")?;
+        if resp.status().is_client_error() {
+            let body = resp.text().await.unwrap_or_default();
+            tracing::warn!(%body, "OIDC token endpoint rejected the authorization code");
+            return Err(AppError::unauthorized("oidc_code_rejected"));
+        }
+        let token: TokenResponse = resp.error_for_status().context("calling token endpoint")?.json().await.context("parsing token response")?;
+        Ok(token.id_token)
+    }
+
+    /// 验签并检查 `iss`、`aud`、`exp` 与 `nonce`；任何不符都是 401 `invalid_id_token`
+    async fn validate(&self, id_token: &str, nonce: &str) -> AppResult<IdClaims> {
+        let invalid = || AppError::unauthorized("invalid_id_token");
+        let header = jsonwebtoken::decode_header(id_token).map_err(|_| invalid())?;
+        let find = |p: &Provider| {
+            p.keys
+                .iter()
+                .find(|k| k.alg == header.alg && (header.kid.is_none() || k.kid == header.kid))
+                .map(|k| (k.alg, k.key.clone()))
+        };
+        let (alg, key) = match find(&*self.provider(false).await?) {
+            Some(found) => found,
+            None => find(&*self.provider(true).await?).ok_or_else(invalid)?,
+        };
+        let mut validation = Validation::new(alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.cfg.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = jsonwebtoken::decode::<IdClaims>(id_token, &key, &validation).map_err(|e| {
+            tracing::warn!(err = %e, "invalid OIDC ID token");
+            invalid()
+        })?;
+        if claims.claims.nonce.as_deref() != Some(nonce) {
+            return Err(invalid());
+        }
+        Ok(claims.claims)
+    }
+}
+
+fn decoding_key(jwk: &RawJwk) -> Option<ProviderKey> {
+    if jwk.use_.as_deref().is_some_and(|u| u != "sig") {
+        return None;
+    }
+    let (alg, key) = match (jwk.kty.as_str(), jwk.crv.as_deref()) {
+        ("RSA", _) => {
+            let alg = jwk.alg.as_deref().map_or(Ok(Algorithm::RS256), Algorithm::from_str).ok()?;
+            let key = DecodingKey::from_rsa_components(jwk.n.as_deref()?, jwk.e.as_deref()?).ok()?;
+            (alg, key)
+        }
+        ("EC", Some(crv @ ("P-256" | "P-384"))) => {
+            let alg = if crv == "P-256" { Algorithm::ES256 } else { Algorithm::ES384 };
+            (alg, DecodingKey::from_ec_components(jwk.x.as_deref()?, jwk.y.as_deref()?).ok()?)
+        }
+        ("OKP", Some("Ed25519")) => (Algorithm::EdDSA, DecodingKey::from_ed_components(jwk.x.as_deref()?).ok()?),
+        _ => return None,
+    };
+    Some(ProviderKey { kid: jwk.kid.clone(), alg, key })
+}
+
+fn client(st: &AppState) -> AppResult<&OidcClient> {
+    st.oidc_client.as_deref().ok_or(AppError::NotFound { code: "oidc_disabled" })
+}
+
+/// 浏览器直接访问：302 跳到身份提供方的授权页
+#[utoipa::path(get, path = "/api/v1/auth/oidc/authorize", tag = "auth", responses(
+    (status = 302, description = "跳转到身份提供方，`Location` 里带 `state`、`nonce` 与 PKCE 参数；`Set-Cookie` 把 `state` 绑定到这个浏览器"),
+    (status = 404, description = "`oidc_disabled`：未配置 `[oidc] issuer`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn oidc_authorize(State(st): State<AppState>) -> AppResult<Response> {
+    let client = client(&st)?;
+    let login = OidcLogin {
+        state: random_token(),
+        code_verifier: random_token(),
+        nonce: random_token(),
+        expires_at: OffsetDateTime::now_utc() + Duration::seconds(st.cfg.oidc.login_ttl_secs),
+    };
+    // 先拿到提供方地址：提供方不可用时不留下登录状态
+    let url = client.authorize_url(&login).await?;
+    st.oidc.begin_login(&login).await?;
+    let cookie = state_cookie(&st, &login.state, st.cfg.oidc.login_ttl_secs);
+    Ok((StatusCode::FOUND, [(header::LOCATION, url), (header::SET_COOKIE, cookie)]).into_response())
+}
+
+/// 只发往 OIDC 端点；`redirect_uri` 是 https 时加上 `Secure`
+fn state_cookie(st: &AppState, value: &str, max_age: i64) -> String {
+    let secure = if st.cfg.oidc.redirect_uri.starts_with("https://") { "; Secure" } else { "" };
+    format!("{STATE_COOKIE}={value}; Path=/api/v1/auth/oidc; Max-Age={max_age}; HttpOnly; SameSite=Lax{secure}")
+}
+
+fn cookie_state(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|c| c.trim().strip_prefix(STATE_COOKIE)?.strip_prefix('='))
+}
+
+/// 外部身份第一次登录时，按提供方验证过的邮箱绑定到已有账号，没有则新建一个（没有可用的密码，
+/// 需要时走重置密码设置）。已有账号的邮箱没验证过时，它的密码与会话一并作废。之后同一个身份总是登录同一个用户，即使提供方那边改了邮箱
+#[utoipa::path(post, path = "/api/v1/auth/oidc/callback", tag = "auth", request_body = OidcCallbackReq, responses(
+    (status = 200, body = AuthResp),
+    (status = 400, description = "`invalid_oidc_state`：`state` 未知、已用过、已过期，或与本浏览器 cookie 里的不同", body = ErrorBody),
+    (status = 401, description = "`oidc_code_rejected` 或 `invalid_id_token`", body = ErrorBody),
+    (status = 403, description = "`oidc_email_unverified` 或 `account_disabled`", body = ErrorBody),
+    (status = 404, description = "`oidc_disabled`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+pub async fn oidc_callback(
+    State(st): State<AppState>,
+    headers: HeaderMap,
+    audit: AuditCtx,
+    AppJson(req): AppJson<OidcCallbackReq>,
+) -> AppResult<Response> {
+    let session: AppResult<_> = async {
+        let client = client(&st)?;
+        let invalid_state = || AppError::BadRequest {
+            code: "invalid_oidc_state",
+            message: "login state is unknown, expired, already used or was started in another browser".into(),
+        };
+        // 先比对 cookie 再取出登录状态：别处送来的回调不会把它用掉
+        if cookie_state(&headers) != Some(req.state.as_str()) {
+            return Err(invalid_state());
+        }
+        let login = st.oidc.take_login(&req.state).await?.ok_or_else(invalid_state)?;
+        let id_token = client.exchange(&req.code, &login.code_verifier).await?;
+        let claims = client.validate(&id_token, &login.nonce).await?;
+        let (user_id, email) = link_account(&st, &client.issuer, claims).await?;
+        Ok((user_id, email.clone(), start_session(&st, user_id).await?))
+    }
+    .await;
+    let target = session.as_ref().ok().map(|(_, email, _)| email.clone());
+    let audit = match &session {
+        Ok((user_id, ..)) => audit.with_actor(*user_id),
+        Err(_) => audit,
+    };
+    audit.record(&st, "auth.oidc_login", target.as_deref(), &session).await;
+    let cleared = state_cookie(&st, "", 0);
+    Ok(([(header::SET_COOKIE, cleared)], Json(session?.2)).into_response())
+}
+
+/// 找到或建立外部身份对应的本地用户，返回用户 ID 与本地邮箱
+async fn link_account(st: &AppState, issuer: &str, id: IdClaims) -> AppResult<(Uuid, String)> {
+    if let Some(user_id) = st.oidc.find_identity(issuer, &id.sub).await? {
+        let access = st.users.access(user_id).await?.ok_or_else(|| AppError::unauthorized("invalid_credentials"))?;
+        return Ok((user_id, access.email));
+    }
+    // 只按提供方验证过的邮箱绑定，否则谁都能在提供方注册别人的邮箱来接管账号
+    let email = id.email.filter(|_| id.email_verified).ok_or(AppError::Forbidden { code: "oidc_email_unverified" })?;
+    let user_id = match st.users.find_by_email(&email).await? {
+        // 没验证过的本地账号可能是别人抢注的：不作废的话，抢注的人还能用自己设的密码和已有的会话登进来
+        Some((user_id, _)) => {
+            if !st.users.access(user_id).await?.is_some_and(|a| a.email_verified) {
+                let unusable = st.passwords.hash(&random_token())?;
+                st.users.set_password_hash(user_id, &unusable).await?;
+                st.sessions.revoke_user(user_id).await?;
+            }
+            user_id
+        }
+        None => {
+            let unusable = st.passwords.hash(&random_token())?;
+            st.users.create(&email, &unusable).await?
+        }
+    };
+    st.oidc.link_identity(user_id, issuer, &id.sub, &email).await?;
+    st.users.mark_email_verified(user_id).await?;
+    tracing::info!(%user_id, issuer, "linked OIDC identity");
+    Ok((user_id, email))
+}
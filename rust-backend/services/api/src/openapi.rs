@@ -0,0 +1,55 @@
+//! OpenAPI 3 规范：路径来自各处理器上的 `#[utoipa::path]`，模型来自 dto 的 `ToSchema`。
+//! 规范的快照提交在 `services/api/openapi.json`，接口变更会在评审的 diff 里直接体现。
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::{account, admin, api_keys, auth, oidc, routes, stream};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Todo API", description = "第 16 章的 Todo 服务", license(name = "Apache-2.0", identifier = "Apache-2.0")),
+    paths(
+        auth::register,
+        auth::login,
+        auth::refresh,
+        auth::logout,
+        account::verify_email,
+        account::resend_verification,
+        account::request_password_reset,
+        account::confirm_password_reset,
+        oidc::oidc_authorize,
+        oidc::oidc_callback,
+        api_keys::create_api_key,
+        api_keys::list_api_keys,
+        api_keys::revoke_api_key,
+        routes::jwks,
+        routes::create_todo,
+        routes::list_todos,
+        routes::update_todo,
+        routes::complete_todo,
+        routes::delete_todo,
+        stream::todo_stream,
+        admin::list_audit,
+        admin::list_users,
+        admin::disable_user,
+        admin::enable_user,
+        admin::revoke_sessions,
+        admin::list_user_todos,
+    ),
+    modifiers(&BearerAuth),
+    tags((name = "auth", description = "注册、登录与会话"), (name = "todos", description = "当前用户的 todo"), (name = "admin", description = "管理接口，按权限开放")),
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Outbox 投递（第 15.5 节）：仓储在业务事务里写入事件，这里的 dispatcher 定期领取一批，
+//! 交给 `EventPublisher` 发布后标记为已投递。
+//!
+//! 投递是至少一次：发布成功但标记前进程退出，事件会在租约到期后再发一次，消费方按事件 `id` 去重。
+//! 失败按指数退避重试，用完 `max_attempts` 转入死信（`status = 'dead'`），不再自动投递。
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use app_core::{OutboxEvent, OutboxRepo, RepoResult};
+use time::OffsetDateTime;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+use tokio::time::{interval, Duration};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{OutboxCfg, PublisherKind};
+use crate::state::AppState;
+
+/// 事件的去向。生产环境接 Kafka/NATS 时实现这个 trait 即可
+#[async_trait::async_trait]
+pub trait EventPublisher: Send + Sync {
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()>;
+}
+
+/// 发布到进程内存，测试里用来断言投递了哪些事件
+#[derive(Default)]
+pub struct InMemoryPublisher {
+    events: Mutex<Vec<OutboxEvent>>,
+}
+
+impl InMemoryPublisher {
+    pub fn events(&self) -> Vec<OutboxEvent> {
+        self.events.lock().expect("publisher mutex poisoned").clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl EventPublisher for InMemoryPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        self.events.lock().expect("publisher mutex poisoned").push(event.clone());
+        Ok(())
+    }
+}
+
+/// 每个事件一行 JSON，追加写入；本地开发时代替消息队列
+pub struct JsonlPublisher {
+    file: tokio::sync::Mutex<tokio::fs::File>,
+}
+
+impl JsonlPublisher {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening outbox jsonl file {}", path.display()))?;
+        Ok(Self { file: tokio::sync::Mutex::new(tokio::fs::File::from_std(file)) })
+    }
+}
+
+#[async_trait::async_trait]
+impl EventPublisher for JsonlPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// 广播给本进程内的订阅者（WebSocket 推送，见 stream.rs）；没有订阅者时直接丢弃
+pub struct BroadcastPublisher {
+    tx: broadcast::Sender<Arc<OutboxEvent>>,
+}
+
+impl BroadcastPublisher {
+    pub fn new(capacity: usize) -> Self {
+        Self { tx: broadcast::channel(capacity).0 }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<OutboxEvent>> {
+        self.tx.subscribe()
+    }
+}
+
+#[async_trait::async_trait]
+impl EventPublisher for BroadcastPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        let _ = self.tx.send(Arc::new(event.clone()));
+        Ok(())
+    }
+}
+
+/// 依次发布到多个去处，任一失败即整体失败并重试，之前成功的去处会再收到一次
+pub struct FanoutPublisher(pub Vec<Arc<dyn EventPublisher>>);
+
+#[async_trait::async_trait]
+impl EventPublisher for FanoutPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        for publisher in &self.0 {
+            publisher.publish(event).await?;
+        }
+        Ok(())
+    }
+}
+
+/// 按配置选出外部去处，再接上本进程的推送通道 `live`
+pub fn publisher(cfg: &OutboxCfg, live: Arc<BroadcastPublisher>) -> anyhow::Result<Arc<dyn EventPublisher>> {
+    let external: Arc<dyn EventPublisher> = match cfg.publisher {
+        PublisherKind::Memory => Arc::new(InMemoryPublisher::default()),
+        PublisherKind::Jsonl => Arc::new(JsonlPublisher::open(cfg.jsonl_path.as_ref())?),
+    };
+    Ok(Arc::new(FanoutPublisher(vec![external, live])))
+}
+
+/// 每个 `poll_interval_ms` 一轮；一批领满说明还有积压，接着领下一批。取消只在两批之间生效
+pub async fn run_outbox_dispatcher(st: AppState, cancel: CancellationToken) {
+    let cfg = &st.cfg.outbox;
+    let mut tick = interval(Duration::from_millis(cfg.poll_interval_ms));
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tick.tick() => {}
+        }
+        while !cancel.is_cancelled() {
+            match dispatch_once(st.outbox.as_ref(), st.publisher.as_ref(), cfg).await {
+                Ok(n) if n == cfg.batch_size as usize => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::error!(err = ?e, "outbox dispatch failed");
+                    break;
+                }
+            }
+        }
+    }
+    tracing::info!("outbox dispatcher stopped");
+}
+
+/// 领取并发布一批，返回领取条数。单个事件发布失败只影响它自己
+pub async fn dispatch_once(outbox: &dyn OutboxRepo, publisher: &dyn EventPublisher, cfg: &OutboxCfg) -> RepoResult<usize> {
+    let events = outbox.claim(cfg.batch_size, time::Duration::seconds(cfg.lease_secs)).await?;
+    for event in &events {
+        match publisher.publish(event).await {
+            Ok(()) => {
+                outbox.mark_dispatched(event.id).await?;
+                metrics::counter!("outbox_events_total", "outcome" => "dispatched").increment(1);
+            }
+            Err(e) if event.attempts >= cfg.max_attempts => {
+                tracing::error!(err = ?e, event_id = %event.id, event_type = event.event_type, attempts = event.attempts, "outbox event dead-lettered");
+                outbox.mark_dead(event.id, &format!("{e:#}")).await?;
+                metrics::counter!("outbox_events_total", "outcome" => "dead").increment(1);
+            }
+            Err(e) => {
+                tracing::warn!(err = ?e, event_id = %event.id, attempts = event.attempts, "outbox publish failed, will retry");
+                let retry_at = OffsetDateTime::now_utc() + backoff(cfg, event.attempts);
+                outbox.retry_later(event.id, &format!("{e:#}"), retry_at).await?;
+                metrics::counter!("outbox_events_total", "outcome" => "retry").increment(1);
+            }
+        }
+    }
+    Ok(events.len())
+}
+
+fn backoff(cfg: &OutboxCfg, attempts: i32) -> time::Duration {
+    let exp = u32::try_from(attempts.saturating_sub(1)).unwrap_or(0).min(30);
+    let secs = cfg.retry_base_secs.saturating_mul(1 << exp).min(cfg.retry_max_secs);
+    time::Duration::seconds(secs)
+}
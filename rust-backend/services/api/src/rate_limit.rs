@@ -0,0 +1,185 @@
+//! 按路由组限流：`/api/v1/auth/*` 按客户端 IP，登录后的接口按 `Claims.sub`。
+//!
+//! 用 governor 的 GCRA 实现，状态只在本进程内存里，多实例部署时每个实例各算各的。
+//! 每个响应都带 `RateLimit-Limit`/`RateLimit-Remaining`/`RateLimit-Reset`，
+//! 被拒绝时返回 429 `rate_limited` 并附 `Retry-After`。
+
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use governor::clock::{Clock, DefaultClock};
+use governor::middleware::StateInformationMiddleware;
+use governor::state::keyed::DefaultKeyedStateStore;
+use governor::{Quota, RateLimiter};
+use ipnet::IpNet;
+use uuid::Uuid;
+
+use crate::auth::Claims;
+use crate::config::{RateLimitCfg, RateLimitRule};
+use crate::error::AppError;
+use crate::state::AppState;
+
+const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+type Inner<K> = RateLimiter<K, DefaultKeyedStateStore<K>, DefaultClock, StateInformationMiddleware>;
+
+/// 一个路由组的限流器，`K` 为计数的键
+pub struct KeyedLimiter<K: Hash + Eq + Clone> {
+    group: &'static str,
+    quota: Quota,
+    inner: Inner<K>,
+}
+
+/// 一次检查的结果，用于填响应头
+struct Outcome {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    /// 配额完全恢复还要多久；被拒绝时即下一次可以放行的时间
+    reset: Duration,
+}
+
+impl<K: Hash + Eq + Clone> KeyedLimiter<K> {
+    pub fn new(group: &'static str, rule: &RateLimitRule) -> anyhow::Result<Self> {
+        let per_minute = NonZeroU32::new(rule.per_minute).with_context(|| format!("rate_limit.{group}.per_minute must be > 0"))?;
+        let burst = NonZeroU32::new(rule.burst).with_context(|| format!("rate_limit.{group}.burst must be > 0"))?;
+        let quota = Quota::per_minute(per_minute).allow_burst(burst);
+        Ok(Self { group, quota, inner: RateLimiter::keyed(quota).with_middleware::<StateInformationMiddleware>() })
+    }
+
+    fn check(&self, key: &K) -> Outcome {
+        let limit = self.quota.burst_size().get();
+        let interval = self.quota.replenish_interval();
+        match self.inner.check_key(key) {
+            Ok(snapshot) => {
+                let remaining = snapshot.remaining_burst_capacity();
+                Outcome { allowed: true, limit, remaining, reset: interval * (limit - remaining) }
+            }
+            Err(not_until) => {
+                let wait = not_until.wait_time_from(self.inner.clock().now());
+                Outcome { allowed: false, limit, remaining: 0, reset: wait }
+            }
+        }
+    }
+
+    /// 不需要响应头的入口（gRPC）用：被拒绝时返回还要等多少秒
+    pub fn try_acquire(&self, key: &K) -> Result<(), u64> {
+        let outcome = self.check(key);
+        if outcome.allowed {
+            return Ok(());
+        }
+        metrics::counter!("grpc_rate_limited_total", "group" => self.group).increment(1);
+        Err(ceil_secs(outcome.reset))
+    }
+
+    /// 丢掉已经恢复满配额的键，防止状态表随客户端数量无限增长
+    pub fn retain_recent(&self) {
+        self.inner.retain_recent();
+        self.inner.shrink_to_fit();
+    }
+}
+
+/// 被拒令牌的审计：每个客户端 IP 每分钟一条
+const REJECTION_AUDIT: RateLimitRule = RateLimitRule { per_minute: 1, burst: 1 };
+
+pub struct RateLimits {
+    pub auth: KeyedLimiter<IpAddr>,
+    pub api: KeyedLimiter<Uuid>,
+    /// 没有 `ConnectInfo`（如测试）时所有请求共用 `None` 这一个键
+    rejections: KeyedLimiter<Option<IpAddr>>,
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl RateLimits {
+    pub fn from_cfg(cfg: &RateLimitCfg) -> anyhow::Result<Self> {
+        Ok(Self {
+            auth: KeyedLimiter::new("auth", &cfg.auth)?,
+            api: KeyedLimiter::new("api", &cfg.api)?,
+            rejections: KeyedLimiter::new("rejections", &REJECTION_AUDIT)?,
+            trusted_proxies: cfg.trusted_proxies.clone(),
+        })
+    }
+
+    pub fn retain_recent(&self) {
+        self.auth.retain_recent();
+        self.api.retain_recent();
+        self.rejections.retain_recent();
+    }
+
+    /// 这次被拒的令牌要不要写审计。`auth_mw` 在任何限流之前执行，不抽样的话，
+    /// 未登录的客户端用垃圾令牌就能让审计表无限增长
+    pub fn audit_rejection(&self, ip: Option<IpAddr>) -> bool {
+        self.rejections.check(&ip).allowed
+    }
+
+    /// 直连对端是可信代理时，从右往左跳过 `X-Forwarded-For` 中的可信代理，取第一个不可信的地址；
+    /// 最左边的地址由客户端自己填写，只有整条链都可信时才会用到。无法解析的条目之后的内容一律不信
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let trusted = |ip: &IpAddr| self.trusted_proxies.iter().any(|net| net.contains(ip));
+        if !trusted(&peer) {
+            return peer;
+        }
+        let hops: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect();
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            let Ok(ip) = hop.parse::<IpAddr>() else { break };
+            client = ip;
+            if !trusted(&ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// `/api/v1/auth/*` 的限流层。未经 `ConnectInfo` 启动（如测试）拿不到对端地址时不限流
+pub async fn limit_by_ip(
+    State(st): State<AppState>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(ConnectInfo(peer)) = peer else { return next.run(req).await };
+    let ip = st.rate_limits.client_ip(peer.ip(), req.headers());
+    limit(&st.rate_limits.auth, &ip, req, next).await
+}
+
+/// 登录后接口的限流层，须挂在 `auth_mw` 之内；拿不到 `Claims` 的请求交给处理器按 401 处理
+pub async fn limit_by_user(State(st): State<AppState>, req: Request, next: Next) -> Response {
+    let Some(sub) = req.extensions().get::<Claims>().map(|c| c.sub) else { return next.run(req).await };
+    limit(&st.rate_limits.api, &sub, req, next).await
+}
+
+async fn limit<K: Hash + Eq + Clone>(limiter: &KeyedLimiter<K>, key: &K, req: Request, next: Next) -> Response {
+    let outcome = limiter.check(key);
+    let mut resp = if outcome.allowed {
+        next.run(req).await
+    } else {
+        metrics::counter!("http_rate_limited_total", "group" => limiter.group).increment(1);
+        AppError::TooManyRequests { code: "rate_limited", retry_after_secs: ceil_secs(outcome.reset) }.into_response()
+    };
+    let headers = resp.headers_mut();
+    headers.insert(RATELIMIT_LIMIT, HeaderValue::from(outcome.limit));
+    headers.insert(RATELIMIT_REMAINING, HeaderValue::from(outcome.remaining));
+    headers.insert(RATELIMIT_RESET, HeaderValue::from(ceil_secs(outcome.reset)));
+    resp
+}
+
+fn ceil_secs(d: Duration) -> u64 {
+    d.as_secs() + u64::from(d.subsec_nanos() > 0)
+}
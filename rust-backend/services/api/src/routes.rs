@@ -0,0 +1,249 @@
+use axum::{Router, routing::{delete, post, get, patch}, extract::State, http::{header, StatusCode}, Json};
+use crate::{state::{build_state, AppState}, config::AppCfg, auth::{auth_mw, Claims, login, logout, refresh, register}};
+use crate::account::{confirm_password_reset, request_password_reset, resend_verification, verify_email};
+use crate::admin::{disable_user, enable_user, list_audit, list_user_todos, list_users, revoke_sessions};
+use crate::api_keys::{create_api_key, list_api_keys, revoke_api_key};
+use crate::audit::AuditCtx;
+use crate::authz::{email_verified_mw, todo_scope_mw};
+use crate::cursor;
+use crate::openapi::ApiDoc;
+use crate::error::{parse_time, AppError, AppJson, AppPath, AppQuery, AppResult};
+use crate::health::{healthz, readyz};
+use crate::idempotency::idempotency_mw;
+use crate::metrics::{metrics_handler, metrics_mw};
+use crate::oidc::{oidc_authorize, oidc_callback};
+use crate::rate_limit::{limit_by_ip, limit_by_user};
+use crate::request_id::request_id_mw;
+use crate::stream::todo_stream;
+use app_core::{NewTodo, Recurrence, Todo, TodoChanges, TodoQuery, TodoSort};
+use dto::{ErrorBody, JwkSet, Page, TodoCreate, TodoListQuery, TodoSortParam, TodoUpdate, TodoView};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+use validator::Validate;
+use uuid::Uuid;
+
+pub async fn mk_router(cfg: AppCfg) -> anyhow::Result<Router> {
+    let st = build_state(cfg).await?;
+    Ok(router(st))
+}
+
+pub fn router(st: AppState) -> Router {
+    // 限流按路由组用 route_layer 挂载：在 auth_mw 之内执行，按用户限流时已拿到 Claims；
+    // 未匹配的路由不计数
+    let auth = Router::new()
+        .route("/api/v1/auth/register", post(register))
+        .route("/api/v1/auth/login", post(login))
+        .route("/api/v1/auth/refresh", post(refresh))
+        .route("/api/v1/auth/logout", post(logout))
+        .route("/api/v1/auth/verify-email", post(verify_email))
+        .route("/api/v1/auth/verify-email/resend", post(resend_verification))
+        .route("/api/v1/auth/password-reset", post(request_password_reset))
+        .route("/api/v1/auth/password-reset/confirm", post(confirm_password_reset))
+        .route("/api/v1/auth/oidc/authorize", get(oidc_authorize))
+        .route("/api/v1/auth/oidc/callback", post(oidc_callback))
+        .route_layer(axum::middleware::from_fn_with_state(st.clone(), limit_by_ip));
+    let api = Router::new()
+        .route("/api/v1/todos", post(create_todo).get(list_todos))
+        .route("/api/v1/todos/:id", patch(update_todo).delete(delete_todo))
+        .route("/api/v1/todos/:id/complete", post(complete_todo))
+        .route("/api/v1/todos/stream", get(todo_stream))
+        // 只作用于以上 todo 路由
+        .route_layer(axum::middleware::from_fn_with_state(st.clone(), todo_scope_mw))
+        .route("/api/v1/admin/audit", get(list_audit))
+        .route("/api/v1/admin/users", get(list_users))
+        .route("/api/v1/admin/users/:id/disable", post(disable_user))
+        .route("/api/v1/admin/users/:id/enable", post(enable_user))
+        .route("/api/v1/admin/users/:id/revoke-sessions", post(revoke_sessions))
+        .route("/api/v1/admin/users/:id/todos", get(list_user_todos))
+        .route_layer(axum::middleware::from_fn_with_state(st.clone(), email_verified_mw))
+        .route_layer(axum::middleware::from_fn_with_state(st.clone(), idempotency_mw))
+        .route_layer(axum::middleware::from_fn_with_state(st.clone(), limit_by_user));
+    // 创建 key 的响应里是明文令牌，不挂 idempotency_mw，以免落进 idempotency_keys；带幂等键的创建请求由处理器拒绝
+    let keys = Router::new()
+        .route("/api/v1/api-keys", post(create_api_key).get(list_api_keys))
+        .route("/api/v1/api-keys/:id", delete(revoke_api_key))
+        .route_layer(axum::middleware::from_fn_with_state(st.clone(), email_verified_mw))
+        .route_layer(axum::middleware::from_fn_with_state(st.clone(), limit_by_user));
+    Router::new()
+        .merge(auth)
+        .merge(api)
+        .merge(keys)
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics_handler))
+        .route("/.well-known/jwks.json", get(jwks))
+        // Swagger UI 的静态资源编译进二进制，离线可用；规范同时挂在 /api/v1/openapi.json
+        .merge(SwaggerUi::new("/api/v1/docs").url("/api/v1/openapi.json", ApiDoc::openapi()))
+        .fallback(|| async { AppError::NotFound { code: "route_not_found" } })
+        .layer(axum::middleware::from_fn_with_state(st.clone(), auth_mw))
+        // 在鉴权之外计数，401 也按路由模板统计
+        .layer(axum::middleware::from_fn(metrics_mw))
+        // 最外层：鉴权失败的响应同样带上 request_id
+        .layer(axum::middleware::from_fn(request_id_mw))
+        .with_state(st)
+}
+
+/// 其他服务据此验签；短时缓存即可，轮换时新密钥会提前出现在这里
+#[utoipa::path(get, path = "/.well-known/jwks.json", tag = "auth", responses((status = 200, body = JwkSet)))]
+async fn jwks(State(st): State<AppState>) -> ([(header::HeaderName, &'static str); 1], Json<JwkSet>) {
+    ([(header::CACHE_CONTROL, "public, max-age=300")], Json(st.jwt.jwks()))
+}
+
+#[utoipa::path(post, path = "/api/v1/todos", tag = "todos", params(("Idempotency-Key" = Option<String>, Header, description = "可选；同一个键的重试重放第一次的响应")), request_body = TodoCreate, security(("bearer" = [])), responses(
+    (status = 200, body = TodoView),
+    (status = 400, description = "`invalid_recurrence` 或 `validation_failed`（时间不是 RFC 3339）", body = ErrorBody),
+    (status = 401, body = ErrorBody),
+    (status = 409, description = "`todo_title_conflict` 或 `idempotency_request_in_progress`", body = ErrorBody),
+    (status = 422, description = "`idempotency_key_reused`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+async fn create_todo(
+    State(st): State<AppState>,
+    Claims { sub, .. }: Claims,
+    audit: AuditCtx,
+    AppJson(req): AppJson<TodoCreate>,
+) -> AppResult<Json<TodoView>> {
+    let todo = match new_todo(req) {
+        Ok(new) => st.todos.create(sub, &new).await.map_err(AppError::from),
+        Err(e) => Err(e),
+    };
+    let target = todo.as_ref().ok().map(|t| t.id.to_string());
+    audit.record(&st, "todo.create", target.as_deref(), &todo).await;
+    Ok(Json(view(todo?)))
+}
+
+const DEFAULT_PAGE_SIZE: u32 = 20;
+
+#[utoipa::path(get, path = "/api/v1/todos", tag = "todos", params(TodoListQuery), security(("bearer" = [])), responses(
+    (status = 200, body = Page<TodoView>),
+    (status = 400, description = "`invalid_cursor`、`invalid_query` 或 `validation_failed`", body = ErrorBody),
+    (status = 401, body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+async fn list_todos(
+    State(st): State<AppState>,
+    Claims { sub, .. }: Claims,
+    AppQuery(req): AppQuery<TodoListQuery>,
+) -> AppResult<Json<Page<TodoView>>> {
+    Ok(Json(todo_page(&st, sub, req).await?))
+}
+
+/// 列出 `owner` 的 todo；管理端查看他人 todo 时复用，游标同样绑定 `owner`
+pub(crate) async fn todo_page(st: &AppState, owner: Uuid, req: TodoListQuery) -> AppResult<Page<TodoView>> {
+    req.validate()?;
+    let sort = match req.sort {
+        TodoSortParam::CreatedDesc => TodoSort::CreatedDesc,
+        TodoSortParam::CreatedAsc => TodoSort::CreatedAsc,
+    };
+    let secret = &st.cfg.pagination.cursor_secret;
+    let after = req.cursor.as_deref().map(|c| cursor::decode(secret, owner, sort, c)).transpose()?;
+    let query = TodoQuery { done: req.done, q: req.q, sort, limit: req.limit.unwrap_or(DEFAULT_PAGE_SIZE), after };
+    let page = st.todos.list(owner, &query).await?;
+    Ok(Page {
+        items: page.items.into_iter().map(view).collect(),
+        next_cursor: page.next.map(|c| cursor::encode(secret, owner, sort, c)),
+    })
+}
+
+#[utoipa::path(patch, path = "/api/v1/todos/{id}", tag = "todos", params(("id" = Uuid, Path), ("Idempotency-Key" = Option<String>, Header, description = "可选；同一个键的重试重放第一次的响应")), request_body = TodoUpdate, security(("bearer" = [])), responses(
+    (status = 200, body = TodoView),
+    (status = 400, description = "`invalid_recurrence` 或 `validation_failed`（时间不是 RFC 3339）", body = ErrorBody),
+    (status = 401, body = ErrorBody),
+    (status = 404, description = "`todo_not_found`", body = ErrorBody),
+    (status = 409, description = "`todo_title_conflict`：改名后与已有的 todo 同名；或 `idempotency_request_in_progress`", body = ErrorBody),
+    (status = 422, description = "`idempotency_key_reused`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+async fn update_todo(
+    State(st): State<AppState>,
+    Claims { sub, .. }: Claims,
+    audit: AuditCtx,
+    AppPath(id): AppPath<Uuid>,
+    AppJson(req): AppJson<TodoUpdate>,
+) -> AppResult<Json<TodoView>> {
+    let todo = match changes(req) {
+        Ok(changes) => st.todos.update(sub, id, &changes).await.map_err(AppError::from),
+        Err(e) => Err(e),
+    };
+    audit.record(&st, "todo.update", Some(&id.to_string()), &todo).await;
+    Ok(Json(view(todo?)))
+}
+
+/// 请求里的计划字段转为领域类型；gRPC 的创建接口也走这里
+pub(crate) fn new_todo(req: TodoCreate) -> AppResult<NewTodo> {
+    Ok(NewTodo {
+        title: req.title,
+        due_at: req.due_at.as_deref().map(|t| parse_time("due_at", t)).transpose()?,
+        remind_at: req.remind_at.as_deref().map(|t| parse_time("remind_at", t)).transpose()?,
+        recurrence: req.recurrence.as_deref().map(parse_recurrence).transpose()?,
+    })
+}
+
+fn changes(req: TodoUpdate) -> AppResult<TodoChanges> {
+    let time = |field, value: Option<Option<String>>| value.map(|t| t.map(|t| parse_time(field, &t)).transpose()).transpose();
+    Ok(TodoChanges {
+        title: req.title,
+        done: req.done,
+        due_at: time("due_at", req.due_at)?,
+        remind_at: time("remind_at", req.remind_at)?,
+        recurrence: req.recurrence.map(|r| r.as_deref().map(parse_recurrence).transpose()).transpose()?,
+    })
+}
+
+fn parse_recurrence(value: &str) -> AppResult<Recurrence> {
+    value.parse().map_err(|e: app_core::RecurrenceError| AppError::BadRequest { code: "invalid_recurrence", message: e.to_string() })
+}
+
+/// 重复的 todo 第一次完成时同时生成下一次（见 `TodoRepo::update`）：原标题让给下一次，
+/// 响应里被完成的这一条改名为 `标题 (YYYY-MM-DD)`，日期是它的截止日期（没有截止时间时为完成当天）
+#[utoipa::path(post, path = "/api/v1/todos/{id}/complete", tag = "todos", params(("id" = Uuid, Path), ("Idempotency-Key" = Option<String>, Header, description = "可选；同一个键的重试重放第一次的响应")), security(("bearer" = [])), responses(
+    (status = 200, body = TodoView),
+    (status = 401, body = ErrorBody),
+    (status = 404, description = "`todo_not_found`", body = ErrorBody),
+    (status = 409, description = "`todo_title_conflict`：改名后的标题已被占用；或 `idempotency_request_in_progress`", body = ErrorBody),
+    (status = 422, description = "`idempotency_key_reused`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+async fn complete_todo(
+    State(st): State<AppState>,
+    Claims { sub, .. }: Claims,
+    audit: AuditCtx,
+    AppPath(id): AppPath<Uuid>,
+) -> AppResult<Json<TodoView>> {
+    let todo = st.todos.complete(sub, id).await.map_err(AppError::from);
+    audit.record(&st, "todo.complete", Some(&id.to_string()), &todo).await;
+    Ok(Json(view(todo?)))
+}
+
+/// 别人的 todo 与不存在的 todo 一样返回 404，不暴露其是否存在
+#[utoipa::path(delete, path = "/api/v1/todos/{id}", tag = "todos", params(("id" = Uuid, Path), ("Idempotency-Key" = Option<String>, Header, description = "可选；同一个键的重试重放第一次的响应")), security(("bearer" = [])), responses(
+    (status = 204, description = "已删除"),
+    (status = 401, body = ErrorBody),
+    (status = 404, description = "`todo_not_found`", body = ErrorBody),
+    (status = 409, description = "`idempotency_request_in_progress`", body = ErrorBody),
+    (status = 422, description = "`idempotency_key_reused`", body = ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = ErrorBody),
+))]
+async fn delete_todo(
+    State(st): State<AppState>,
+    Claims { sub, .. }: Claims,
+    audit: AuditCtx,
+    AppPath(id): AppPath<Uuid>,
+) -> AppResult<StatusCode> {
+    let deleted = st.todos.delete(sub, id).await.map_err(AppError::from);
+    audit.record(&st, "todo.delete", Some(&id.to_string()), &deleted).await;
+    deleted?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub(crate) fn view(t: Todo) -> TodoView {
+    TodoView {
+        id: t.id,
+        title: t.title,
+        done: t.done,
+        due_at: t.due_at.map(dto::fmt_time),
+        remind_at: t.remind_at.map(dto::fmt_time),
+        recurrence: t.recurrence.map(|r| r.to_string()),
+        created_at: dto::fmt_time(t.created_at),
+    }
+}
@@ -0,0 +1,99 @@
+//! 持久化的任务调度：替代进程内的定时循环。到点的 todo 提醒与周期清理（tasks.rs）都是 jobs 表里的一条任务
+//! （见 core 的 job.rs），任意一个实例领取执行。
+//!
+//! 领取带租约，多个实例同时运行也不会重复执行；正常退出时先做完当前一批，已完成的任务重启后不会再执行。
+//! 只有执行到一半进程崩溃时，任务才会在租约到期后被再次领取，所以提醒在这种情况下可能重复一次。
+//! 失败按 `retry_secs` 重试，用完 `max_attempts` 转为 dead。
+
+use app_core::{Job, RepoResult, Reminder};
+use time::OffsetDateTime;
+use tokio::time::{interval, Duration};
+use tokio_util::sync::CancellationToken;
+
+use crate::notifier::{Notification, Notifier};
+use crate::state::AppState;
+use crate::tasks::maintenance_once;
+
+/// 周期清理任务的 `kind` 与 `key`，全局只有一条
+pub const MAINTENANCE_JOB: &str = "maintenance";
+
+/// 每个 `poll_interval_ms` 一轮；一批领满说明还有积压，接着领下一批。取消只在两批之间生效
+pub async fn run_scheduler(st: AppState, cancel: CancellationToken) {
+    if let Err(e) = st.jobs.ensure(MAINTENANCE_JOB, MAINTENANCE_JOB, OffsetDateTime::now_utc()).await {
+        tracing::error!(err = ?e, "registering maintenance job failed");
+    }
+    let cfg = &st.cfg.scheduler;
+    let mut tick = interval(Duration::from_millis(cfg.poll_interval_ms));
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tick.tick() => {}
+        }
+        while !cancel.is_cancelled() {
+            match run_due_jobs(&st, st.notifier.as_ref()).await {
+                Ok(n) if n == cfg.batch_size as usize => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::error!(err = ?e, "scheduler run failed");
+                    break;
+                }
+            }
+        }
+    }
+    tracing::info!("scheduler stopped");
+}
+
+/// 领取并执行一批到期的任务，返回领取条数。单个任务失败只影响它自己
+pub async fn run_due_jobs(st: &AppState, notifier: &dyn Notifier) -> RepoResult<usize> {
+    let cfg = &st.cfg.scheduler;
+    let jobs = st.jobs.claim(cfg.batch_size, time::Duration::seconds(cfg.lease_secs)).await?;
+    for job in &jobs {
+        let kind = job.kind.clone();
+        match run(st, notifier, job).await {
+            Ok(next) => {
+                st.jobs.finish(job, next).await?;
+                metrics::counter!("scheduler_jobs_total", "kind" => kind, "outcome" => "ok").increment(1);
+            }
+            Err(e) if job.attempts >= cfg.max_attempts => {
+                tracing::error!(err = ?e, job_id = %job.id, kind = job.kind, attempts = job.attempts, "job failed permanently");
+                st.jobs.mark_dead(job, &format!("{e:#}")).await?;
+                metrics::counter!("scheduler_jobs_total", "kind" => kind, "outcome" => "dead").increment(1);
+            }
+            Err(e) => {
+                tracing::warn!(err = ?e, job_id = %job.id, kind = job.kind, attempts = job.attempts, "job failed, will retry");
+                let retry_at = OffsetDateTime::now_utc() + time::Duration::seconds(cfg.retry_secs);
+                st.jobs.retry_later(job, &format!("{e:#}"), retry_at).await?;
+                metrics::counter!("scheduler_jobs_total", "kind" => kind, "outcome" => "retry").increment(1);
+            }
+        }
+    }
+    Ok(jobs.len())
+}
+
+/// 执行一条任务，返回周期任务的下次运行时间
+async fn run(st: &AppState, notifier: &dyn Notifier, job: &Job) -> anyhow::Result<Option<OffsetDateTime>> {
+    match job.kind.as_str() {
+        MAINTENANCE_JOB => {
+            maintenance_once(st).await;
+            Ok(Some(OffsetDateTime::now_utc() + time::Duration::seconds(st.cfg.scheduler.maintenance_interval_secs)))
+        }
+        Reminder::JOB => {
+            remind(st, notifier, serde_json::from_value(job.payload.clone())?).await?;
+            Ok(None)
+        }
+        other => anyhow::bail!("unknown job kind `{other}`"),
+    }
+}
+
+/// todo 已删除、已完成或提醒时间已改时静默跳过：写入时已取消或改期，这里兜住执行与写入并发的情况
+async fn remind(st: &AppState, notifier: &dyn Notifier, reminder: Reminder) -> anyhow::Result<()> {
+    let Some(todo) = st.todos.get(reminder.user_id, reminder.todo_id).await? else { return Ok(()) };
+    if todo.reminder().as_ref() != Some(&reminder) {
+        return Ok(());
+    }
+    let Some(user) = st.users.access(reminder.user_id).await? else { return Ok(()) };
+    if user.disabled {
+        return Ok(());
+    }
+    notifier.notify(&Notification { email: user.email, todo }).await
+}
@@ -0,0 +1,93 @@
+//! 优雅退出。顺序：`/readyz` 失败 → 等 `drain_delay_secs` → 停止接受连接并等待在途请求
+//! （上限 `shutdown_timeout_secs`）→ 取消后台任务 → 关闭连接池。
+
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio::task::{JoinError, JoinHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+
+use crate::state::AppState;
+
+/// SIGTERM（K8s 停止 Pod）或 Ctrl+C
+pub async fn signal() {
+    let ctrl_c = async { tokio::signal::ctrl_c().await.expect("install Ctrl+C handler") };
+    #[cfg(unix)]
+    let term = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let term = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = term => {}
+    }
+}
+
+/// 运行 HTTP 服务直到收到退出信号，然后按模块文档的顺序收尾。
+/// `tasks` 中的后台任务须在 `tasks_cancel` 取消后自行结束。
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    st: AppState,
+    mut tasks: JoinSet<()>,
+    tasks_cancel: CancellationToken,
+) -> anyhow::Result<()> {
+    let stop_accepting = CancellationToken::new();
+    let mut server = tokio::spawn(
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(stop_accepting.clone().cancelled_owned())
+            .into_future(),
+    );
+
+    let served = tokio::select! {
+        // 没收到信号服务就退出了，多半是监听出错
+        res = &mut server => flatten(res),
+        _ = signal() => drain(&st, &stop_accepting, &mut server).await,
+    };
+    // 服务出错也要把后台任务与连接池收拾干净，错误最后再返回
+    if let Err(e) = &served {
+        tracing::error!(err = ?e, "http server failed");
+    }
+
+    tasks_cancel.cancel();
+    while let Some(res) = tasks.join_next().await {
+        if let Err(e) = res {
+            tracing::error!(err = %e, "background task failed");
+        }
+    }
+    st.db.close().await;
+    tracing::info!("shutdown complete");
+    served
+}
+
+async fn drain(
+    st: &AppState,
+    stop_accepting: &CancellationToken,
+    server: &mut JoinHandle<std::io::Result<()>>,
+) -> anyhow::Result<()> {
+    tracing::info!("shutdown signal received, failing readiness");
+    st.shutdown.cancel();
+    tokio::time::sleep(Duration::from_secs(st.cfg.server.drain_delay_secs)).await;
+
+    tracing::info!("draining in-flight requests");
+    stop_accepting.cancel();
+    match tokio::time::timeout(Duration::from_secs(st.cfg.server.shutdown_timeout_secs), &mut *server).await {
+        Ok(res) => flatten(res),
+        Err(_) => {
+            tracing::warn!("drain deadline exceeded, dropping remaining connections");
+            server.abort();
+            Ok(())
+        }
+    }
+}
+
+fn flatten(res: Result<std::io::Result<()>, JoinError>) -> anyhow::Result<()> {
+    Ok(res??)
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+use anyhow::Context;
+use time::Duration;
+use crate::config::AppCfg;
+use crate::jwt::JwtKeys;
+use crate::mailer::Mailer;
+use crate::notifier::Notifier;
+use crate::oidc::OidcClient;
+use crate::outbox::{BroadcastPublisher, EventPublisher};
+use crate::rate_limit::RateLimits;
+use app_core::{
+    ApiKeyRepo, Auditor, EmailTokenRepo, HashParams, IdempotencyStore, JobRepo, LockoutPolicy, LoginAttemptRepo, OidcRepo, OutboxRepo, PasswordPolicy, PasswordService, SessionRepo, TodoService, UserRepo,
+};
+use infra::Db;
+use metrics_exporter_prometheus::PrometheusHandle;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub cfg: Arc<AppCfg>,
+    pub db: Db,
+    pub users: Arc<dyn UserRepo>,
+    pub audit: Arc<Auditor>,
+    pub api_keys: Arc<dyn ApiKeyRepo>,
+    pub email_tokens: Arc<dyn EmailTokenRepo>,
+    pub mailer: Arc<dyn Mailer>,
+    pub oidc: Arc<dyn OidcRepo>,
+    /// 未配置 `[oidc] issuer` 时为空
+    pub oidc_client: Option<Arc<OidcClient>>,
+    pub todos: Arc<TodoService>,
+    pub sessions: Arc<dyn SessionRepo>,
+    pub outbox: Arc<dyn OutboxRepo>,
+    pub publisher: Arc<dyn EventPublisher>,
+    /// 已投递事件在本进程内的广播，WebSocket 连接从这里订阅
+    pub live: Arc<BroadcastPublisher>,
+    pub idempotency: Arc<dyn IdempotencyStore>,
+    pub jobs: Arc<dyn JobRepo>,
+    /// 到点的提醒经它送出（见 scheduler.rs）
+    pub notifier: Arc<dyn Notifier>,
+    pub passwords: Arc<PasswordService>,
+    pub jwt: Arc<JwtKeys>,
+    pub metrics: PrometheusHandle,
+    pub rate_limits: Arc<RateLimits>,
+    /// 收到退出信号时取消；/readyz 据此返回 503
+    pub shutdown: CancellationToken,
+}
+
+pub async fn build_state(cfg: AppCfg) -> anyhow::Result<AppState> {
+    let db = infra::connect(&cfg.db.url).await?;
+    infra::migrate(&db).await?;
+    with_pool(cfg, db)
+}
+
+/// 用已有连接池组装依赖，不做迁移；测试可配合 `infra::connect_lazy` 使用。
+pub fn with_pool(cfg: AppCfg, db: Db) -> anyhow::Result<AppState> {
+    let repos = infra::repos(&db);
+    let passwords = password_service(&cfg, repos.login_attempts)?;
+    let jwt = JwtKeys::from_cfg(&cfg.jwt.keys, Duration::minutes(cfg.jwt.exp_minutes))?;
+    let rate_limits = RateLimits::from_cfg(&cfg.rate_limit)?;
+    let live = Arc::new(BroadcastPublisher::new(cfg.stream.buffer));
+    let publisher = crate::outbox::publisher(&cfg.outbox, live.clone())?;
+    let idempotency = crate::idempotency::store(&cfg.idempotency, repos.idempotency);
+    let mailer = crate::mailer::mailer(&cfg.mail)?;
+    let oidc_client = OidcClient::from_cfg(&cfg.oidc)?.map(Arc::new);
+    let notifier = crate::notifier::notifier(&cfg.scheduler, mailer.clone());
+    Ok(AppState {
+        cfg: Arc::new(cfg),
+        users: repos.users,
+        audit: Arc::new(Auditor::new(repos.audit)),
+        api_keys: repos.api_keys,
+        email_tokens: repos.email_tokens,
+        mailer,
+        oidc: repos.oidc,
+        oidc_client,
+        todos: Arc::new(TodoService::new(repos.todos)),
+        sessions: repos.sessions,
+        outbox: repos.outbox,
+        publisher,
+        live,
+        idempotency,
+        jobs: repos.jobs,
+        notifier,
+        passwords: Arc::new(passwords),
+        jwt: Arc::new(jwt),
+        metrics: crate::metrics::handle(),
+        rate_limits: Arc::new(rate_limits),
+        shutdown: CancellationToken::new(),
+        db,
+    })
+}
+
+fn password_service(cfg: &AppCfg, attempts: Arc<dyn LoginAttemptRepo>) -> anyhow::Result<PasswordService> {
+    let p = &cfg.password;
+    let breached = match &p.breached_list {
+        Some(path) => app_core::password::load_breached(path.as_ref())
+            .with_context(|| format!("reading breached password list {path}"))?,
+        None => Default::default(),
+    };
+    let policy = PasswordPolicy { min_length: p.min_length, max_length: p.max_length, breached };
+    let params = HashParams { m_cost: p.argon2.m_cost, t_cost: p.argon2.t_cost, p_cost: p.argon2.p_cost };
+    let lockout = LockoutPolicy {
+        account_threshold: p.lockout.account_threshold,
+        ip_threshold: p.lockout.ip_threshold,
+        base: Duration::seconds(p.lockout.base_secs),
+        max: Duration::seconds(p.lockout.max_secs),
+        window: Duration::seconds(p.lockout.window_secs),
+    };
+    Ok(PasswordService::new(params, policy, lockout, attempts)?)
+}
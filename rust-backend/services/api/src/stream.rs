@@ -0,0 +1,127 @@
+//! `GET /api/v1/todos/stream`：登录用户的 todo 变更推送（WebSocket）。
+//!
+//! 事件来自 outbox dispatcher 投递后的本进程广播（`AppState::live`），因此推送与外部消费方看到的
+//! 是同一批事件，延迟不超过一个轮询间隔。
+//!
+//! 只保证单实例部署时推送完整：每条 outbox 事件只由领取到它的那个实例投递，多实例部署时
+//! 连接只能收到本实例投递的那部分，其余的静默丢失（不会收到 `resync`）。需要全量时应改为
+//! 订阅 dispatcher 投递的消息队列。
+//!
+//! 浏览器的 WebSocket API 不能设置请求头，令牌可以放在子协议 `bearer.<token>`（与 `todo.v1`
+//! 一起提供）或查询参数 `access_token` 里，由 `auth_mw` 统一校验。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use app_core::{OutboxEvent, Todo};
+use axum::body::Body;
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{header, Request};
+use axum::response::Response;
+use dto::TodoStreamMsg;
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::auth::Claims;
+use crate::state::AppState;
+
+pub const STREAM_PATH: &str = "/api/v1/todos/stream";
+/// 服务端选定的子协议；客户端用子协议传令牌时必须同时提供它，否则浏览器会拒绝握手响应
+pub const PROTOCOL: &str = "todo.v1";
+const TOKEN_PROTOCOL_PREFIX: &str = "bearer.";
+
+#[derive(Deserialize)]
+struct StreamQuery {
+    access_token: Option<String>,
+}
+
+/// 升级请求里的令牌：先看子协议，再看查询参数
+pub fn upgrade_token(req: &Request<Body>) -> Option<String> {
+    let from_protocol = req
+        .headers()
+        .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .find_map(|p| p.trim().strip_prefix(TOKEN_PROTOCOL_PREFIX))
+        .map(str::to_string);
+    from_protocol.or_else(|| Query::<StreamQuery>::try_from_uri(req.uri()).ok()?.0.access_token)
+}
+
+/// 先订阅再升级，握手期间投递的事件不会漏掉
+#[utoipa::path(get, path = "/api/v1/todos/stream", tag = "todos", security(("bearer" = [])),
+    description = "推送当前用户的 todo 变更。只在单实例部署时完整：多实例时只收到所连实例投递的事件，其余不会推送，也不会收到 `resync`。",
+    responses(
+    (status = 101, description = "升级为 WebSocket，之后每个文本帧是一条 `TodoStreamMsg`", body = TodoStreamMsg),
+    (status = 401, body = dto::ErrorBody),
+    (status = 429, description = "`rate_limited`，见 `Retry-After`", body = dto::ErrorBody),
+))]
+pub async fn todo_stream(State(st): State<AppState>, Claims { sub, .. }: Claims, ws: WebSocketUpgrade) -> Response {
+    let rx = st.live.subscribe();
+    let ping = Duration::from_secs(st.cfg.stream.ping_secs);
+    let shutdown = st.shutdown.clone();
+    ws.protocols([PROTOCOL]).on_upgrade(move |socket| push_events(socket, sub, rx, ping, shutdown))
+}
+
+async fn push_events(
+    mut socket: WebSocket,
+    user_id: Uuid,
+    mut rx: broadcast::Receiver<Arc<OutboxEvent>>,
+    ping: Duration,
+    shutdown: CancellationToken,
+) {
+    let mut heartbeat = tokio::time::interval_at(tokio::time::Instant::now() + ping, ping);
+    loop {
+        let msg = tokio::select! {
+            // 长连接会拖住优雅退出，收到退出信号就主动关闭
+            _ = shutdown.cancelled() => {
+                let frame = CloseFrame { code: close_code::AWAY, reason: "server shutting down".into() };
+                let _ = socket.send(Message::Close(Some(frame))).await;
+                break;
+            }
+            _ = heartbeat.tick() => Message::Ping(Vec::new()),
+            // 客户端不需要发消息；读是为了处理关闭帧与 pong
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+            event = rx.recv() => match event {
+                Ok(event) if event.user_id == user_id => match to_msg(&event) {
+                    Some(msg) => text(&msg),
+                    None => continue,
+                },
+                Ok(_) => continue,
+                // 落后太多，广播通道已覆盖了旧事件：告诉客户端重新拉取，而不是假装什么都没丢
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(%user_id, missed, "todo stream lagged, asking client to resync");
+                    text(&TodoStreamMsg::Resync { missed })
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+        if socket.send(msg).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn to_msg(event: &OutboxEvent) -> Option<TodoStreamMsg> {
+    match serde_json::from_value::<Todo>(event.payload.clone()) {
+        Ok(todo) => Some(TodoStreamMsg::Event {
+            event_id: event.id,
+            event_type: event.event_type.clone(),
+            todo: crate::routes::view(todo),
+        }),
+        Err(e) => {
+            tracing::error!(err = %e, event_id = %event.id, "malformed todo event payload");
+            None
+        }
+    }
+}
+
+fn text(msg: &TodoStreamMsg) -> Message {
+    Message::Text(serde_json::to_string(msg).expect("stream messages serialize"))
+}
@@ -0,0 +1,62 @@
+use tokio::time::{interval, Duration};
+use tokio_util::sync::CancellationToken;
+use app_core::RepoResult;
+use crate::config::IdempotencyStoreKind;
+use crate::state::AppState;
+
+/// 过期的访问令牌在列表里再保留一段时间，方便用户看出脚本为什么失效
+const EXPIRED_API_KEY_RETENTION_DAYS: i64 = 30;
+
+/// 每个实例都要跑的本地清理，间隔同 `[scheduler] maintenance_interval_secs`；取消只在两轮之间生效
+pub async fn run_local_cleanup(st: AppState, cancel: CancellationToken) {
+    let secs = st.cfg.scheduler.maintenance_interval_secs.max(1) as u64;
+    let mut tick = interval(Duration::from_secs(secs));
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tick.tick() => local_cleanup_once(&st).await,
+        }
+    }
+    tracing::info!("local cleanup stopped");
+}
+
+/// 只清本进程内存里的状态（限流计数、内存幂等存储）。`maintenance` 任务全局只在一个实例上执行，
+/// 这些放在那里的话，其他实例的内存会一直涨
+pub async fn local_cleanup_once(st: &AppState) {
+    st.rate_limits.retain_recent();
+    if st.cfg.idempotency.store == IdempotencyStoreKind::Memory {
+        record("purge_expired_idempotency_keys", st.idempotency.purge_expired().await);
+    }
+}
+
+/// 执行一轮数据库清理；由调度器（scheduler.rs）每隔 `[scheduler] maintenance_interval_secs` 领取执行，
+/// 多个实例只有一个在跑。每个任务的成败与删除条数记入指标（见 metrics.rs）
+pub async fn maintenance_once(st: &AppState) {
+    record("purge_blank_todos", st.todos.purge_blank().await);
+    record("purge_expired_sessions", st.sessions.purge_expired().await);
+    let retention = time::Duration::days(st.cfg.outbox.retention_days);
+    record("purge_dispatched_outbox", st.outbox.purge_dispatched(time::OffsetDateTime::now_utc() - retention).await);
+    if st.cfg.idempotency.store == IdempotencyStoreKind::Db {
+        record("purge_expired_idempotency_keys", st.idempotency.purge_expired().await);
+    }
+    let expired_keys = time::OffsetDateTime::now_utc() - time::Duration::days(EXPIRED_API_KEY_RETENTION_DAYS);
+    record("purge_expired_api_keys", st.api_keys.purge_expired(expired_keys).await);
+    record("purge_expired_email_tokens", st.email_tokens.purge_expired().await);
+    record("purge_expired_oidc_logins", st.oidc.purge_expired_logins().await);
+    record("purge_login_attempts", st.passwords.purge_attempts().await);
+    let finished_jobs = time::OffsetDateTime::now_utc() - time::Duration::days(st.cfg.scheduler.retention_days);
+    record("purge_finished_jobs", st.jobs.purge_finished(finished_jobs).await);
+}
+
+fn record(job: &'static str, res: RepoResult<u64>) {
+    match res {
+        Ok(deleted) => {
+            metrics::counter!("maintenance_runs_total", "job" => job, "outcome" => "ok").increment(1);
+            metrics::counter!("maintenance_deleted_total", "job" => job).increment(deleted);
+        }
+        Err(e) => {
+            tracing::error!(err=?e, job, "maintenance failed");
+            metrics::counter!("maintenance_runs_total", "job" => job, "outcome" => "error").increment(1);
+        }
+    }
+}
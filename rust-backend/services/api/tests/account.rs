@@ -0,0 +1,197 @@
+//! 账号生命周期：邮箱验证、找回密码与 OIDC 登录
+
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{mailed_token, spawn_app, TestApp, TestResponse};
+use dto::{AuthResp, OidcCallbackReq};
+use serde_json::json;
+use services_api::auth::Claims;
+use services_api::config::UnverifiedAccess;
+use services_api::mailer::InMemoryMailer;
+
+#[tokio::test]
+async fn email_verification_and_password_reset() {
+    let app = spawn_app!(|c| c.account.unverified = UnverifiedAccess::ReadOnly);
+    let mail = Arc::new(InMemoryMailer::default());
+    let app = app.with_mailer(mail.clone());
+    let user = app.register_user().await;
+
+    // 未验证邮箱：能读不能写
+    app.authed_get(user.token(), "/api/v1/todos").await.assert_status(StatusCode::OK);
+    app.authed_post(user.token(), "/api/v1/todos", &json!({ "title": "early" }))
+        .await
+        .assert_error(StatusCode::FORBIDDEN, "email_unverified");
+
+    // 重发后只有最新一封里的链接有效，且只能用一次
+    let first = mailed_token(&mail, &user.email);
+    app.post("/api/v1/auth/verify-email/resend", &json!({ "email": user.email })).await.assert_status(StatusCode::ACCEPTED);
+    let second = mailed_token(&mail, &user.email);
+    assert_ne!(first, second);
+    let verify = "/api/v1/auth/verify-email";
+    app.post(verify, &json!({ "token": first })).await.assert_error(StatusCode::BAD_REQUEST, "invalid_email_token");
+    app.post(verify, &json!({ "token": second })).await.assert_status(StatusCode::NO_CONTENT);
+    app.post(verify, &json!({ "token": second })).await.assert_error(StatusCode::BAD_REQUEST, "invalid_email_token");
+
+    // 刷新后的令牌不再受限；已验证的邮箱不再收到验证邮件
+    let session: AuthResp = app
+        .post("/api/v1/auth/refresh", &json!({ "refresh_token": user.auth.refresh_token }))
+        .await
+        .assert_status(StatusCode::OK)
+        .json();
+    app.authed_post(&session.token, "/api/v1/todos", &json!({ "title": "verified" })).await.assert_status(StatusCode::OK);
+    let sent = mail.sent().len();
+    app.post("/api/v1/auth/verify-email/resend", &json!({ "email": user.email })).await.assert_status(StatusCode::ACCEPTED);
+    // 未注册的邮箱同样 202，但不发信
+    let nobody = format!("{}@example.com", uuid::Uuid::new_v4());
+    app.post("/api/v1/auth/password-reset", &json!({ "email": nobody })).await.assert_status(StatusCode::ACCEPTED);
+    assert_eq!(mail.sent().len(), sent);
+
+    // 重置密码：验证邮箱的令牌不能用；新密码不合格时令牌不会被用掉
+    app.post("/api/v1/auth/password-reset", &json!({ "email": user.email })).await.assert_status(StatusCode::ACCEPTED);
+    let reset = mailed_token(&mail, &user.email);
+    let confirm = "/api/v1/auth/password-reset/confirm";
+    let body = |token: &str, password: &str| json!({ "token": token, "password": password });
+    app.post(confirm, &body(&second, "new battery staple")).await.assert_error(StatusCode::BAD_REQUEST, "invalid_email_token");
+    app.post(confirm, &body(&reset, "short")).await.assert_error(StatusCode::BAD_REQUEST, "validation_failed");
+    app.post(confirm, &body(&reset, "new battery staple")).await.assert_status(StatusCode::NO_CONTENT);
+    app.post(confirm, &body(&reset, "new battery staple")).await.assert_error(StatusCode::BAD_REQUEST, "invalid_email_token");
+
+    // 旧会话全部失效，旧密码不能再登录
+    app.authed_get(&session.token, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "token_revoked");
+    app.try_login(None, &user.email, &user.password).await.assert_error(StatusCode::UNAUTHORIZED, "invalid_credentials");
+    app.login(&user.email, "new battery staple").await;
+}
+
+/// 发起登录，返回提供方的授权地址与绑定 `state` 的 cookie（`name=value`）
+async fn oidc_authorize(app: &TestApp) -> (String, String) {
+    let resp = app.get("/api/v1/auth/oidc/authorize").await.assert_status(StatusCode::FOUND);
+    let cookie = resp.headers[header::SET_COOKIE].to_str().unwrap();
+    assert!(cookie.contains("HttpOnly") && cookie.contains("SameSite=Lax"));
+    let cookie = cookie.split(';').next().unwrap().to_string();
+    (resp.headers[header::LOCATION].to_str().unwrap().to_string(), cookie)
+}
+
+/// 前端把回调参数交给服务端；`cookie` 为空表示换了一个浏览器
+async fn oidc_callback(app: &TestApp, cookie: &str, cb: &OidcCallbackReq) -> TestResponse {
+    let mut req = Request::post("/api/v1/auth/oidc/callback").header(header::CONTENT_TYPE, "application/json");
+    if !cookie.is_empty() {
+        req = req.header(header::COOKIE, format!("theme=dark; {cookie}"));
+    }
+    app.send(req.body(Body::from(serde_json::to_vec(cb).unwrap())).unwrap()).await
+}
+
+/// 同一个浏览器走完一次登录：发起、同意、回调
+async fn oidc_login(app: &TestApp, extra: &[(&str, &str)]) -> TestResponse {
+    let (location, cookie) = oidc_authorize(app).await;
+    oidc_callback(app, &cookie, &oidc_consent(&location, extra).await).await
+}
+
+/// 扮演浏览器：打开授权页（mock 直接同意），取出跳回 `redirect_uri` 时带的 `code` 与 `state`
+async fn oidc_consent(location: &str, extra: &[(&str, &str)]) -> OidcCallbackReq {
+    let mut url = reqwest::Url::parse(location).unwrap();
+    url.query_pairs_mut().extend_pairs(extra);
+    let http = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+    let resp = http.get(url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FOUND);
+    let back = reqwest::Url::parse(resp.headers()[header::LOCATION].to_str().unwrap()).unwrap();
+    assert_eq!(back.path(), "/oidc/callback");
+    let param = |name: &str| back.query_pairs().find(|(k, _)| k == name).unwrap().1.into_owned();
+    OidcCallbackReq { code: param("code"), state: param("state") }
+}
+
+#[tokio::test]
+async fn oidc_login_links_accounts_by_verified_email() {
+    TestApp::offline().get("/api/v1/auth/oidc/authorize").await.assert_error(StatusCode::NOT_FOUND, "oidc_disabled");
+    let issuer = common::serve_mock_oidc().await;
+    let app = spawn_app!(|c| c.oidc.issuer = Some(issuer.clone()));
+    let sub = |auth: &AuthResp| app.state.jwt.verify::<Claims>(&auth.token).unwrap();
+
+    // 授权请求带 PKCE；已有的密码账号按验证过的邮箱绑定，邮箱随之视为已验证
+    let existing_email = format!("{}@example.com", uuid::Uuid::new_v4());
+    let existing = app.register_verified(&existing_email, "correct horse").await;
+    let (location, cookie) = oidc_authorize(&app).await;
+    assert!(location.starts_with(&format!("{issuer}/authorize?")) && location.contains("code_challenge_method=S256"));
+    let cb = oidc_consent(&location, &[("login_hint", &existing_email)]).await;
+
+    // state 绑定发起登录的浏览器：没有 cookie 或 cookie 属于另一次登录都不行，也不会把这次登录用掉
+    oidc_callback(&app, "", &cb).await.assert_error(StatusCode::BAD_REQUEST, "invalid_oidc_state");
+    let (_, other_cookie) = oidc_authorize(&app).await;
+    oidc_callback(&app, &other_cookie, &cb).await.assert_error(StatusCode::BAD_REQUEST, "invalid_oidc_state");
+    let resp = oidc_callback(&app, &cookie, &cb).await.assert_status(StatusCode::OK);
+    assert!(resp.headers[header::SET_COOKIE].to_str().unwrap().contains("Max-Age=0"));
+    let linked: AuthResp = resp.json();
+    let claims = sub(&linked);
+    assert_eq!(claims.sub, sub(&existing).sub);
+    assert!(!claims.email_unverified);
+    oidc_callback(&app, &cookie, &cb).await.assert_error(StatusCode::BAD_REQUEST, "invalid_oidc_state");
+    // 已验证的账号保留自己的密码与会话
+    app.authed_get(&existing.token, "/api/v1/todos").await.assert_status(StatusCode::OK);
+    app.login(&existing_email, "correct horse").await;
+
+    // 没验证过的本地账号可能是抢注的：绑定时作废它的密码与会话
+    let squatter = app.register_user().await;
+    let owner: AuthResp = oidc_login(&app, &[("login_hint", &squatter.email)]).await.assert_status(StatusCode::OK).json();
+    assert_eq!(sub(&owner).sub, sub(&squatter.auth).sub);
+    app.authed_get(squatter.token(), "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "token_revoked");
+    app.post("/api/v1/auth/refresh", &json!({ "refresh_token": squatter.auth.refresh_token }))
+        .await
+        .assert_error(StatusCode::UNAUTHORIZED, "invalid_refresh_token");
+    app.try_login(None, &squatter.email, &squatter.password).await.assert_error(StatusCode::UNAUTHORIZED, "invalid_credentials");
+    app.authed_get(&owner.token, "/api/v1/todos").await.assert_status(StatusCode::OK);
+
+    // 没有本地账号时新建；同一个外部身份再次登录得到同一个用户
+    let email = format!("{}@example.com", uuid::Uuid::new_v4());
+    let first: AuthResp = oidc_login(&app, &[("login_hint", &email)]).await.json();
+    let again: AuthResp = oidc_login(&app, &[("login_hint", &email)]).await.json();
+    assert_eq!(sub(&first).sub, sub(&again).sub);
+    assert_ne!(sub(&first).sub, claims.sub);
+
+    // 提供方没验证过的邮箱不绑定也不建号
+    let unverified = format!("{}@example.com", uuid::Uuid::new_v4());
+    oidc_login(&app, &[("login_hint", &unverified), ("email_verified", "false")])
+        .await
+        .assert_error(StatusCode::FORBIDDEN, "oidc_email_unverified");
+    assert!(app.state.users.find_by_email(&unverified).await.unwrap().is_none());
+
+    // 授权码与发起它的登录绑定：拿别的登录的 state（PKCE 校验码不同）换不出令牌
+    let (location, _) = oidc_authorize(&app).await;
+    let a = oidc_consent(&location, &[]).await;
+    let (location, cookie) = oidc_authorize(&app).await;
+    let b = oidc_consent(&location, &[]).await;
+    let mixed = OidcCallbackReq { code: a.code, state: b.state };
+    oidc_callback(&app, &cookie, &mixed).await.assert_error(StatusCode::UNAUTHORIZED, "oidc_code_rejected");
+}
+
+#[tokio::test]
+async fn password_reset_revokes_api_keys() {
+    let app = spawn_app!();
+    let mail = Arc::new(InMemoryMailer::default());
+    let app = app.with_mailer(mail.clone());
+    let user = app.register_user().await;
+    let pat = app.create_api_key(user.token(), "ci").await;
+    app.authed_get(&pat, "/api/v1/todos").await.assert_status(StatusCode::OK);
+
+    // 令牌可能是拿到旧密码的人建的，重置后一并作废
+    app.post("/api/v1/auth/password-reset", &json!({ "email": user.email })).await.assert_status(StatusCode::ACCEPTED);
+    let reset = mailed_token(&mail, &user.email);
+    app.post("/api/v1/auth/password-reset/confirm", &json!({ "token": reset, "password": "new battery staple" }))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    app.authed_get(&pat, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "invalid_token");
+}
+
+#[tokio::test]
+async fn oidc_takeover_revokes_squatter_api_keys() {
+    let issuer = common::serve_mock_oidc().await;
+    let app = spawn_app!(|c| c.oidc.issuer = Some(issuer.clone()));
+    let squatter = app.register_user().await;
+    let pat = app.create_api_key(squatter.token(), "backdoor").await;
+    app.authed_get(&pat, "/api/v1/todos").await.assert_status(StatusCode::OK);
+
+    oidc_login(&app, &[("login_hint", &squatter.email)]).await.assert_status(StatusCode::OK);
+    app.authed_get(&pat, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "invalid_token");
+}
@@ -0,0 +1,169 @@
+//! 管理接口：审计日志与 RBAC
+
+mod common;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use app_core::AuditQuery;
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{mailed_token, spawn_app};
+use dto::{AuditEventView, Page, TodoView, UserAdminView};
+use serde_json::json;
+use services_api::auth::Claims;
+use services_api::mailer::InMemoryMailer;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+#[tokio::test]
+async fn audit_log_records_security_events_for_admins() {
+    let admin_email = format!("admin-{}@example.com", uuid::Uuid::new_v4());
+    let app = spawn_app!(|c| c.admin.emails = vec![admin_email.to_uppercase()]);
+    let since = OffsetDateTime::now_utc().format(&Rfc3339).unwrap();
+    let user = app.register_user().await;
+    let user_id = app.state.jwt.verify::<Claims>(user.token()).unwrap().sub;
+    let ip: IpAddr = "203.0.113.7".parse().unwrap();
+    app.try_login(Some(ip), &user.email, "wrong password").await.assert_status(StatusCode::UNAUTHORIZED);
+    let todo: TodoView = app.authed_post(user.token(), "/api/v1/todos", &json!({ "title": "audited" })).await.json();
+    app.authed_delete(user.token(), &format!("/api/v1/todos/{}", todo.id)).await.assert_status(StatusCode::NO_CONTENT);
+    app.authed_get("not-a-jwt", "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "invalid_token");
+    // 被拒的令牌每个 IP 每分钟只审计一条，垃圾令牌刷不爆审计表
+    for _ in 0..5 {
+        app.authed_get("junk", "/api/v1/no-such-route").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+    let junk = |path: &str| Request::get(path).header(header::AUTHORIZATION, "Bearer junk").body(Body::empty()).unwrap();
+    let elsewhere: IpAddr = "198.51.100.77".parse().unwrap();
+    app.send_from(elsewhere, junk("/api/v1/todos")).await.assert_status(StatusCode::UNAUTHORIZED);
+    app.send_from(elsewhere, junk("/api/v1/todos")).await.assert_status(StatusCode::UNAUTHORIZED);
+
+    // 普通用户没有 `audit:read`，拒绝本身也记下来
+    app.authed_get(user.token(), "/api/v1/admin/audit").await.assert_error(StatusCode::FORBIDDEN, "forbidden");
+
+    let admin = app.register_verified(&admin_email, "correct horse").await;
+    let query = |q: String| {
+        let (token, uri) = (admin.token.clone(), format!("/api/v1/admin/audit?from={since}&{q}"));
+        let app = &app;
+        async move { app.authed_get(&token, &uri).await }
+    };
+    let page: Page<AuditEventView> = query(format!("actor={user_id}")).await.assert_status(StatusCode::OK).json();
+    let actions: Vec<_> = page.items.iter().map(|e| (e.action.as_str(), e.reason.as_deref())).collect();
+    assert_eq!(
+        actions,
+        [("authz.denied", Some("forbidden")), ("todo.delete", None), ("todo.create", None), ("auth.register", None)]
+    );
+    assert_eq!(page.items[0].target.as_deref(), Some("audit:read"));
+    assert_eq!(page.items[1].target.as_deref(), Some(todo.id.to_string().as_str()));
+    assert!(page.items.iter().all(|e| e.request_id.is_some()));
+
+    // 登录失败与被拒的令牌没有 actor，按动作与结果查
+    let failed: Page<AuditEventView> =
+        query("action=auth.login&outcome=failure".into()).await.assert_status(StatusCode::OK).json();
+    let login = failed.items.iter().find(|e| e.target.as_deref() == Some(user.email.as_str())).unwrap();
+    assert_eq!((login.reason.as_deref(), login.ip.as_deref(), login.actor), (Some("invalid_credentials"), Some("203.0.113.7"), None));
+    let rejected: Page<AuditEventView> = query("action=auth.token_rejected".into()).await.json();
+    let rejected: Vec<_> = rejected.items.iter().map(|e| (e.ip.as_deref(), e.target.as_deref(), e.reason.as_deref())).collect();
+    assert_eq!(rejected, [(Some("198.51.100.77"), Some("/api/v1/todos"), Some("invalid_token")), (None, Some("/api/v1/todos"), Some("invalid_token"))]);
+
+    // 翻页与时间范围
+    let first: Page<AuditEventView> = query(format!("actor={user_id}&limit=1")).await.json();
+    let cursor = first.next_cursor.unwrap();
+    let second: Page<AuditEventView> = query(format!("actor={user_id}&limit=1&cursor={cursor}")).await.json();
+    assert_eq!(second.items[0].id, page.items[1].id);
+    let until = &page.items[2].occurred_at;
+    let older: Page<AuditEventView> = query(format!("actor={user_id}&to={until}")).await.json();
+    assert_eq!(older.items.iter().map(|e| e.id).collect::<Vec<_>>(), [page.items[3].id]);
+    app.authed_get(&admin.token, "/api/v1/admin/audit?from=yesterday")
+        .await
+        .assert_error(StatusCode::BAD_REQUEST, "validation_failed");
+
+    // 只追加：库里也改不了
+    assert!(sqlx::query("update audit_events set action = 'x'").execute(&app.state.db).await.is_err());
+    assert!(sqlx::query("delete from audit_events").execute(&app.state.db).await.is_err());
+}
+
+#[tokio::test]
+async fn rbac_admin_manages_users() {
+    let admin_email = format!("admin-{}@example.com", uuid::Uuid::new_v4());
+    let app = spawn_app!(|c| c.admin.emails = vec![admin_email.clone()]);
+    let mail = Arc::new(InMemoryMailer::default());
+    let app = app.with_mailer(mail.clone());
+    let user = app.register_user().await;
+    let claims = app.state.jwt.verify::<Claims>(user.token()).unwrap();
+    assert_eq!((claims.roles, claims.permissions), (vec!["user".to_string()], vec![]));
+    let user_id = claims.sub;
+    app.authed_post(user.token(), "/api/v1/todos", &json!({ "title": "private" })).await.assert_status(StatusCode::OK);
+    app.authed_get(user.token(), "/api/v1/admin/users").await.assert_error(StatusCode::FORBIDDEN, "forbidden");
+
+    // 引导管理员的地址要先验证：抢先注册它的人拿不到 admin 角色
+    let unverified = app.register(&admin_email, "correct horse").await;
+    assert!(!app.state.jwt.verify::<Claims>(&unverified.token).unwrap().roles.contains(&"admin".to_string()));
+    app.authed_get(&unverified.token, "/api/v1/admin/users").await.assert_error(StatusCode::FORBIDDEN, "forbidden");
+    app.authed_get(&unverified.token, "/api/v1/admin/audit").await.assert_error(StatusCode::FORBIDDEN, "forbidden");
+
+    // 验证邮箱时授予 admin 角色，重新登录后权限展开在令牌里
+    app.post("/api/v1/auth/verify-email", &json!({ "token": mailed_token(&mail, &admin_email) }))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let admin = app.login(&admin_email, "correct horse").await;
+    let admin_claims = app.state.jwt.verify::<Claims>(&admin.token).unwrap();
+    assert!(admin_claims.roles.contains(&"admin".to_string()));
+    assert!(admin_claims.permissions.contains(&"users:manage".to_string()));
+
+    let page: Page<UserAdminView> =
+        app.authed_get(&admin.token, &format!("/api/v1/admin/users?q={}", user.email.to_uppercase())).await.json();
+    assert_eq!(page.items.len(), 1);
+    assert_eq!((page.items[0].id, page.items[0].roles.clone(), page.items[0].disabled), (user_id, vec!["user".to_string()], false));
+    let todos: Page<TodoView> = app.authed_get(&admin.token, &format!("/api/v1/admin/users/{user_id}/todos")).await.json();
+    assert_eq!(todos.items.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), ["private"]);
+
+    // 停用：已签出的访问令牌和刷新令牌立即失效，也不能再登录
+    let admin_id = admin_claims.sub;
+    app.authed_post(&admin.token, &format!("/api/v1/admin/users/{admin_id}/disable"), &json!({}))
+        .await
+        .assert_error(StatusCode::UNPROCESSABLE_ENTITY, "cannot_disable_self");
+    let disabled: UserAdminView =
+        app.authed_post(&admin.token, &format!("/api/v1/admin/users/{user_id}/disable"), &json!({})).await.json();
+    assert!(disabled.disabled);
+    app.authed_get(user.token(), "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "token_revoked");
+    app.post("/api/v1/auth/refresh", &json!({ "refresh_token": user.auth.refresh_token }))
+        .await
+        .assert_error(StatusCode::UNAUTHORIZED, "invalid_refresh_token");
+    app.try_login(None, &user.email, &user.password).await.assert_error(StatusCode::FORBIDDEN, "account_disabled");
+
+    // 启用后重新登录；强制下线只吊销会话
+    app.authed_post(&admin.token, &format!("/api/v1/admin/users/{user_id}/enable"), &json!({})).await.assert_status(StatusCode::OK);
+    let session = app.login(&user.email, &user.password).await;
+    app.authed_get(&session.token, "/api/v1/todos").await.assert_status(StatusCode::OK);
+    app.authed_post(&admin.token, &format!("/api/v1/admin/users/{user_id}/revoke-sessions"), &json!({}))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    app.authed_get(&session.token, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "token_revoked");
+    app.login(&user.email, &user.password).await;
+    let missing = uuid::Uuid::new_v4();
+    app.authed_post(&admin.token, &format!("/api/v1/admin/users/{missing}/disable"), &json!({}))
+        .await
+        .assert_error(StatusCode::NOT_FOUND, "user_not_found");
+
+    let audit = |action: &str| AuditQuery { action: Some(action.into()), limit: 50, ..Default::default() };
+    let denied = app.state.audit.query(&AuditQuery { actor: Some(user_id), ..audit("authz.denied") }).await.unwrap();
+    assert_eq!(denied.items[0].target.as_deref(), Some("users:read"));
+    let managed = app.state.audit.query(&audit("admin.user.disable")).await.unwrap();
+    assert!(managed.items.iter().any(|e| e.actor == Some(admin_id) && e.target == Some(user_id.to_string())));
+}
+
+#[tokio::test]
+async fn revoke_sessions_also_revokes_api_keys() {
+    let admin_email = format!("{}@example.com", uuid::Uuid::new_v4());
+    let app = spawn_app!(|c| c.admin.emails = vec![admin_email.clone()]);
+    let admin = app.register_verified(&admin_email, "correct horse").await;
+    let user = app.register_user().await;
+    let pat = app.create_api_key(user.token(), "ci").await;
+    app.authed_get(&pat, "/api/v1/todos").await.assert_status(StatusCode::OK);
+
+    let user_id = app.state.jwt.verify::<Claims>(user.token()).unwrap().sub;
+    app.authed_post(&admin.token, &format!("/api/v1/admin/users/{user_id}/revoke-sessions"), &json!({}))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    app.authed_get(&pat, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "invalid_token");
+}
@@ -0,0 +1,85 @@
+//! 个人访问令牌（API key）
+
+mod common;
+
+use std::net::IpAddr;
+
+use app_core::NewApiKey;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::spawn_app;
+use dto::{ApiKeyCreated, ApiKeyView};
+use serde_json::json;
+use services_api::auth::Claims;
+use time::OffsetDateTime;
+
+#[tokio::test]
+async fn api_keys_authenticate_machine_clients() {
+    let app = spawn_app!();
+    let user = app.register_user().await;
+    let created: ApiKeyCreated = app
+        .authed_post(user.token(), "/api/v1/api-keys", &json!({ "name": "ci", "scopes": ["todos:read", "todos:read"] }))
+        .await
+        .assert_status(StatusCode::OK)
+        .json();
+    let pat = created.token.as_str();
+    assert!(pat.starts_with("pat_") && pat.starts_with(&created.key.prefix));
+    assert_eq!(created.key.scopes, ["todos:read"]);
+
+    // 只读 key：能读，不能写；也不能管理 key 或登出
+    let ip: IpAddr = "198.51.100.23".parse().unwrap();
+    let get = Request::get("/api/v1/todos").header(header::AUTHORIZATION, format!("Bearer {pat}")).body(Body::empty()).unwrap();
+    app.send_from(ip, get).await.assert_status(StatusCode::OK);
+    app.authed_post(pat, "/api/v1/todos", &json!({ "title": "from ci" })).await.assert_error(StatusCode::FORBIDDEN, "forbidden");
+    app.authed_get(pat, "/api/v1/api-keys").await.assert_error(StatusCode::FORBIDDEN, "session_required");
+    app.authed_post(pat, "/api/v1/auth/logout", &json!({})).await.assert_error(StatusCode::FORBIDDEN, "session_required");
+
+    // 列表不含令牌本身，记录了最后使用时间与来源
+    let keys: Vec<ApiKeyView> = app.authed_get(user.token(), "/api/v1/api-keys").await.json();
+    assert_eq!(keys.len(), 1);
+    assert_eq!((keys[0].prefix.as_str(), keys[0].last_used_ip.as_deref()), (created.key.prefix.as_str(), Some("198.51.100.23")));
+    assert!(keys[0].last_used_at.is_some());
+    assert!(!app.authed_get(user.token(), "/api/v1/api-keys").await.text().contains(pat));
+
+    // 不能授予自己没有的权限；同名冲突
+    app.authed_post(user.token(), "/api/v1/api-keys", &json!({ "name": "ops", "scopes": ["audit:read"] }))
+        .await
+        .assert_error(StatusCode::BAD_REQUEST, "invalid_scope");
+    app.authed_post(user.token(), "/api/v1/api-keys", &json!({ "name": "ci", "scopes": ["todos:write"] }))
+        .await
+        .assert_error(StatusCode::CONFLICT, "api_key_name_taken");
+    let writer: ApiKeyCreated = app
+        .authed_post(user.token(), "/api/v1/api-keys", &json!({ "name": "bot", "scopes": ["todos:write"], "expires_in_days": 1 }))
+        .await
+        .json();
+    app.authed_post(&writer.token, "/api/v1/todos", &json!({ "title": "from bot" })).await.assert_status(StatusCode::OK);
+
+    // 创建 key 不支持幂等键：重放不了令牌，也不能把明文存进 idempotency_keys，所以直接拒绝，也不建 key
+    let body = json!({ "name": "deploy", "scopes": ["todos:read"] });
+    app.idempotent(Method::POST, user.token(), "deploy-key", "/api/v1/api-keys", Some(&body))
+        .await
+        .assert_error(StatusCode::BAD_REQUEST, "idempotency_not_supported");
+    let keys: Vec<ApiKeyView> = app.authed_get(user.token(), "/api/v1/api-keys").await.json();
+    assert!(keys.iter().all(|k| k.name != "deploy"));
+    let stored: i64 = sqlx::query_scalar("select count(*) from idempotency_keys").fetch_one(&app.state.db).await.unwrap();
+    assert_eq!(stored, 0);
+
+    // 吊销立即生效；过期的 key 同样被拒
+    app.authed_delete(user.token(), &format!("/api/v1/api-keys/{}", created.key.id)).await.assert_status(StatusCode::NO_CONTENT);
+    app.authed_get(pat, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "invalid_token");
+    app.authed_delete(user.token(), &format!("/api/v1/api-keys/{}", created.key.id))
+        .await
+        .assert_error(StatusCode::NOT_FOUND, "api_key_not_found");
+    let user_id = app.state.jwt.verify::<Claims>(user.token()).unwrap().sub;
+    let hash = "0".repeat(64);
+    let stale = NewApiKey {
+        name: "stale",
+        prefix: "pat_stale000",
+        token_hash: &hash,
+        scopes: &["todos:read".to_string()],
+        expires_at: OffsetDateTime::now_utc() - time::Duration::minutes(1),
+    };
+    app.state.api_keys.create(user_id, &stale).await.unwrap();
+    assert!(app.state.api_keys.authenticate(&hash, None).await.unwrap().is_none());
+    assert!(app.state.api_keys.purge_expired(OffsetDateTime::now_utc()).await.unwrap() >= 1);
+}
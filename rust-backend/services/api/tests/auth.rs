@@ -0,0 +1,200 @@
+//! 注册、登录与令牌：刷新轮换、密码策略与锁定、JWT 密钥轮换
+
+mod common;
+
+use std::net::IpAddr;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{jwt_key, spawn_app, TestApp};
+use dto::AuthResp;
+use serde_json::{json, Value};
+use services_api::config::{JwtAlg, JwtKeyCfg, Profile};
+use services_api::jwt::JwtKeys;
+
+#[tokio::test]
+async fn register_and_login() {
+    let app = spawn_app!();
+    let email = format!("{}@example.com", uuid::Uuid::new_v4());
+
+    let registered = app.register(&email, "correct horse").await;
+    assert!(!registered.token.is_empty());
+    let session = app.login(&email, "correct horse").await;
+    app.authed_get(&session.token, "/api/v1/todos").await.assert_status(StatusCode::OK);
+
+    app.try_login(None, &email, "wrong password").await.assert_error(StatusCode::UNAUTHORIZED, "invalid_credentials");
+
+    // 重复注册是 409，而不是把唯一约束的数据库报错透给客户端
+    let resp = app
+        .post("/api/v1/auth/register", &json!({ "email": email, "password": "another one" }))
+        .await
+        .assert_error(StatusCode::CONFLICT, "email_taken");
+    assert!(!resp.text().contains("duplicate key"));
+    assert!(!resp.text().contains("UNIQUE constraint"));
+}
+
+#[tokio::test]
+async fn todos_require_bearer_token() {
+    // 请求在鉴权中间件就被拒绝，不需要数据库
+    let app = TestApp::offline();
+
+    app.get("/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "missing_token");
+    app.authed_get("not-a-jwt", "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "invalid_token");
+    app.get("/healthz").await.assert_status(StatusCode::OK);
+}
+
+#[tokio::test]
+async fn jwt_key_rotation_and_jwks() {
+    let ttl = time::Duration::minutes(60);
+    let now = time::OffsetDateTime::now_utc();
+    let claims = json!({ "sub": uuid::Uuid::new_v4(), "exp": (now + ttl).unix_timestamp(), "jti": uuid::Uuid::new_v4() });
+    let kid_of = |token: &str| jsonwebtoken::decode_header(token).unwrap().kid.unwrap();
+
+    let old = jwt_key("ed-1", JwtAlg::EdDSA, "dev-ed25519");
+    let token = JwtKeys::from_cfg(std::slice::from_ref(&old), ttl).unwrap().sign(&claims).unwrap();
+    assert_eq!(kid_of(&token), "ed-1");
+
+    // 轮换期：旧密钥在令牌有效期内就要退役，不再签发，但仍能验签；新密钥接手签发
+    let rotating = vec![
+        JwtKeyCfg { retire_at: Some(now + time::Duration::minutes(30)), ..old.clone() },
+        JwtKeyCfg { not_before: Some(now - time::Duration::minutes(1)), ..jwt_key("rsa-2", JwtAlg::RS256, "dev-rsa") },
+    ];
+    let keys = JwtKeys::from_cfg(&rotating, ttl).unwrap();
+    assert_eq!(kid_of(&keys.sign(&claims).unwrap()), "rsa-2");
+    assert_eq!(keys.verify::<Value>(&token).unwrap()["jti"], claims["jti"]);
+
+    // 退役后旧令牌失效
+    let retired = vec![JwtKeyCfg { retire_at: Some(now - time::Duration::seconds(1)), ..old }, rotating[1].clone()];
+    let app = TestApp::offline_with(|cfg| cfg.jwt.keys = retired);
+    app.authed_get(&token, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "invalid_token");
+
+    // 不带 kid 的 HS256 令牌（旧版本签发的格式）一律拒绝
+    let hs = jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"x")).unwrap();
+    app.authed_get(&hs, "/api/v1/todos").await.assert_status(StatusCode::UNAUTHORIZED);
+
+    let resp = app.get("/.well-known/jwks.json").await.assert_status(StatusCode::OK);
+    assert!(resp.headers[header::CACHE_CONTROL].to_str().unwrap().contains("max-age"));
+    resp.assert_json(json!({ "keys": [{ "kid": "rsa-2", "kty": "RSA", "e": "AQAB" }] }));
+}
+
+#[tokio::test]
+async fn refresh_rotation_reuse_and_logout() {
+    let app = spawn_app!();
+    let user = app.register_user().await;
+    assert_eq!(user.auth.expires_in, 3600);
+    let r1 = user.auth.refresh_token.clone();
+
+    let rotated: AuthResp =
+        app.post("/api/v1/auth/refresh", &json!({ "refresh_token": r1 })).await.assert_status(StatusCode::OK).json();
+    let r2 = rotated.refresh_token.clone();
+    assert_ne!(r1, r2);
+    app.authed_get(&rotated.token, "/api/v1/todos").await.assert_status(StatusCode::OK);
+
+    // 旧令牌被重放：整族吊销，连刚换出来的 r2 也不能用了
+    app.post("/api/v1/auth/refresh", &json!({ "refresh_token": r1 }))
+        .await
+        .assert_error(StatusCode::UNAUTHORIZED, "refresh_token_reused");
+    app.post("/api/v1/auth/refresh", &json!({ "refresh_token": r2 }))
+        .await
+        .assert_error(StatusCode::UNAUTHORIZED, "invalid_refresh_token");
+
+    // 登出后访问令牌进黑名单，刷新令牌所在的族一并吊销
+    let session = app.login(&user.email, &user.password).await;
+    app.authed_post(&session.token, "/api/v1/auth/logout", &json!({ "refresh_token": session.refresh_token }))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    app.authed_get(&session.token, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "token_revoked");
+    app.post("/api/v1/auth/refresh", &json!({ "refresh_token": session.refresh_token }))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    app.post("/api/v1/auth/logout", &json!({})).await.assert_error(StatusCode::UNAUTHORIZED, "missing_token");
+}
+
+#[tokio::test]
+async fn password_policy_lockout_and_rehash() {
+    let app = spawn_app!();
+    let email = format!("{}@example.com", uuid::Uuid::new_v4());
+
+    app.post("/api/v1/auth/register", &json!({ "email": email, "password": "Password123" }))
+        .await
+        .assert_error(StatusCode::BAD_REQUEST, "password_breached");
+    app.register(&email, "correct horse").await;
+
+    // 换一组 Argon2 参数重启后，成功登录会把存量哈希升级到新参数
+    let upgraded = app.reconfigure(|cfg| cfg.password.argon2.m_cost = 2048);
+    upgraded.login(&email, "correct horse").await;
+    let (_, hash) = upgraded.state.users.find_by_email(&email).await.unwrap().unwrap();
+    assert!(hash.contains("m=2048"), "{hash}");
+
+    // 连续失败达到阈值后，正确密码也被拒绝，直到锁定到期
+    for _ in 0..3 {
+        app.try_login(None, &email, "wrong password").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+    let resp = app.try_login(None, &email, "correct horse").await.assert_error(StatusCode::TOO_MANY_REQUESTS, "account_locked");
+    assert_eq!(resp.headers[header::RETRY_AFTER], "30");
+
+    // 同一 IP 换着邮箱试也会被锁
+    let ip = IpAddr::from(*uuid::Uuid::new_v4().as_bytes().first_chunk::<4>().unwrap());
+    for _ in 0..4 {
+        let other = format!("{}@example.com", uuid::Uuid::new_v4());
+        app.try_login(Some(ip), &other, "wrong password").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+    app.try_login(Some(ip), "someone@example.com", "whatever")
+        .await
+        .assert_error(StatusCode::TOO_MANY_REQUESTS, "ip_locked");
+
+    // 经可信代理转发时按真实客户端计数：一个客户端被锁，同一代理后面的其他人照常登录
+    let proxied = app.reconfigure(|c| c.rate_limit.trusted_proxies = vec!["10.0.0.0/8".parse().unwrap()]);
+    let victim = proxied.register_user().await;
+    let via_proxy = |client: IpAddr, email: &str, password: &str| {
+        Request::post("/api/v1/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-forwarded-for", client.to_string())
+            .body(Body::from(serde_json::to_vec(&json!({ "email": email, "password": password })).unwrap()))
+            .unwrap()
+    };
+    let proxy: IpAddr = "10.1.2.3".parse().unwrap();
+    let attacker = IpAddr::from(*uuid::Uuid::new_v4().as_bytes().first_chunk::<4>().unwrap());
+    for _ in 0..4 {
+        let other = format!("{}@example.com", uuid::Uuid::new_v4());
+        proxied.send_from(proxy, via_proxy(attacker, &other, "wrong password")).await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+    proxied
+        .send_from(proxy, via_proxy(attacker, &victim.email, &victim.password))
+        .await
+        .assert_error(StatusCode::TOO_MANY_REQUESTS, "ip_locked");
+    let neighbour = IpAddr::from(*uuid::Uuid::new_v4().as_bytes().first_chunk::<4>().unwrap());
+    proxied.send_from(proxy, via_proxy(neighbour, &victim.email, &victim.password)).await.assert_status(StatusCode::OK);
+
+    // 过了窗口的失败计数由计划任务删除，锁定中的保留到到期
+    let expired = app.reconfigure(|c| c.password.lockout.window_secs = 0);
+    let purged = expired.state.passwords.purge_attempts().await.unwrap();
+    assert!(purged >= 8, "{purged}");
+    let unlocked: i64 = sqlx::query_scalar("select count(*) from login_attempts where locked_until is null")
+        .fetch_one(&app.state.db)
+        .await
+        .unwrap();
+    assert_eq!(unlocked, 0);
+    app.try_login(Some(ip), "someone@example.com", "whatever")
+        .await
+        .assert_error(StatusCode::TOO_MANY_REQUESTS, "ip_locked");
+}
+
+#[test]
+fn prod_profile_rejects_dev_signing_keys() {
+    let mut cfg = common::test_cfg();
+    cfg.check_profile().unwrap();
+
+    // 仓库里的开发密钥：按文件位置或 kid 识别
+    cfg.profile = Profile::Prod;
+    let err = cfg.check_profile().unwrap_err().to_string();
+    assert!(err.contains("test-ed25519"), "{err}");
+    let mut key = jwt_key("dev-ed25519", JwtAlg::EdDSA, "dev-ed25519");
+    key.private_key = Some("/run/secrets/jwt.pem".into());
+    key.public_key = "/run/secrets/jwt.pub.pem".into();
+    cfg.jwt.keys = vec![key];
+    assert!(cfg.check_profile().is_err());
+
+    cfg.jwt.keys[0].kid = "prod-2026-10".into();
+    cfg.check_profile().unwrap();
+}
@@ -0,0 +1,72 @@
+//! 命令行客户端对真实服务端
+
+mod common;
+
+use axum::http::StatusCode;
+use clap::Parser;
+use common::spawn_app;
+use dto::TodoView;
+
+/// 以 `creds` 为凭据文件运行一条 todo-cli 命令，返回 stdout
+async fn cli(creds: &std::path::Path, args: &[&str]) -> anyhow::Result<String> {
+    let argv = ["todo-cli", "--credentials", creds.to_str().unwrap()].into_iter().chain(args.iter().copied());
+    let cli = todo_cli::Cli::try_parse_from(argv)?;
+    let mut out = Vec::new();
+    todo_cli::run(cli, &mut out).await?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[tokio::test]
+async fn cli_logs_in_follows_pagination_and_refreshes() {
+    let app = spawn_app!();
+    let server = format!("http://{}", app.serve().await);
+    let user = app.register_user().await;
+    let creds = std::env::temp_dir().join(format!("todo-cli-{}", uuid::Uuid::new_v4())).join("credentials.json");
+
+    let err = cli(&creds, &["list"]).await.unwrap_err();
+    assert!(err.to_string().starts_with("not logged in"), "{err}");
+    cli(&creds, &["--server", &server, "login", "--email", &user.email, "--password", &user.password]).await.unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&creds).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    for title in ["write report", "buy milk", "call mom"] {
+        cli(&creds, &["add", title]).await.unwrap();
+    }
+    // 每页 2 条，取完为止
+    let all: Vec<TodoView> = serde_json::from_str(&cli(&creds, &["list", "--page-size", "2", "-o", "json"]).await.unwrap()).unwrap();
+    assert_eq!(all.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), ["call mom", "buy milk", "write report"]);
+    let first: Vec<TodoView> = serde_json::from_str(&cli(&creds, &["list", "--oldest-first", "--limit", "1", "-o", "json"]).await.unwrap()).unwrap();
+    assert_eq!(first[0].title, "write report");
+
+    let milk = all[1].id.to_string();
+    cli(&creds, &["complete", &milk]).await.unwrap();
+    let table = cli(&creds, &["list", "--done"]).await.unwrap();
+    let lines: Vec<&str> = table.lines().collect();
+    assert!(lines[0].starts_with("ID") && lines[0].contains("TITLE"), "{table}");
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].starts_with(&milk) && lines[1].contains(" x ") && lines[1].contains("buy milk"), "{table}");
+
+    cli(&creds, &["delete", &milk]).await.unwrap();
+    let err = cli(&creds, &["complete", &milk]).await.unwrap_err();
+    let api = err.downcast_ref::<todo_cli::ApiError>().expect("an API error");
+    assert_eq!((api.status.as_u16(), api.body.code.as_str()), (404, "todo_not_found"));
+
+    // 访问令牌失效时用刷新令牌续期，并把新令牌写回文件
+    let mut saved = todo_cli::Credentials::load(&creds).unwrap().unwrap();
+    let old_refresh = saved.refresh_token.clone();
+    saved.token = "expired".into();
+    saved.save(&creds).unwrap();
+    cli(&creds, &["list"]).await.unwrap();
+    let saved = todo_cli::Credentials::load(&creds).unwrap().unwrap();
+    assert_ne!(saved.refresh_token, old_refresh);
+    app.authed_get(&saved.token, "/api/v1/todos").await.assert_status(StatusCode::OK);
+
+    // 登出吊销会话并删除凭据文件
+    cli(&creds, &["logout"]).await.unwrap();
+    assert!(!creds.exists());
+    app.authed_get(&saved.token, "/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "token_revoked");
+    std::fs::remove_dir_all(creds.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,486 @@
+//! 进程内测试夹具：`TestApp` 在临时数据库上组装 `AppState`，用 `oneshot` 直接驱动 `Router`，
+//! 不监听端口。每个 `TestApp` 独占一个库，测试之间互不影响。
+
+// 各测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::body::{Body, Bytes};
+use axum::extract::ConnectInfo;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use dto::{ApiKeyCreated, AuthResp, LoginReq, RegisterReq};
+use http_body_util::BodyExt;
+use mock_oidc::MockCfg;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use services_api::config::{
+    AccountCfg, AdminCfg, AppCfg, Argon2Cfg, DbCfg, GrpcCfg, IdempotencyCfg, IdempotencyStoreKind, JwtAlg, JwtCfg, JwtKeyCfg,
+    LockoutCfg, LogCfg, MailCfg, MailerKind, OidcCfg, OutboxCfg, PaginationCfg, PasswordCfg, PublisherKind, RateLimitCfg, RateLimitRule,
+    NotifierKind, Profile, SchedulerCfg, ServerCfg, SmtpCfg, SmtpTls, StreamCfg, UnverifiedAccess,
+};
+use services_api::auth::Claims;
+use services_api::grpc;
+use services_api::mailer::{InMemoryMailer, Mailer};
+use services_api::routes::router;
+use services_api::state::{with_pool, AppState};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+/// 使用 config/keys 下的开发密钥
+pub fn jwt_key(kid: &str, alg: JwtAlg, file: &str) -> JwtKeyCfg {
+    let path = |suffix: &str| format!("{}/../../config/keys/{file}{suffix}", env!("CARGO_MANIFEST_DIR"));
+    JwtKeyCfg {
+        kid: kid.into(),
+        alg,
+        private_key: Some(path(".pem")),
+        public_key: path(".pub.pem"),
+        not_before: None,
+        retire_at: None,
+    }
+}
+
+/// 在随机端口上启动 mock-oidc（用 config/keys 下的 RSA 开发密钥签名），返回它的 issuer
+pub async fn serve_mock_oidc() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let key = |suffix: &str| format!("{}/../../config/keys/dev-rsa{suffix}", env!("CARGO_MANIFEST_DIR")).into();
+    let cfg = MockCfg {
+        addr: listener.local_addr().unwrap().to_string(),
+        issuer: None,
+        client_id: "todo".into(),
+        email: "dev@example.com".into(),
+        private_key: key(".pem"),
+        public_key: key(".pub.pem"),
+    };
+    let app = mock_oidc::router(&cfg).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    cfg.issuer()
+}
+
+/// `db.url` 不会被读取：连接池由 `TestApp` 自己建
+pub fn test_cfg() -> AppCfg {
+    AppCfg {
+        profile: Profile::Dev,
+        server: ServerCfg { addr: "127.0.0.1:0".into(), drain_delay_secs: 0, shutdown_timeout_secs: 5 },
+        db: DbCfg { url: String::new() },
+        jwt: JwtCfg { keys: vec![jwt_key("test-ed25519", JwtAlg::EdDSA, "dev-ed25519")], exp_minutes: 60, refresh_days: 30 },
+        log: LogCfg { json: false, level: "info".into() },
+        pagination: PaginationCfg { cursor_secret: "testcursor".into() },
+        password: PasswordCfg {
+            min_length: 8,
+            max_length: 128,
+            breached_list: Some(concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/breached-passwords.txt").into()),
+            // 测试用低成本参数，哈希够快
+            argon2: Argon2Cfg { m_cost: 1024, t_cost: 1, p_cost: 1 },
+            lockout: LockoutCfg { account_threshold: 3, ip_threshold: 4, base_secs: 30, max_secs: 3600, window_secs: 86400 },
+        },
+        // 宽松到其他测试碰不到；限流测试自己收紧
+        rate_limit: RateLimitCfg {
+            trusted_proxies: vec![],
+            auth: RateLimitRule { per_minute: 6000, burst: 1000 },
+            api: RateLimitRule { per_minute: 6000, burst: 1000 },
+        },
+        // 不启动 dispatcher，测试自己调用 `outbox::dispatch_once`；重试不等待
+        outbox: OutboxCfg {
+            publisher: PublisherKind::Memory,
+            jsonl_path: String::new(),
+            poll_interval_ms: 1000,
+            batch_size: 100,
+            lease_secs: 30,
+            max_attempts: 2,
+            retry_base_secs: 0,
+            retry_max_secs: 0,
+            max_pending: 10_000,
+            retention_days: 7,
+        },
+        scheduler: SchedulerCfg {
+            notifier: NotifierKind::Memory,
+            poll_interval_ms: 1000,
+            batch_size: 100,
+            lease_secs: 60,
+            max_attempts: 2,
+            retry_secs: 0,
+            maintenance_interval_secs: 60,
+            retention_days: 7,
+        },
+        stream: StreamCfg { buffer: 64, ping_secs: 30 },
+        grpc: GrpcCfg { addr: "127.0.0.1:0".into() },
+        idempotency: IdempotencyCfg { store: IdempotencyStoreKind::Db, ttl_secs: 3600, lock_secs: 60 },
+        admin: AdminCfg { emails: vec![] },
+        mail: MailCfg {
+            mailer: MailerKind::Memory,
+            from: "Todo <no-reply@example.com>".into(),
+            file_dir: String::new(),
+            smtp: SmtpCfg { host: "localhost".into(), port: 1025, tls: SmtpTls::None, username: None, password: None },
+        },
+        // 不限制未验证邮箱的账号；邮箱验证测试自己收紧
+        account: AccountCfg {
+            token_secret: "testaccount".into(),
+            public_url: "http://app.test".into(),
+            verify_ttl_hours: 48,
+            reset_ttl_minutes: 30,
+            unverified: UnverifiedAccess::Full,
+        },
+        // 关闭；OIDC 测试指向进程内的 mock-oidc
+        oidc: OidcCfg {
+            issuer: None,
+            client_id: "todo".into(),
+            client_secret: None,
+            redirect_uri: "http://app.test/oidc/callback".into(),
+            scopes: vec!["openid".into(), "email".into()],
+            login_ttl_secs: 600,
+        },
+    }
+}
+
+/// 迁移好的空库：SQLite 后端用内存库；Postgres 后端在 `TEST_DATABASE_URL` 所在的服务器上新建一个库，
+/// 未设置时返回 `None`
+#[cfg(feature = "sqlite")]
+async fn test_db() -> Option<(infra::Db, DbGuard)> {
+    let db = infra::connect("sqlite::memory:").await.unwrap();
+    infra::migrate(&db).await.unwrap();
+    Some((db, DbGuard(None)))
+}
+
+#[cfg(feature = "postgres")]
+async fn test_db() -> Option<(infra::Db, DbGuard)> {
+    use sqlx::Connection;
+
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL not set, skipping");
+        return None;
+    };
+    let name = format!("test_{}", uuid::Uuid::new_v4().simple());
+    let mut admin = sqlx::PgConnection::connect(&url).await.unwrap();
+    sqlx::query(&format!(r#"create database "{name}""#)).execute(&mut admin).await.unwrap();
+    let guard = DbGuard(Some((url.clone(), name.clone())));
+    let mut db_url = reqwest::Url::parse(&url).unwrap();
+    db_url.set_path(&name);
+    let db = infra::connect(db_url.as_str()).await.unwrap();
+    infra::migrate(&db).await.unwrap();
+    Some((db, guard))
+}
+
+/// Postgres 测试库的 `(TEST_DATABASE_URL, 库名)`：最后一个用到它的 `TestApp` 释放时删库，
+/// 并行的测试与重复运行之间不共享数据。SQLite 内存库随连接池消失，为 `None`
+struct DbGuard(Option<(String, String)>);
+
+impl Drop for DbGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "postgres")]
+        if let Some((url, name)) = self.0.take() {
+            use sqlx::Connection;
+            // Drop 里不能 await，测试的运行时也不能再 block_on：换一个线程、一个临时运行时；
+            // 连接池可能还在别的任务里，`with (force)` 断开残留连接
+            let dropped = std::thread::spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+                rt.block_on(async {
+                    let mut admin = sqlx::PgConnection::connect(&url).await?;
+                    sqlx::query(&format!(r#"drop database if exists "{name}" with (force)"#)).execute(&mut admin).await
+                })
+            })
+            .join();
+            if let Ok(Err(e)) = dropped {
+                eprintln!("dropping test database failed: {e}");
+            }
+        }
+    }
+}
+
+/// 不会真正连库的连接池
+fn lazy_db() -> infra::Db {
+    let url = if cfg!(feature = "sqlite") { "sqlite::memory:" } else { "postgres://localhost/unused" };
+    infra::connect_lazy(url).unwrap()
+}
+
+/// 连库的 `TestApp`（参数同 [`TestApp::spawn_with`]）；Postgres 后端没有可用的库时直接结束当前测试
+#[allow(unused_macros)]
+macro_rules! spawn_app {
+    () => {
+        spawn_app!(|_| {})
+    };
+    ($f:expr) => {
+        match $crate::common::TestApp::spawn_with($f).await {
+            Some(app) => app,
+            None => return,
+        }
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use spawn_app;
+
+pub struct TestApp {
+    pub router: Router,
+    pub state: AppState,
+    /// `reconfigure` 与 `with_mailer` 得到的 `TestApp` 共用同一个库
+    db: Option<Arc<DbGuard>>,
+}
+
+/// 注册得到的账号；`auth` 是注册时签发的会话
+pub struct TestUser {
+    pub email: String,
+    pub password: String,
+    pub auth: AuthResp,
+}
+
+impl TestUser {
+    pub fn token(&self) -> &str {
+        &self.auth.token
+    }
+}
+
+impl TestApp {
+    /// 需要数据库的测试用；Postgres 后端没有可用的库时返回 `None`，测试直接跳过
+    pub async fn spawn() -> Option<Self> {
+        Self::spawn_with(|_| {}).await
+    }
+
+    pub async fn spawn_with(f: impl FnOnce(&mut AppCfg)) -> Option<Self> {
+        let (db, guard) = test_db().await?;
+        Some(Self { db: Some(Arc::new(guard)), ..Self::build(db, f) })
+    }
+
+    /// 请求在到达仓储之前就会返回的测试用（鉴权、文档、错误体），不连库
+    pub fn offline() -> Self {
+        Self::offline_with(|_| {})
+    }
+
+    pub fn offline_with(f: impl FnOnce(&mut AppCfg)) -> Self {
+        Self::build(lazy_db(), f)
+    }
+
+    /// 同一个库、另一份配置，模拟改配置后重启
+    pub fn reconfigure(&self, f: impl FnOnce(&mut AppCfg)) -> Self {
+        Self { db: self.db.clone(), ..Self::build(self.state.db.clone(), f) }
+    }
+
+    /// 换上测试自己持有的邮件实现，通常是 `InMemoryMailer`，以便取出邮件里的链接
+    pub fn with_mailer(&self, mailer: Arc<dyn Mailer>) -> Self {
+        let state = AppState { mailer, ..self.state.clone() };
+        Self { router: router(state.clone()), state, db: self.db.clone() }
+    }
+
+    fn build(db: infra::Db, f: impl FnOnce(&mut AppCfg)) -> Self {
+        let mut cfg = test_cfg();
+        f(&mut cfg);
+        let state = with_pool(cfg, db).unwrap();
+        Self { router: router(state.clone()), state, db: None }
+    }
+
+    /// 在随机端口上真正监听，WebSocket 这类需要连接升级的测试用；服务随测试的运行时结束
+    pub async fn serve(&self) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = self.router.clone().into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    /// 在随机端口上启动 gRPC 服务，与 `router` 共用同一个 `AppState`
+    pub async fn serve_grpc(&self) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(grpc::serve(listener, self.state.clone(), CancellationToken::new()));
+        addr
+    }
+
+    pub async fn send(&self, req: Request<Body>) -> TestResponse {
+        let resp = self.router.clone().oneshot(req).await.unwrap();
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        TestResponse { status, headers, body }
+    }
+
+    /// 模拟来自 `ip` 的连接（`ConnectInfo`）
+    pub async fn send_from(&self, ip: IpAddr, mut req: Request<Body>) -> TestResponse {
+        req.extensions_mut().insert(ConnectInfo(SocketAddr::new(ip, 40000)));
+        self.send(req).await
+    }
+
+    pub async fn call(&self, method: Method, uri: &str, token: Option<&str>, body: Option<&impl Serialize>) -> TestResponse {
+        let mut req = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            req = req.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let req = match body {
+            Some(body) => req
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(body).unwrap())),
+            None => req.body(Body::empty()),
+        };
+        self.send(req.unwrap()).await
+    }
+
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.call(Method::GET, uri, None, None::<&()>).await
+    }
+
+    pub async fn post(&self, uri: &str, body: &impl Serialize) -> TestResponse {
+        self.call(Method::POST, uri, None, Some(body)).await
+    }
+
+    pub async fn authed_get(&self, token: &str, uri: &str) -> TestResponse {
+        self.call(Method::GET, uri, Some(token), None::<&()>).await
+    }
+
+    pub async fn authed_post(&self, token: &str, uri: &str, body: &impl Serialize) -> TestResponse {
+        self.call(Method::POST, uri, Some(token), Some(body)).await
+    }
+
+    pub async fn authed_patch(&self, token: &str, uri: &str, body: &impl Serialize) -> TestResponse {
+        self.call(Method::PATCH, uri, Some(token), Some(body)).await
+    }
+
+    pub async fn authed_delete(&self, token: &str, uri: &str) -> TestResponse {
+        self.call(Method::DELETE, uri, Some(token), None::<&()>).await
+    }
+
+    /// 带 `Idempotency-Key` 的写请求
+    pub async fn idempotent(
+        &self,
+        method: Method,
+        token: &str,
+        key: &str,
+        uri: &str,
+        body: Option<&impl Serialize>,
+    ) -> TestResponse {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .header("idempotency-key", key);
+        let req = match body {
+            Some(body) => req
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(body).unwrap())),
+            None => req.body(Body::empty()),
+        };
+        self.send(req.unwrap()).await
+    }
+
+    /// 注册并断言成功
+    pub async fn register(&self, email: &str, password: &str) -> AuthResp {
+        let req = RegisterReq { email: email.into(), password: password.into() };
+        self.post("/api/v1/auth/register", &req).await.assert_status(StatusCode::OK).json()
+    }
+
+    /// 注册、把邮箱标记为已验证后重新登录；`[admin] emails` 里的地址由此拿到 admin 角色
+    pub async fn register_verified(&self, email: &str, password: &str) -> AuthResp {
+        let auth = self.register(email, password).await;
+        let user_id = self.state.jwt.verify::<Claims>(&auth.token).unwrap().sub;
+        self.state.users.mark_email_verified(user_id).await.unwrap();
+        self.login(email, password).await
+    }
+
+    /// 登录并断言成功
+    pub async fn login(&self, email: &str, password: &str) -> AuthResp {
+        self.try_login(None, email, password).await.assert_status(StatusCode::OK).json()
+    }
+
+    /// 原样返回登录响应；`ip` 模拟 `ConnectInfo` 中的客户端地址
+    pub async fn try_login(&self, ip: Option<IpAddr>, email: &str, password: &str) -> TestResponse {
+        let body = LoginReq { email: email.into(), password: password.into() };
+        let mut req = Request::post("/api/v1/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&body).unwrap()))
+            .unwrap();
+        if let Some(ip) = ip {
+            req.extensions_mut().insert(ConnectInfo(SocketAddr::new(ip, 40000)));
+        }
+        self.send(req).await
+    }
+
+    /// 用随机邮箱注册一个新用户
+    pub async fn register_user(&self) -> TestUser {
+        let email = format!("{}@example.com", uuid::Uuid::new_v4());
+        let password = "correct horse".to_string();
+        let auth = self.register(&email, &password).await;
+        TestUser { email, password, auth }
+    }
+
+    /// 用登录会话创建一个只读的个人访问令牌，返回令牌明文
+    pub async fn create_api_key(&self, token: &str, name: &str) -> String {
+        let body = json!({ "name": name, "scopes": ["todos:read"] });
+        let created: ApiKeyCreated = self.authed_post(token, "/api/v1/api-keys", &body).await.assert_status(StatusCode::OK).json();
+        created.token
+    }
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    #[track_caller]
+    pub fn assert_status(self, expected: StatusCode) -> Self {
+        assert_eq!(self.status, expected, "body: {}", String::from_utf8_lossy(&self.body));
+        self
+    }
+
+    /// 按 dto 类型解析响应体
+    #[track_caller]
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("{e}: {}", String::from_utf8_lossy(&self.body)))
+    }
+
+    /// 空响应体为 `Null`
+    pub fn value(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or(Value::Null)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// 断言响应体包含 `expected` 中的所有字段（见 [`assert_json_include`]）
+    #[track_caller]
+    pub fn assert_json(self, expected: Value) -> Self {
+        assert_json_include(&self.value(), &expected);
+        self
+    }
+
+    /// 断言是统一错误体，且状态码与 `code` 符合预期
+    #[track_caller]
+    pub fn assert_error(self, status: StatusCode, code: &str) -> Self {
+        let this = self.assert_status(status);
+        assert_eq!(this.value()["code"], code, "body: {}", this.text());
+        this
+    }
+}
+
+/// `actual` 至少包含 `expected` 中的字段与值：对象允许多出的键，数组要求长度相同，
+/// 两者都逐项递归比较；标量必须相等。失败时给出第一个不符的路径
+#[track_caller]
+pub fn assert_json_include(actual: &Value, expected: &Value) {
+    if let Err(path) = json_include(actual, expected, "$") {
+        panic!("JSON mismatch at {path}\n  actual:   {actual}\n  expected: {expected}");
+    }
+}
+
+fn json_include(actual: &Value, expected: &Value, path: &str) -> Result<(), String> {
+    match (actual, expected) {
+        (Value::Object(a), Value::Object(e)) => e.iter().try_for_each(|(k, ev)| {
+            let av = a.get(k).ok_or_else(|| format!("{path}.{k} (missing)"))?;
+            json_include(av, ev, &format!("{path}.{k}"))
+        }),
+        (Value::Array(a), Value::Array(e)) if a.len() == e.len() => {
+            a.iter().zip(e).enumerate().try_for_each(|(i, (av, ev))| json_include(av, ev, &format!("{path}[{i}]")))
+        }
+        _ if actual == expected => Ok(()),
+        _ => Err(path.to_string()),
+    }
+}
+
+/// 发给 `to` 的最后一封邮件里链接的令牌
+pub fn mailed_token(mail: &InMemoryMailer, to: &str) -> String {
+    let email = mail.sent().into_iter().rev().find(|e| e.to == to).expect("no email sent");
+    let (_, rest) = email.body.split_once("token=").expect("email contains a link");
+    rest.split_whitespace().next().unwrap().to_string()
+}
@@ -0,0 +1,151 @@
+//! 领域事件：outbox 投递与 WebSocket 推送
+
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use app_core::OutboxEvent;
+use axum::http::{header, StatusCode};
+use common::{assert_json_include, spawn_app};
+use dto::{ReadinessView, TodoStreamMsg, TodoView};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use services_api::outbox::{dispatch_once, EventPublisher, InMemoryPublisher, JsonlPublisher};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+/// 对指定 todo 的事件一律发布失败，其余照常
+struct FailingFor(uuid::Uuid, AtomicU32);
+
+#[async_trait::async_trait]
+impl EventPublisher for FailingFor {
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        if event.aggregate_id == self.0 {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            anyhow::bail!("broker unavailable");
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn todo_writes_emit_outbox_events() {
+    let app = spawn_app!();
+    let cfg = &app.state.cfg.outbox;
+    let user = app.register_user().await;
+    let todo: TodoView = app.authed_post(user.token(), "/api/v1/todos", &json!({ "title": "ship it" })).await.json();
+    let uri = format!("/api/v1/todos/{}", todo.id);
+    // 写入失败时事务回滚，事件也不会留下
+    app.authed_post(user.token(), "/api/v1/todos", &json!({ "title": "ship it" }))
+        .await
+        .assert_error(StatusCode::CONFLICT, "todo_title_conflict");
+    app.authed_patch(user.token(), &uri, &json!({ "done": true })).await.assert_status(StatusCode::OK);
+    app.authed_delete(user.token(), &uri).await.assert_status(StatusCode::NO_CONTENT);
+
+    let publisher = InMemoryPublisher::default();
+    dispatch_once(app.state.outbox.as_ref(), &publisher, cfg).await.unwrap();
+    let events: Vec<_> = publisher.events().into_iter().filter(|e| e.aggregate_id == todo.id).collect();
+    let types: Vec<_> = events.iter().map(|e| e.event_type.as_str()).collect();
+    assert_eq!(types, ["TodoCreated", "TodoUpdated", "TodoDeleted"]);
+    assert_json_include(&events[1].payload, &json!({ "id": todo.id, "title": "ship it", "done": true }));
+    // 删除事件带着删除前的最后状态
+    assert_json_include(&events[2].payload, &json!({ "id": todo.id, "done": true }));
+
+    // 已投递的事件不会再被领取
+    let again = InMemoryPublisher::default();
+    dispatch_once(app.state.outbox.as_ref(), &again, cfg).await.unwrap();
+    assert!(again.events().iter().all(|e| e.aggregate_id != todo.id));
+
+    // 发布失败按次数重试（测试配置不退避），用完 max_attempts 进死信，之后不再投递
+    let flaky: TodoView = app.authed_post(user.token(), "/api/v1/todos", &json!({ "title": "flaky" })).await.json();
+    let failing = FailingFor(flaky.id, AtomicU32::new(0));
+    for _ in 0..3 {
+        dispatch_once(app.state.outbox.as_ref(), &failing, cfg).await.unwrap();
+    }
+    assert_eq!(failing.1.load(Ordering::SeqCst), 2);
+    assert!(app.state.outbox.stats().await.unwrap().dead >= 1);
+    let ready: ReadinessView = app.get("/readyz").await.assert_status(StatusCode::OK).json();
+    assert!(ready.checks["outbox"].detail.as_deref().unwrap().contains("dead"));
+
+    // jsonl 发布器：每个事件一行
+    let path = std::env::temp_dir().join(format!("outbox-{}.jsonl", uuid::Uuid::new_v4()));
+    let jsonl = JsonlPublisher::open(&path).unwrap();
+    for event in &events {
+        jsonl.publish(event).await.unwrap();
+    }
+    let lines: Vec<Value> = std::fs::read_to_string(&path).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(lines.len(), 3);
+    assert_json_include(&lines[0], &json!({ "event_type": "TodoCreated", "aggregate_type": "todo", "aggregate_id": todo.id }));
+}
+
+/// 与仓储写入 outbox 的事件同形
+fn todo_event(user_id: uuid::Uuid, title: &str) -> OutboxEvent {
+    let now = time::OffsetDateTime::now_utc();
+    let id = uuid::Uuid::new_v4();
+    OutboxEvent {
+        id: uuid::Uuid::new_v4(),
+        aggregate_type: "todo".into(),
+        aggregate_id: id,
+        user_id,
+        event_type: "TodoCreated".into(),
+        payload: json!({ "id": id, "user_id": user_id, "title": title, "done": false, "created_at": dto::fmt_time(now) }),
+        attempts: 1,
+        created_at: now,
+    }
+}
+
+async fn next_msg(ws: &mut WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>) -> TodoStreamMsg {
+    let msg = tokio::time::timeout(std::time::Duration::from_secs(5), ws.next()).await.unwrap().unwrap().unwrap();
+    serde_json::from_str(msg.to_text().unwrap()).unwrap()
+}
+
+// 默认的单线程运行时：连续发布时服务端的推送任务插不进来，能稳定地制造积压
+#[tokio::test]
+async fn todo_stream_pushes_own_events_and_resyncs_on_lag() {
+    let app = spawn_app!(|cfg| cfg.stream.buffer = 4);
+    let addr = app.serve().await;
+    let alice = app.register_user().await;
+    let bob = app.register_user().await;
+    let (alice_id, _) = app.state.users.find_by_email(&alice.email).await.unwrap().unwrap();
+    let (bob_id, _) = app.state.users.find_by_email(&bob.email).await.unwrap().unwrap();
+    let url = format!("ws://{addr}/api/v1/todos/stream");
+
+    // 没有令牌：握手阶段就是 401
+    let Err(WsError::Http(resp)) = connect_async(url.as_str()).await else { panic!("upgrade without a token") };
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // 浏览器的写法：令牌放在子协议里，服务端选定 todo.v1
+    let mut req = url.as_str().into_client_request().unwrap();
+    let protocols = format!("todo.v1, bearer.{}", alice.token());
+    req.headers_mut().insert(header::SEC_WEBSOCKET_PROTOCOL, protocols.parse().unwrap());
+    let (mut ws, resp) = connect_async(req).await.unwrap();
+    assert_eq!(resp.headers()[header::SEC_WEBSOCKET_PROTOCOL], "todo.v1");
+
+    // 经由 outbox 的发布器进入推送通道；别人的事件不推送
+    app.state.publisher.publish(&todo_event(bob_id, "bob's")).await.unwrap();
+    let mine = todo_event(alice_id, "alice's");
+    app.state.publisher.publish(&mine).await.unwrap();
+    let TodoStreamMsg::Event { event_id, event_type, todo } = next_msg(&mut ws).await else { panic!("expected an event") };
+    assert_eq!((event_id, event_type.as_str(), todo.title.as_str()), (mine.id, "TodoCreated", "alice's"));
+
+    // 通道容量 4，一次发 6 条：先收到 resync，再收到仍在通道里的 4 条
+    for i in 0..6 {
+        app.state.publisher.publish(&todo_event(alice_id, &format!("burst {i}"))).await.unwrap();
+    }
+    assert!(matches!(next_msg(&mut ws).await, TodoStreamMsg::Resync { missed: 2 }));
+    for i in 2..6 {
+        let TodoStreamMsg::Event { todo, .. } = next_msg(&mut ws).await else { panic!("expected an event") };
+        assert_eq!(todo.title, format!("burst {i}"));
+    }
+
+    // 查询参数同样可以；退出时服务端主动以 1001 关闭
+    let (mut ws2, _) = connect_async(format!("{url}?access_token={}", bob.token())).await.unwrap();
+    app.state.shutdown.cancel();
+    for ws in [&mut ws, &mut ws2] {
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(5), ws.next()).await.unwrap().unwrap().unwrap();
+        let WsMessage::Close(Some(frame)) = msg else { panic!("expected a close frame, got {msg:?}") };
+        assert_eq!(u16::from(frame.code), 1001);
+    }
+}
@@ -0,0 +1,76 @@
+//! gRPC 与 REST 共用数据和令牌
+
+mod common;
+
+use axum::http::StatusCode;
+use common::spawn_app;
+use futures_util::TryStreamExt;
+use serde_json::json;
+use services_api::grpc::pb::{self, todo_service_client::TodoServiceClient};
+
+fn grpc_req<T>(token: &str, msg: T) -> tonic::Request<T> {
+    let mut req = tonic::Request::new(msg);
+    req.metadata_mut().insert("authorization", format!("Bearer {token}").parse().unwrap());
+    req
+}
+
+fn assert_grpc_error<T: std::fmt::Debug>(res: Result<T, tonic::Status>, code: tonic::Code, message: &str) {
+    let status = res.expect_err("expected an error status");
+    assert_eq!((status.code(), status.message()), (code, message));
+}
+
+#[tokio::test]
+async fn grpc_shares_todos_and_tokens_with_rest() {
+    let app = spawn_app!();
+    let addr = app.serve_grpc().await;
+    let mut client = TodoServiceClient::connect(format!("http://{addr}")).await.unwrap();
+    let alice = app.register_user().await;
+    let bob = app.register_user().await;
+
+    let create = |title: &str| pb::CreateTodoRequest { title: title.into(), ..Default::default() };
+    assert_grpc_error(client.create(create("no token")).await, tonic::Code::Unauthenticated, "missing_token");
+    assert_grpc_error(client.create(grpc_req("nope", create("bad token"))).await, tonic::Code::Unauthenticated, "invalid_token");
+
+    // 同一个 TodoService：gRPC 写入的 REST 能看到，反之亦然
+    let milk = client.create(grpc_req(alice.token(), create("buy milk"))).await.unwrap().into_inner();
+    app.authed_post(alice.token(), "/api/v1/todos", &json!({ "title": "call mom" })).await.assert_status(StatusCode::OK);
+    app.authed_get(alice.token(), "/api/v1/todos")
+        .await
+        .assert_json(json!({ "items": [{ "title": "call mom" }, { "id": milk.id, "title": "buy milk", "done": false }] }));
+    assert_grpc_error(
+        client.create(grpc_req(alice.token(), create("buy milk"))).await,
+        tonic::Code::AlreadyExists,
+        "todo_title_conflict",
+    );
+
+    let done = client.complete(grpc_req(alice.token(), pb::TodoId { id: milk.id.clone() })).await.unwrap().into_inner();
+    assert!(done.done);
+    // 别人的 todo 与不存在的一样
+    assert_grpc_error(
+        client.complete(grpc_req(bob.token(), pb::TodoId { id: milk.id.clone() })).await,
+        tonic::Code::NotFound,
+        "todo_not_found",
+    );
+    assert_grpc_error(
+        client.delete(grpc_req(alice.token(), pb::TodoId { id: "42".into() })).await,
+        tonic::Code::InvalidArgument,
+        "invalid_id",
+    );
+
+    // 服务端流式列表：按条件逐条推送，只有自己的
+    let list = |done, oldest_first| pb::ListTodosRequest { done, q: String::new(), oldest_first };
+    let titles = |todos: Vec<pb::Todo>| todos.into_iter().map(|t| t.title).collect::<Vec<_>>();
+    let all = client.list(grpc_req(alice.token(), list(None, true))).await.unwrap().into_inner();
+    assert_eq!(titles(all.try_collect().await.unwrap()), ["buy milk", "call mom"]);
+    let open = client.list(grpc_req(alice.token(), list(Some(false), false))).await.unwrap().into_inner();
+    assert_eq!(titles(open.try_collect().await.unwrap()), ["call mom"]);
+    let none = client.list(grpc_req(bob.token(), list(None, false))).await.unwrap().into_inner();
+    assert!(none.try_collect::<Vec<_>>().await.unwrap().is_empty());
+
+    client.delete(grpc_req(alice.token(), pb::TodoId { id: milk.id.clone() })).await.unwrap();
+    app.authed_get(alice.token(), "/api/v1/todos").await.assert_json(json!({ "items": [{ "title": "call mom" }] }));
+
+    // REST 登出吊销的令牌，gRPC 同样拒绝
+    app.authed_post(alice.token(), "/api/v1/auth/logout", &json!({})).await.assert_status(StatusCode::NO_CONTENT);
+    assert_grpc_error(client.list(grpc_req(alice.token(), list(None, false))).await, tonic::Code::Unauthenticated, "token_revoked");
+}
@@ -0,0 +1,63 @@
+//! 与业务无关的 HTTP 约定：错误体、OpenAPI 文档
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn errors_use_the_json_envelope() {
+    let app = TestApp::offline();
+
+    // 上游传入的 x-request-id 原样回写到响应头与错误体
+    let req = Request::post("/api/v1/auth/register")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("x-request-id", "req-123")
+        .body(Body::from("{not json"))
+        .unwrap();
+    let resp = app.send(req).await.assert_error(StatusCode::BAD_REQUEST, "invalid_json");
+    assert_eq!(resp.headers["x-request-id"], "req-123");
+    resp.assert_json(json!({ "request_id": "req-123" }));
+
+    let body = app
+        .post("/api/v1/auth/register", &json!({ "email": "a", "password": "short" }))
+        .await
+        .assert_error(StatusCode::BAD_REQUEST, "validation_failed")
+        .assert_json(json!({ "message": "invalid fields: email, password" }))
+        .value();
+    assert!(body["request_id"].is_string());
+
+    app.get("/api/v1/auth/nope").await.assert_error(StatusCode::NOT_FOUND, "route_not_found");
+}
+
+/// 规范与提交的快照不一致时失败；有意修改接口后用
+/// `UPDATE_OPENAPI_SNAPSHOT=1 cargo test -p api openapi` 重新生成并一起提交
+#[test]
+fn openapi_matches_snapshot() {
+    use utoipa::OpenApi;
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+    let spec = services_api::openapi::ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+    if std::env::var_os("UPDATE_OPENAPI_SNAPSHOT").is_some() {
+        std::fs::write(path, &spec).unwrap();
+        return;
+    }
+    let snapshot = std::fs::read_to_string(path).unwrap_or_default();
+    if let Some((i, (want, got))) = snapshot.lines().zip(spec.lines()).enumerate().find(|(_, (a, b))| a != b) {
+        panic!("openapi.json line {}: snapshot has `{want}`, generated `{got}`; rerun with UPDATE_OPENAPI_SNAPSHOT=1 if intended", i + 1);
+    }
+    assert_eq!(snapshot.lines().count(), spec.lines().count(), "openapi.json length changed; rerun with UPDATE_OPENAPI_SNAPSHOT=1 if intended");
+}
+
+#[tokio::test]
+async fn openapi_and_swagger_ui_are_public() {
+    let app = TestApp::offline();
+
+    let spec = app.get("/api/v1/openapi.json").await.assert_status(StatusCode::OK).value();
+    assert!(spec["paths"]["/api/v1/todos/{id}"]["patch"].is_object());
+    assert!(spec["components"]["schemas"]["ErrorBody"].is_object());
+
+    let html = app.get("/api/v1/docs/").await.assert_status(StatusCode::OK).text();
+    assert!(html.contains("swagger-ui"));
+}
@@ -0,0 +1,69 @@
+//! 幂等键
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{spawn_app, TestUser};
+use dto::{Page, TodoView};
+use serde_json::json;
+use services_api::auth::Claims;
+use services_api::config::IdempotencyStoreKind;
+use time::OffsetDateTime;
+
+#[tokio::test]
+async fn idempotency_key_replays_and_rejects_reuse() {
+    for store in [IdempotencyStoreKind::Db, IdempotencyStoreKind::Memory] {
+        let app = spawn_app!(|c| c.idempotency.store = store);
+        let alice = app.register_user().await;
+        let bob = app.register_user().await;
+        let create = |user: &TestUser, key: &str, title: &str| {
+            let (token, key, body) = (user.token().to_string(), key.to_string(), json!({ "title": title }));
+            let app = &app;
+            async move { app.idempotent(Method::POST, &token, &key, "/api/v1/todos", Some(&body)).await }
+        };
+
+        // 重试只执行一次，响应原样重放
+        let first = create(&alice, "k1", "milk").await.assert_status(StatusCode::OK);
+        assert!(first.headers.get("idempotent-replayed").is_none());
+        let again = create(&alice, "k1", "milk").await.assert_status(StatusCode::OK);
+        assert_eq!(again.headers["idempotent-replayed"], "true");
+        assert_eq!(again.json::<TodoView>().id, first.json::<TodoView>().id);
+        let page: Page<TodoView> = app.authed_get(alice.token(), "/api/v1/todos").await.json();
+        assert_eq!(page.items.len(), 1);
+
+        // 同一个键换了请求体；键按用户隔离
+        create(&alice, "k1", "eggs").await.assert_error(StatusCode::UNPROCESSABLE_ENTITY, "idempotency_key_reused");
+        create(&bob, "k1", "milk").await.assert_status(StatusCode::OK);
+
+        // 4xx 也会重放，不会重新执行
+        create(&alice, "k2", "milk").await.assert_error(StatusCode::CONFLICT, "todo_title_conflict");
+        let replayed = create(&alice, "k2", "milk").await.assert_error(StatusCode::CONFLICT, "todo_title_conflict");
+        assert_eq!(replayed.headers["idempotent-replayed"], "true");
+
+        // 删除的重试仍是 204，而不是 404
+        let uri = format!("/api/v1/todos/{}", first.json::<TodoView>().id);
+        for _ in 0..2 {
+            app.idempotent(Method::DELETE, alice.token(), "k3", &uri, None::<&()>).await.assert_status(StatusCode::NO_CONTENT);
+        }
+
+        // 第一次请求还没执行完；锁过期后（进程崩溃）同一个键可以重新执行
+        let claims: Claims = app.state.jwt.verify(alice.token()).unwrap();
+        let now = OffsetDateTime::now_utc();
+        app.state.idempotency.begin(claims.sub, "k4", "other", now + time::Duration::minutes(1)).await.unwrap();
+        create(&alice, "k4", "bread").await.assert_error(StatusCode::CONFLICT, "idempotency_request_in_progress");
+        app.state.idempotency.begin(claims.sub, "k5", "other", now - time::Duration::seconds(1)).await.unwrap();
+        create(&alice, "k5", "bread").await.assert_status(StatusCode::OK);
+
+        create(&alice, &"k".repeat(256), "tea").await.assert_error(StatusCode::BAD_REQUEST, "invalid_idempotency_key");
+    }
+
+    // 过期的键会被清理，之后同一个键重新执行
+    let app = spawn_app!(|c| c.idempotency.ttl_secs = 0);
+    let user = app.register_user().await;
+    let body = json!({ "title": "expires" });
+    app.idempotent(Method::POST, user.token(), "k", "/api/v1/todos", Some(&body)).await.assert_status(StatusCode::OK);
+    app.idempotent(Method::POST, user.token(), "k", "/api/v1/todos", Some(&body))
+        .await
+        .assert_error(StatusCode::CONFLICT, "todo_title_conflict");
+    assert!(app.state.idempotency.purge_expired().await.unwrap() >= 1);
+}
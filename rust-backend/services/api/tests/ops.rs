@@ -0,0 +1,117 @@
+//! 运维面：限流、就绪探针与指标
+
+mod common;
+
+use std::net::IpAddr;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::spawn_app;
+use dto::{CheckStatus, ReadinessView, TodoView};
+use serde_json::json;
+use services_api::config::RateLimitRule;
+
+#[tokio::test]
+async fn rate_limits_by_client_ip_and_user() {
+    let app = spawn_app!(|cfg| {
+        cfg.rate_limit.trusted_proxies = vec!["10.0.0.0/8".parse().unwrap()];
+        cfg.rate_limit.auth = RateLimitRule { per_minute: 1, burst: 2 };
+        cfg.rate_limit.api = RateLimitRule { per_minute: 1, burst: 2 };
+    });
+    let refresh = |xff: Option<&str>| {
+        let mut req = Request::post("/api/v1/auth/refresh").header(header::CONTENT_TYPE, "application/json");
+        if let Some(xff) = xff {
+            req = req.header("x-forwarded-for", xff);
+        }
+        req.body(Body::from(r#"{"refresh_token":"nope"}"#)).unwrap()
+    };
+    let client: IpAddr = "203.0.113.7".parse().unwrap();
+    let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+
+    let resp = app.send_from(client, refresh(None)).await.assert_status(StatusCode::UNAUTHORIZED);
+    assert_eq!(resp.headers["ratelimit-limit"], "2");
+    assert_eq!(resp.headers["ratelimit-remaining"], "1");
+    // 经可信代理转发：按 X-Forwarded-For 中第一个不可信的地址计数，最左边客户端自填的值不算
+    app.send_from(proxy, refresh(Some("198.51.100.1, 203.0.113.7, 10.0.0.9"))).await.assert_status(StatusCode::UNAUTHORIZED);
+    let resp = app.send_from(client, refresh(None)).await.assert_error(StatusCode::TOO_MANY_REQUESTS, "rate_limited");
+    assert_eq!(resp.headers["ratelimit-remaining"], "0");
+    let retry_after: u64 = resp.headers[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry_after), "retry-after {retry_after}");
+    assert_eq!(resp.headers["ratelimit-reset"], retry_after.to_string().as_str());
+
+    // 不可信的对端伪造 X-Forwarded-For 无效，仍按对端地址计数
+    let other: IpAddr = "192.0.2.1".parse().unwrap();
+    app.send_from(other, refresh(Some("203.0.113.99"))).await.assert_status(StatusCode::UNAUTHORIZED);
+    app.send_from(other, refresh(Some("203.0.113.98"))).await.assert_status(StatusCode::UNAUTHORIZED);
+    app.send_from(other, refresh(Some("203.0.113.97"))).await.assert_error(StatusCode::TOO_MANY_REQUESTS, "rate_limited");
+
+    // 登录后的接口按用户计数，互不影响（注册不带 ConnectInfo，不计入 IP 配额）
+    let alice = app.register_user().await;
+    let bob = app.register_user().await;
+    for _ in 0..2 {
+        app.authed_get(alice.token(), "/api/v1/todos").await.assert_status(StatusCode::OK);
+    }
+    app.authed_get(alice.token(), "/api/v1/todos").await.assert_error(StatusCode::TOO_MANY_REQUESTS, "rate_limited");
+    app.authed_get(bob.token(), "/api/v1/todos").await.assert_status(StatusCode::OK);
+    // 未登录的请求先被鉴权拒绝，不消耗任何人的配额
+    app.get("/api/v1/todos").await.assert_error(StatusCode::UNAUTHORIZED, "missing_token");
+
+    let metrics = app.get("/metrics").await.text();
+    assert!(metrics.contains(r#"http_rate_limited_total{group="api"}"#));
+}
+
+#[tokio::test]
+async fn readiness_reports_dependencies_and_fails_on_shutdown() {
+    let app = spawn_app!();
+
+    let ready: ReadinessView = app.get("/readyz").await.assert_status(StatusCode::OK).json();
+    assert_eq!(ready.status, CheckStatus::Ok);
+    assert_eq!(ready.checks["database"].status, CheckStatus::Ok);
+    assert_eq!(ready.checks["outbox"].status, CheckStatus::Ok);
+    assert_eq!(ready.checks["migrations"].detail.as_deref(), Some("applied 13, expected 13"));
+
+    // 收到退出信号：探针先失败，存活探针不受影响
+    app.state.shutdown.cancel();
+    app.get("/readyz")
+        .await
+        .assert_status(StatusCode::SERVICE_UNAVAILABLE)
+        .assert_json(json!({ "status": "fail", "checks": { "shutdown": { "status": "fail" }, "database": { "status": "ok" } } }));
+    app.get("/healthz").await.assert_status(StatusCode::OK);
+
+    // 后台任务在取消后退出
+    let cancel = tokio_util::sync::CancellationToken::new();
+    let task = tokio::spawn(services_api::scheduler::run_scheduler(app.state.clone(), cancel.clone()));
+    cancel.cancel();
+    tokio::time::timeout(std::time::Duration::from_secs(5), task).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn metrics_use_route_templates_and_skip_auth() {
+    let app = spawn_app!();
+    let user = app.register_user().await;
+    let todo: TodoView = app.authed_post(user.token(), "/api/v1/todos", &json!({ "title": "scrape me" })).await.json();
+    app.authed_patch(user.token(), &format!("/api/v1/todos/{}", todo.id), &json!({ "done": true }))
+        .await
+        .assert_status(StatusCode::OK);
+    app.get("/api/v1/todos").await.assert_status(StatusCode::UNAUTHORIZED);
+    services_api::tasks::maintenance_once(&app.state).await;
+
+    // 不带令牌也能抓取
+    let resp = app.get("/metrics").await.assert_status(StatusCode::OK);
+    assert!(resp.headers[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+    let text = resp.text();
+    for line in [
+        r#"http_requests_total{method="PATCH",route="/api/v1/todos/:id",status="200"}"#,
+        r#"http_requests_total{method="GET",route="/api/v1/todos",status="401"}"#,
+        r#"http_request_duration_seconds_bucket{method="POST",route="/api/v1/todos",le="0.005"}"#,
+        r#"db_pool_connections{state="idle"}"#,
+        "db_pool_max_connections",
+        "db_pool_acquire_wait_seconds",
+        r#"maintenance_runs_total{job="purge_blank_todos",outcome="ok"}"#,
+        r#"maintenance_deleted_total{job="purge_expired_sessions"}"#,
+    ] {
+        assert!(text.contains(line), "missing {line}");
+    }
+    // 路径参数不会变成标签值
+    assert!(!text.contains(&todo.id.to_string()));
+}
@@ -0,0 +1,132 @@
+//! 提醒与重复 todo 的调度
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{assert_json_include, spawn_app, TestApp};
+use dto::{Page, TodoView};
+use serde_json::{json, Value};
+use services_api::notifier::InMemoryNotifier;
+use time::OffsetDateTime;
+
+/// 把到期的任务跑完
+async fn run_jobs(app: &TestApp, notifier: &InMemoryNotifier) {
+    while services_api::scheduler::run_due_jobs(&app.state, notifier).await.unwrap() >= app.state.cfg.scheduler.batch_size as usize {}
+}
+
+fn reminded(notifier: &InMemoryNotifier, todo: &TodoView) -> usize {
+    notifier.sent().iter().filter(|n| n.todo.id == todo.id).count()
+}
+
+#[tokio::test]
+async fn reminders_fire_once_and_recurring_todos_roll_over() {
+    let app = spawn_app!();
+    let alice = app.register_user().await;
+    let notifier = InMemoryNotifier::default();
+    let past = dto::fmt_time(OffsetDateTime::now_utc() - time::Duration::minutes(1));
+    let future = dto::fmt_time(OffsetDateTime::now_utc() + time::Duration::hours(1));
+    let (app, alice) = (&app, &alice);
+    let create = |body: Value| async move {
+        app.authed_post(alice.token(), "/api/v1/todos", &body).await.assert_status(StatusCode::OK).json::<TodoView>()
+    };
+
+    // 规则与时间在入口校验
+    for rule in ["FREQ=HOURLY", "FREQ=DAILY;BYDAY=MO", "FREQ=DAILY;COUNT=2;UNTIL=20300101", "BYDAY=MO"] {
+        app.authed_post(alice.token(), "/api/v1/todos", &json!({ "title": "bad rule", "recurrence": rule }))
+            .await
+            .assert_error(StatusCode::BAD_REQUEST, "invalid_recurrence");
+    }
+    app.authed_post(alice.token(), "/api/v1/todos", &json!({ "title": "bad time", "due_at": "tomorrow" }))
+        .await
+        .assert_error(StatusCode::BAD_REQUEST, "validation_failed");
+
+    // 到点的提醒只发一次：重复执行、两个实例并发领取都不会再发，没到点的不发
+    let water = create(json!({ "title": "water plants", "remind_at": past })).await;
+    let taxes = create(json!({ "title": "file taxes", "remind_at": future })).await;
+    run_jobs(app, &notifier).await;
+    tokio::join!(run_jobs(app, &notifier), run_jobs(app, &notifier));
+    assert_eq!(reminded(&notifier, &water), 1);
+    assert_eq!(reminded(&notifier, &taxes), 0);
+    let sent = notifier.sent().into_iter().find(|n| n.todo.id == water.id).unwrap();
+    assert_eq!(sent.email, alice.email);
+
+    // 改标题不会重发；把提醒改到过去会再发一次；清空提醒、先完成的都不发
+    app.authed_patch(alice.token(), &format!("/api/v1/todos/{}", water.id), &json!({ "title": "water the plants" }))
+        .await
+        .assert_status(StatusCode::OK);
+    app.authed_patch(alice.token(), &format!("/api/v1/todos/{}", taxes.id), &json!({ "remind_at": past }))
+        .await
+        .assert_status(StatusCode::OK);
+    let stretch = create(json!({ "title": "stretch", "remind_at": past })).await;
+    app.authed_patch(alice.token(), &format!("/api/v1/todos/{}", stretch.id), &json!({ "remind_at": null }))
+        .await
+        .assert_status(StatusCode::OK)
+        .assert_json(json!({ "remind_at": null }));
+    let call = create(json!({ "title": "call mom", "remind_at": past })).await;
+    app.authed_post(alice.token(), &format!("/api/v1/todos/{}/complete", call.id), &json!({})).await.assert_status(StatusCode::OK);
+    run_jobs(app, &notifier).await;
+    assert_eq!(reminded(&notifier, &water), 1);
+    assert_eq!(reminded(&notifier, &taxes), 1);
+    assert_eq!(reminded(&notifier, &stretch), 0);
+    assert_eq!(reminded(&notifier, &call), 0);
+
+    // 完成重复的 todo 生成下一次，截止与提醒一起平移；COUNT 用完不再生成
+    let sync = create(json!({
+        "title": "team sync",
+        "due_at": "2030-01-07T09:00:00Z",
+        "remind_at": "2030-01-07T08:30:00Z",
+        "recurrence": "RRULE:FREQ=weekly;BYDAY=TH,MO;COUNT=2",
+    }))
+    .await;
+    assert_eq!(sync.recurrence.as_deref(), Some("FREQ=WEEKLY;BYDAY=MO,TH;COUNT=2"));
+    let open = |q: &'static str| async move {
+        app.authed_get(alice.token(), &format!("/api/v1/todos?done=false&q={q}")).await.assert_status(StatusCode::OK).json::<Page<TodoView>>().items
+    };
+    // 完成的这一次改名为带日期的标题，原标题让给下一次
+    app.authed_post(alice.token(), &format!("/api/v1/todos/{}/complete", sync.id), &json!({}))
+        .await
+        .assert_status(StatusCode::OK)
+        .assert_json(json!({ "title": "team sync (2030-01-07)", "done": true }));
+    let next = open("sync").await;
+    assert_eq!(next.len(), 1);
+    assert_json_include(
+        &serde_json::to_value(&next[0]).unwrap(),
+        &json!({
+            "title": "team sync",
+            "done": false,
+            "due_at": "2030-01-10T09:00:00Z",
+            "remind_at": "2030-01-10T08:30:00Z",
+            "recurrence": "FREQ=WEEKLY;BYDAY=MO,TH;COUNT=1",
+        }),
+    );
+    // 再完成一次不会重复生成；下一次还开着时重新打开完成的这一次也不冲突，重新打开不生成下一次
+    app.authed_post(alice.token(), &format!("/api/v1/todos/{}/complete", sync.id), &json!({})).await.assert_status(StatusCode::OK);
+    assert_eq!(open("sync").await.len(), 1);
+    app.authed_patch(alice.token(), &format!("/api/v1/todos/{}", sync.id), &json!({ "done": false }))
+        .await
+        .assert_status(StatusCode::OK)
+        .assert_json(json!({ "title": "team sync (2030-01-07)", "done": false }));
+    assert_eq!(open("sync").await.len(), 2);
+    // COUNT 用完的最后一次完成时不再生成，也不改名
+    app.authed_post(alice.token(), &format!("/api/v1/todos/{}/complete", next[0].id), &json!({}))
+        .await
+        .assert_status(StatusCode::OK)
+        .assert_json(json!({ "title": "team sync", "done": true }));
+    assert_eq!(open("sync").await.len(), 1);
+
+    // 执行失败按次数重试，用完转为 dead；dead 与完成的任务一样过了保留期就删掉
+    app.state.jobs.ensure("unknown.kind", "x", OffsetDateTime::now_utc() - time::Duration::minutes(1)).await.unwrap();
+    run_jobs(app, &notifier).await;
+    run_jobs(app, &notifier).await;
+    let status: String = sqlx::query_scalar("select status from jobs where kind = 'unknown.kind'").fetch_one(&app.state.db).await.unwrap();
+    assert_eq!(status, "dead");
+    assert!(app.state.jobs.purge_finished(OffsetDateTime::now_utc() + time::Duration::seconds(1)).await.unwrap() >= 1);
+    let left: i64 = sqlx::query_scalar("select count(*) from jobs where kind = 'unknown.kind'").fetch_one(&app.state.db).await.unwrap();
+    assert_eq!(left, 0);
+
+    // 没有 31 日的月份跳过
+    let rent = create(json!({ "title": "pay rent", "due_at": "2030-01-31T12:00:00Z", "recurrence": "FREQ=MONTHLY" })).await;
+    app.authed_post(alice.token(), &format!("/api/v1/todos/{}/complete", rent.id), &json!({})).await.assert_status(StatusCode::OK);
+    let next = open("rent").await;
+    assert_eq!(next[0].due_at.as_deref(), Some("2030-03-31T12:00:00Z"));
+}
@@ -0,0 +1,98 @@
+//! todo 的增删改查与分页
+
+mod common;
+
+use axum::http::StatusCode;
+use common::spawn_app;
+use dto::{Page, TodoView};
+use serde_json::json;
+
+#[tokio::test]
+async fn todo_update_complete_delete() {
+    let app = spawn_app!();
+    let alice = app.register_user().await;
+    let bob = app.register_user().await;
+    let milk = json!({ "title": "buy milk" });
+
+    let todo: TodoView = app.authed_post(alice.token(), "/api/v1/todos", &milk).await.assert_status(StatusCode::OK).json();
+    let uri = format!("/api/v1/todos/{}", todo.id);
+
+    // 标题在同一用户下唯一，不同用户互不影响
+    app.authed_post(alice.token(), "/api/v1/todos", &milk).await.assert_error(StatusCode::CONFLICT, "todo_title_conflict");
+    app.authed_post(bob.token(), "/api/v1/todos", &milk).await.assert_status(StatusCode::OK);
+
+    app.authed_patch(alice.token(), &uri, &json!({ "title": "buy oat milk" }))
+        .await
+        .assert_status(StatusCode::OK)
+        .assert_json(json!({ "title": "buy oat milk", "done": false }));
+
+    app.authed_post(alice.token(), &format!("{uri}/complete"), &json!({}))
+        .await
+        .assert_status(StatusCode::OK)
+        .assert_json(json!({ "done": true }));
+
+    // 完成的 todo 仍然占着标题，重新打开总能成功
+    app.authed_post(alice.token(), "/api/v1/todos", &json!({ "title": "buy oat milk" }))
+        .await
+        .assert_error(StatusCode::CONFLICT, "todo_title_conflict");
+    app.authed_patch(alice.token(), &uri, &json!({ "done": false }))
+        .await
+        .assert_status(StatusCode::OK)
+        .assert_json(json!({ "title": "buy oat milk", "done": false }));
+
+    // 别人的 todo 一律 404
+    app.authed_delete(bob.token(), &uri).await.assert_error(StatusCode::NOT_FOUND, "todo_not_found");
+
+    app.authed_delete(alice.token(), &uri).await.assert_status(StatusCode::NO_CONTENT);
+    app.authed_delete(alice.token(), &uri).await.assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn list_todos_paginates_with_signed_cursors() {
+    let app = spawn_app!();
+    let alice = app.register_user().await;
+    let bob = app.register_user().await;
+    for title in ["write report", "buy milk", "Buy bread", "call mom", "100% done"] {
+        app.authed_post(alice.token(), "/api/v1/todos", &json!({ "title": title })).await.assert_status(StatusCode::OK);
+    }
+    let milk: Page<TodoView> = app.authed_get(alice.token(), "/api/v1/todos?q=milk").await.json();
+    let complete = format!("/api/v1/todos/{}/complete", milk.items[0].id);
+    app.authed_post(alice.token(), &complete, &json!({})).await.assert_status(StatusCode::OK);
+
+    // 默认新的在前，逐页翻到底
+    let mut titles = Vec::new();
+    let mut uri = "/api/v1/todos?limit=2".to_string();
+    loop {
+        let page: Page<TodoView> = app.authed_get(alice.token(), &uri).await.assert_status(StatusCode::OK).json();
+        titles.extend(page.items.into_iter().map(|t| t.title));
+        let Some(next) = page.next_cursor else { break };
+        uri = format!("/api/v1/todos?limit=2&cursor={next}");
+    }
+    assert_eq!(titles, ["100% done", "call mom", "Buy bread", "buy milk", "write report"]);
+
+    let page: Page<TodoView> = app.authed_get(alice.token(), "/api/v1/todos?sort=created_at&limit=1").await.json();
+    assert_eq!(page.items[0].title, "write report");
+    let asc_cursor = page.next_cursor.unwrap();
+
+    // 过滤：done、大小写不敏感的子串，`%` 按字面匹配
+    app.authed_get(alice.token(), "/api/v1/todos?done=true")
+        .await
+        .assert_json(json!({ "items": [{ "title": "buy milk", "done": true }] }));
+    app.authed_get(alice.token(), "/api/v1/todos?q=BUY&done=false")
+        .await
+        .assert_json(json!({ "items": [{ "title": "Buy bread" }], "next_cursor": null }));
+    app.authed_get(alice.token(), "/api/v1/todos?q=%25").await.assert_json(json!({ "items": [{ "title": "100% done" }] }));
+
+    // 游标被篡改、换了排序、或拿给别人用，一律 400
+    let tampered = format!("{}A", &asc_cursor);
+    for (user, uri) in [
+        (&alice, format!("/api/v1/todos?sort=created_at&cursor={tampered}")),
+        (&alice, format!("/api/v1/todos?cursor={asc_cursor}")),
+        (&bob, format!("/api/v1/todos?sort=created_at&cursor={asc_cursor}")),
+    ] {
+        app.authed_get(user.token(), &uri).await.assert_error(StatusCode::BAD_REQUEST, "invalid_cursor");
+    }
+
+    app.authed_get(alice.token(), "/api/v1/todos?limit=0").await.assert_error(StatusCode::BAD_REQUEST, "validation_failed");
+    app.authed_get(alice.token(), "/api/v1/todos?sort=title").await.assert_error(StatusCode::BAD_REQUEST, "invalid_query");
+}
@@ -0,0 +1,250 @@
+//! `mock-oidc`：离线开发与测试用的 OIDC 身份提供方，只实现授权码 + PKCE 流程需要的四个端点：
+//! 发现文档、JWKS、授权与换取令牌。
+//!
+//! 没有登录页：授权请求直接“同意”，用户由 `login_hint` 指定（缺省为 `--email`），
+//! 附加参数 `email_verified=false` 模拟邮箱未经验证的账号。ID 令牌用 RS256 签名。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context};
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use clap::Parser;
+use dto::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use simple_asn1::ASN1Block;
+use time::{Duration, OffsetDateTime};
+
+/// 授权码的有效期
+const CODE_TTL: Duration = Duration::minutes(1);
+const ID_TOKEN_TTL: Duration = Duration::minutes(5);
+const KID: &str = "mock-rsa";
+
+#[derive(Debug, Clone, Parser)]
+#[command(name = "mock-oidc", version, about = "离线测试用的 OIDC 身份提供方")]
+pub struct MockCfg {
+    #[arg(long, env = "MOCK_OIDC_ADDR", default_value = "127.0.0.1:9000")]
+    pub addr: String,
+    /// 对外的 issuer，默认 `http://{addr}`；必须与服务端 `[oidc] issuer` 完全一致
+    #[arg(long, env = "MOCK_OIDC_ISSUER")]
+    pub issuer: Option<String>,
+    /// 只接受这个 client_id
+    #[arg(long, default_value = "todo")]
+    pub client_id: String,
+    /// 授权请求没有 `login_hint` 时登录的用户
+    #[arg(long, default_value = "dev@example.com")]
+    pub email: String,
+    #[arg(long, default_value = "config/keys/dev-rsa.pem")]
+    pub private_key: PathBuf,
+    #[arg(long, default_value = "config/keys/dev-rsa.pub.pem")]
+    pub public_key: PathBuf,
+}
+
+impl MockCfg {
+    pub fn issuer(&self) -> String {
+        self.issuer.clone().unwrap_or_else(|| format!("http://{}", self.addr))
+    }
+}
+
+/// 已签发、尚未换取的授权码
+struct Grant {
+    redirect_uri: String,
+    nonce: Option<String>,
+    code_challenge: String,
+    email: String,
+    email_verified: bool,
+    expires_at: OffsetDateTime,
+}
+
+struct Provider {
+    issuer: String,
+    client_id: String,
+    default_email: String,
+    key: EncodingKey,
+    jwks: JwkSet,
+    grants: Mutex<HashMap<String, Grant>>,
+}
+
+pub fn router(cfg: &MockCfg) -> anyhow::Result<Router> {
+    let read = |path: &PathBuf| std::fs::read(path).with_context(|| format!("reading {}", path.display()));
+    let key = EncodingKey::from_rsa_pem(&read(&cfg.private_key)?)?;
+    let jwk = rsa_jwk(&read(&cfg.public_key)?).context("parsing RSA public key")?;
+    let provider = Provider {
+        issuer: cfg.issuer(),
+        client_id: cfg.client_id.clone(),
+        default_email: cfg.email.clone(),
+        key,
+        jwks: JwkSet { keys: vec![jwk] },
+        grants: Mutex::new(HashMap::new()),
+    };
+    Ok(Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/jwks", get(jwks))
+        .route("/authorize", get(authorize))
+        .route("/token", post(token))
+        .with_state(Arc::new(provider)))
+}
+
+async fn discovery(State(p): State<Arc<Provider>>) -> Json<serde_json::Value> {
+    Json(json!({
+        "issuer": p.issuer,
+        "authorization_endpoint": format!("{}/authorize", p.issuer),
+        "token_endpoint": format!("{}/token", p.issuer),
+        "jwks_uri": format!("{}/jwks", p.issuer),
+        "response_types_supported": ["code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["RS256"],
+        "code_challenge_methods_supported": ["S256"],
+    }))
+}
+
+async fn jwks(State(p): State<Arc<Provider>>) -> Json<JwkSet> {
+    Json(p.jwks.clone())
+}
+
+#[derive(Deserialize)]
+struct AuthorizeParams {
+    response_type: String,
+    client_id: String,
+    redirect_uri: String,
+    state: Option<String>,
+    nonce: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
+    login_hint: Option<String>,
+    email_verified: Option<bool>,
+}
+
+/// 参数有误时按 OAuth 2.0 的约定不跳回 `redirect_uri`，直接返回 400
+async fn authorize(State(p): State<Arc<Provider>>, Query(q): Query<AuthorizeParams>) -> Response {
+    if q.response_type != "code" || q.client_id != p.client_id {
+        return oauth_error("unauthorized_client");
+    }
+    let (Some(code_challenge), Some("S256")) = (q.code_challenge, q.code_challenge_method.as_deref()) else {
+        return oauth_error("invalid_request");
+    };
+    let Ok(mut location) = url::Url::parse(&q.redirect_uri) else { return oauth_error("invalid_request") };
+    let code = random_string();
+    let grant = Grant {
+        redirect_uri: q.redirect_uri,
+        nonce: q.nonce,
+        code_challenge,
+        email: q.login_hint.unwrap_or_else(|| p.default_email.clone()),
+        email_verified: q.email_verified.unwrap_or(true),
+        expires_at: OffsetDateTime::now_utc() + CODE_TTL,
+    };
+    p.grants.lock().expect("grants mutex poisoned").insert(code.clone(), grant);
+    location.query_pairs_mut().append_pair("code", &code);
+    if let Some(state) = &q.state {
+        location.query_pairs_mut().append_pair("state", state);
+    }
+    (StatusCode::FOUND, [(header::LOCATION, location.to_string())]).into_response()
+}
+
+#[derive(Deserialize)]
+struct TokenParams {
+    grant_type: String,
+    code: String,
+    redirect_uri: String,
+    client_id: String,
+    code_verifier: String,
+}
+
+#[derive(Serialize)]
+struct IdClaims<'a> {
+    iss: &'a str,
+    sub: String,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    email: String,
+    email_verified: bool,
+}
+
+/// 授权码只能换一次；redirect_uri、client_id 与 PKCE 校验码都要和授权请求对得上
+async fn token(State(p): State<Arc<Provider>>, Form(f): Form<TokenParams>) -> Response {
+    if f.grant_type != "authorization_code" {
+        return oauth_error("unsupported_grant_type");
+    }
+    let grant = p.grants.lock().expect("grants mutex poisoned").remove(&f.code);
+    let now = OffsetDateTime::now_utc();
+    let Some(grant) = grant.filter(|g| g.expires_at > now) else { return oauth_error("invalid_grant") };
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(f.code_verifier.as_bytes()));
+    if f.client_id != p.client_id || f.redirect_uri != grant.redirect_uri || challenge != grant.code_challenge {
+        return oauth_error("invalid_grant");
+    }
+    let claims = IdClaims {
+        iss: &p.issuer,
+        sub: subject(&grant.email),
+        aud: &p.client_id,
+        iat: now.unix_timestamp(),
+        exp: (now + ID_TOKEN_TTL).unix_timestamp(),
+        nonce: grant.nonce,
+        email: grant.email,
+        email_verified: grant.email_verified,
+    };
+    let header = Header { kid: Some(KID.into()), ..Header::new(Algorithm::RS256) };
+    let id_token = match jsonwebtoken::encode(&header, &claims, &p.key) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    Json(json!({
+        "access_token": random_string(),
+        "token_type": "Bearer",
+        "expires_in": ID_TOKEN_TTL.whole_seconds(),
+        "id_token": id_token,
+    }))
+    .into_response()
+}
+
+fn oauth_error(error: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response()
+}
+
+/// 同一邮箱总是得到同一个 `sub`，像真实提供方那样跨登录稳定
+fn subject(email: &str) -> String {
+    let digest = Sha256::digest(email.to_lowercase().as_bytes());
+    format!("mock-{}", digest[..8].iter().map(|b| format!("{b:02x}")).collect::<String>())
+}
+
+fn random_string() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// 从 SPKI 公钥（`BEGIN PUBLIC KEY`）里取出 RSA 的 `n`/`e`
+fn rsa_jwk(pem: &[u8]) -> anyhow::Result<Jwk> {
+    let pem = pem::parse(pem)?;
+    let blocks = simple_asn1::from_der(pem.contents())?;
+    let Some(ASN1Block::Sequence(_, spki)) = blocks.first() else { bail!("not a SubjectPublicKeyInfo") };
+    let Some(ASN1Block::BitString(_, _, key)) = spki.get(1) else { bail!("not a SubjectPublicKeyInfo") };
+    let rsa = simple_asn1::from_der(key)?;
+    let Some(ASN1Block::Sequence(_, parts)) = rsa.first() else { bail!("malformed RSA public key") };
+    let (Some(ASN1Block::Integer(_, n)), Some(ASN1Block::Integer(_, e))) = (parts.first(), parts.get(1)) else {
+        bail!("malformed RSA public key");
+    };
+    Ok(Jwk {
+        kty: "RSA".into(),
+        kid: KID.into(),
+        alg: "RS256".into(),
+        use_: "sig".into(),
+        crv: None,
+        x: None,
+        n: Some(URL_SAFE_NO_PAD.encode(n.to_bytes_be().1)),
+        e: Some(URL_SAFE_NO_PAD.encode(e.to_bytes_be().1)),
+    })
+}
@@ -0,0 +1,12 @@
+use clap::Parser;
+use mock_oidc::MockCfg;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cfg = MockCfg::parse();
+    let app = mock_oidc::router(&cfg)?;
+    let listener = tokio::net::TcpListener::bind(&cfg.addr).await?;
+    println!("mock OIDC provider listening on {}, issuer {}", listener.local_addr()?, cfg.issuer());
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use dto::{AuthResp, ErrorBody, LoginReq, LogoutReq, Page, RefreshReq, TodoCreate, TodoListQuery, TodoView};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use uuid::Uuid;
+
+use crate::credentials::Credentials;
+
+/// 服务端返回的错误体；拿不到错误体时（如网关返回的页面）`code` 为 `http_error`
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub body: ErrorBody,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} (HTTP {})", self.body.code, self.body.message, self.status.as_u16())?;
+        if let Some(id) = &self.body.request_id {
+            write!(f, " [request_id {id}]")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+pub struct ApiClient {
+    http: reqwest::Client,
+    base: String,
+    creds: Credentials,
+    /// 续期后写回的位置
+    path: PathBuf,
+}
+
+impl ApiClient {
+    /// `server` 为空时用登录时记下的地址
+    pub fn new(server: Option<String>, creds: Credentials, path: PathBuf) -> Self {
+        let base = server.unwrap_or_else(|| creds.server.clone()).trim_end_matches('/').to_string();
+        Self { http: reqwest::Client::new(), base, creds, path }
+    }
+
+    pub async fn login(server: &str, email: &str, password: &str) -> anyhow::Result<AuthResp> {
+        let url = format!("{}/api/v1/auth/login", server.trim_end_matches('/'));
+        let req = LoginReq { email: email.to_string(), password: password.to_string() };
+        let resp = reqwest::Client::new().post(url).json(&req).send().await.context("connecting to server")?;
+        Ok(check(resp).await?.json().await?)
+    }
+
+    /// 吊销访问令牌与刷新令牌所在的令牌族，然后删除凭据文件
+    pub async fn logout(&mut self) -> anyhow::Result<()> {
+        let body = LogoutReq { refresh_token: Some(self.creds.refresh_token.clone()) };
+        self.send(Method::POST, "/api/v1/auth/logout", |r| r.json(&body)).await?;
+        Credentials::remove(&self.path)
+    }
+
+    pub async fn list(&mut self, query: &TodoListQuery) -> anyhow::Result<Page<TodoView>> {
+        Ok(self.send(Method::GET, "/api/v1/todos", |r| r.query(query)).await?.json().await?)
+    }
+
+    pub async fn create(&mut self, title: &str) -> anyhow::Result<TodoView> {
+        let body = TodoCreate { title: title.to_string(), ..Default::default() };
+        Ok(self.send(Method::POST, "/api/v1/todos", |r| r.json(&body)).await?.json().await?)
+    }
+
+    pub async fn complete(&mut self, id: Uuid) -> anyhow::Result<TodoView> {
+        Ok(self.send(Method::POST, &format!("/api/v1/todos/{id}/complete"), |r| r).await?.json().await?)
+    }
+
+    pub async fn delete(&mut self, id: Uuid) -> anyhow::Result<()> {
+        self.send(Method::DELETE, &format!("/api/v1/todos/{id}"), |r| r).await?;
+        Ok(())
+    }
+
+    /// 带上访问令牌发送；401 时用刷新令牌续期一次再重发
+    async fn send(
+        &mut self,
+        method: Method,
+        path: &str,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> anyhow::Result<Response> {
+        let url = format!("{}{path}", self.base);
+        let http = self.http.clone();
+        let request = |token: &str| build(http.request(method.clone(), &url).bearer_auth(token));
+        let resp = request(&self.creds.token).send().await.context("connecting to server")?;
+        if resp.status() != StatusCode::UNAUTHORIZED {
+            return check(resp).await;
+        }
+        let token = self.refresh().await?;
+        check(request(&token).send().await.context("connecting to server")?).await
+    }
+
+    /// 刷新令牌只能用一次，换到新令牌立即写回，否则下次运行就只能重新登录
+    async fn refresh(&mut self) -> anyhow::Result<String> {
+        let body = RefreshReq { refresh_token: self.creds.refresh_token.clone() };
+        let resp = self.http.post(format!("{}/api/v1/auth/refresh", self.base)).json(&body).send().await?;
+        let auth: AuthResp = check(resp).await.context("session expired, run `todo-cli login` again")?.json().await?;
+        self.creds.token = auth.token;
+        self.creds.refresh_token = auth.refresh_token;
+        self.creds.save(&self.path)?;
+        Ok(self.creds.token.clone())
+    }
+}
+
+async fn check(resp: Response) -> anyhow::Result<Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let text = resp.text().await.unwrap_or_default();
+    let body = serde_json::from_str(&text).unwrap_or(ErrorBody { code: "http_error".into(), message: text, request_id: None });
+    Err(ApiError { status, body }.into())
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// 凭据文件的内容。刷新令牌长期有效，文件只对当前用户可读写
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    pub server: String,
+    pub email: String,
+    pub token: String,
+    pub refresh_token: String,
+}
+
+impl Credentials {
+    /// `$XDG_CONFIG_HOME/todo-cli/credentials.json`，未设置时用 `~/.config`
+    pub fn default_path() -> anyhow::Result<PathBuf> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME").context("HOME is not set, pass --credentials")?).join(".config"),
+        };
+        Ok(base.join("todo-cli").join("credentials.json"))
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(
+                serde_json::from_slice(&bytes).with_context(|| format!("parsing credentials {}", path.display()))?,
+            )),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("reading credentials {}", path.display())),
+        }
+    }
+
+    /// 先写临时文件再改名：写到一半被打断也不会留下半个文件
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            create_private_dir(dir).with_context(|| format!("creating {}", dir.display()))?;
+        }
+        let tmp = path.with_extension("tmp");
+        let mut file = open_private(&tmp).with_context(|| format!("writing credentials {}", tmp.display()))?;
+        file.write_all(&serde_json::to_vec_pretty(self)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path).with_context(|| format!("writing credentials {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn remove(path: &Path) -> anyhow::Result<()> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("removing credentials {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+/// 新建时就是 0600；文件已存在时 `mode` 不生效，再显式改一次
+#[cfg(unix)]
+fn open_private(path: &Path) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let file = fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn open_private(path: &Path) -> std::io::Result<fs::File> {
+    fs::File::create(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creds(token: &str) -> Credentials {
+        Credentials {
+            server: "http://127.0.0.1:8080".into(),
+            email: "a@example.com".into(),
+            token: token.into(),
+            refresh_token: "refresh".into(),
+        }
+    }
+
+    #[test]
+    fn save_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("todo-cli-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nested").join("credentials.json");
+        assert!(Credentials::load(&path).unwrap().is_none());
+
+        creds("first").save(&path).unwrap();
+        // 覆盖已有文件，也不留下临时文件
+        creds("second").save(&path).unwrap();
+        let loaded = Credentials::load(&path).unwrap().unwrap();
+        assert_eq!((loaded.email.as_str(), loaded.token.as_str(), loaded.refresh_token.as_str()), ("a@example.com", "second", "refresh"));
+        assert!(!path.with_extension("tmp").exists());
+
+        Credentials::remove(&path).unwrap();
+        Credentials::remove(&path).unwrap();
+        assert!(Credentials::load(&path).unwrap().is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_is_private_even_if_it_existed() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("todo-cli-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("credentials.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        creds("t").save(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error() {
+        let path = std::env::temp_dir().join(format!("todo-cli-{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, "not json").unwrap();
+        let err = Credentials::load(&path).unwrap_err();
+        assert!(err.to_string().starts_with("parsing credentials"), "{err}");
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+//! `todo-cli`：第 16 章 Todo 服务的命令行客户端。
+//!
+//! 请求与响应直接使用 `dto` 中的类型，服务端改了接口，客户端在编译期就会发现。
+//! 登录后令牌保存在凭据文件里（权限 0600），访问令牌过期时自动用刷新令牌续期并写回。
+
+mod client;
+mod credentials;
+mod output;
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand, ValueEnum};
+use dto::{TodoListQuery, TodoSortParam};
+use uuid::Uuid;
+
+pub use client::{ApiClient, ApiError};
+pub use credentials::Credentials;
+
+const DEFAULT_SERVER: &str = "http://127.0.0.1:8080";
+
+#[derive(Debug, Parser)]
+#[command(name = "todo-cli", version, about = "Todo 服务的命令行客户端")]
+pub struct Cli {
+    /// 服务地址；登录时记入凭据文件，之后的命令默认沿用
+    #[arg(long, env = "TODO_SERVER", global = true)]
+    pub server: Option<String>,
+    /// 凭据文件，默认 `$XDG_CONFIG_HOME/todo-cli/credentials.json`
+    #[arg(long, env = "TODO_CREDENTIALS", global = true)]
+    pub credentials: Option<PathBuf>,
+    /// 输出格式；json 的字段与 REST 响应相同
+    #[arg(long, short, value_enum, default_value_t = Output::Table, global = true)]
+    pub output: Output,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 登录并保存令牌
+    Login {
+        #[arg(long)]
+        email: String,
+        /// 不提供时在终端上无回显地提示输入；标准输入不是终端时读取一行
+        #[arg(long, env = "TODO_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+    /// 吊销当前会话并删除凭据文件
+    Logout,
+    /// 列出 todo，自动翻页直到取完或达到 `--limit`
+    List {
+        /// 只看已完成的
+        #[arg(long, conflicts_with = "open")]
+        done: bool,
+        /// 只看未完成的
+        #[arg(long)]
+        open: bool,
+        /// 标题子串，大小写不敏感
+        #[arg(long, short)]
+        q: Option<String>,
+        /// 旧的在前（默认新的在前）
+        #[arg(long)]
+        oldest_first: bool,
+        /// 最多输出多少条
+        #[arg(long)]
+        limit: Option<usize>,
+        /// 每次请求取多少条
+        #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..=100))]
+        page_size: u32,
+    },
+    /// 新建一条 todo
+    Add { title: String },
+    /// 标记为已完成
+    Complete { id: Uuid },
+    /// 删除
+    Delete { id: Uuid },
+}
+
+pub async fn run(cli: Cli, out: &mut impl Write) -> anyhow::Result<()> {
+    let path = match cli.credentials {
+        Some(path) => path,
+        None => Credentials::default_path()?,
+    };
+    if let Command::Login { email, password } = cli.command {
+        let server = cli.server.unwrap_or_else(|| DEFAULT_SERVER.to_string());
+        let password = match password {
+            Some(p) => p,
+            None => read_password()?,
+        };
+        let auth = ApiClient::login(&server, &email, &password).await?;
+        let creds = Credentials { server, email, token: auth.token, refresh_token: auth.refresh_token };
+        creds.save(&path)?;
+        return output::logged_in(out, cli.output, &creds, &path);
+    }
+
+    let creds = Credentials::load(&path)?
+        .with_context(|| format!("not logged in (no credentials at {}), run `todo-cli login` first", path.display()))?;
+    let mut client = ApiClient::new(cli.server, creds, path);
+    match cli.command {
+        Command::Login { .. } => unreachable!("handled above"),
+        Command::Logout => client.logout().await,
+        Command::List { done, open, q, oldest_first, limit, page_size } => {
+            let sort = if oldest_first { TodoSortParam::CreatedAsc } else { TodoSortParam::CreatedDesc };
+            let done = (done || open).then_some(done);
+            let mut query = TodoListQuery { limit: Some(page_size), cursor: None, done, q, sort };
+            let mut items = Vec::new();
+            loop {
+                let page = client.list(&query).await?;
+                items.extend(page.items);
+                if let Some(limit) = limit.filter(|&l| items.len() >= l) {
+                    items.truncate(limit);
+                    break;
+                }
+                match page.next_cursor {
+                    Some(cursor) => query.cursor = Some(cursor),
+                    None => break,
+                }
+            }
+            output::todos(out, cli.output, &items)
+        }
+        Command::Add { title } => output::todo(out, cli.output, &client.create(&title).await?),
+        Command::Complete { id } => output::todo(out, cli.output, &client.complete(id).await?),
+        Command::Delete { id } => {
+            client.delete(id).await?;
+            output::deleted(out, cli.output, id)
+        }
+    }
+}
+
+/// 终端上关闭回显读取（提示写到 tty，不混进可能被脚本解析的 stdout）；
+/// 标准输入被重定向时（`echo $PW | todo-cli login ...`）读取一行
+fn read_password() -> anyhow::Result<String> {
+    if std::io::stdin().is_terminal() {
+        return rpassword::prompt_password("Password: ").context("reading password");
+    }
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_options_go_before_or_after_the_subcommand() {
+        let cli = Cli::try_parse_from(["todo-cli", "--server", "http://api", "list", "-o", "json", "--credentials", "c.json"]).unwrap();
+        assert_eq!(cli.server.as_deref(), Some("http://api"));
+        assert_eq!(cli.credentials, Some(PathBuf::from("c.json")));
+        assert_eq!(cli.output, Output::Json);
+    }
+
+    #[test]
+    fn list_flags() {
+        let cli = Cli::try_parse_from(["todo-cli", "list", "--done", "-q", "milk", "--limit", "5"]).unwrap();
+        let Command::List { done, open, q, oldest_first, limit, page_size } = cli.command else { panic!("{:?}", cli.command) };
+        assert!(done && !open && !oldest_first);
+        assert_eq!((q.as_deref(), limit, page_size), (Some("milk"), Some(5), 100));
+        assert_eq!(cli.output, Output::Table);
+
+        assert!(Cli::try_parse_from(["todo-cli", "list", "--done", "--open"]).is_err());
+        for size in ["0", "101"] {
+            assert!(Cli::try_parse_from(["todo-cli", "list", "--page-size", size]).is_err(), "{size}");
+        }
+    }
+
+    #[test]
+    fn login_password_is_optional_and_ids_must_be_uuids() {
+        let cli = Cli::try_parse_from(["todo-cli", "login", "--email", "a@example.com"]).unwrap();
+        assert!(matches!(cli.command, Command::Login { ref email, password: None } if email == "a@example.com"));
+        assert!(Cli::try_parse_from(["todo-cli", "login"]).is_err());
+
+        let id = Uuid::new_v4();
+        let cli = Cli::try_parse_from(["todo-cli", "complete", &id.to_string()]).unwrap();
+        assert!(matches!(cli.command, Command::Complete { id: parsed } if parsed == id));
+        assert!(Cli::try_parse_from(["todo-cli", "delete", "42"]).is_err());
+    }
+}
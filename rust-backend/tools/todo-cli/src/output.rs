@@ -0,0 +1,73 @@
+//! 表格给人看，JSON 给脚本用；JSON 的字段与 REST 响应相同
+
+use std::io::Write;
+use std::path::Path;
+
+use dto::TodoView;
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::credentials::Credentials;
+use crate::Output;
+
+pub fn todos(out: &mut impl Write, fmt: Output, items: &[TodoView]) -> anyhow::Result<()> {
+    match fmt {
+        Output::Json => write_json(out, &items),
+        Output::Table => {
+            let rows: Vec<[String; 4]> = items
+                .iter()
+                .map(|t| [t.id.to_string(), if t.done { "x" } else { "" }.into(), t.title.clone(), t.created_at.clone()])
+                .collect();
+            table(out, ["ID", "DONE", "TITLE", "CREATED"].map(String::from), &rows)
+        }
+    }
+}
+
+pub fn todo(out: &mut impl Write, fmt: Output, item: &TodoView) -> anyhow::Result<()> {
+    match fmt {
+        Output::Json => write_json(out, item),
+        Output::Table => todos(out, fmt, std::slice::from_ref(item)),
+    }
+}
+
+pub fn deleted(out: &mut impl Write, fmt: Output, id: Uuid) -> anyhow::Result<()> {
+    match fmt {
+        Output::Json => write_json(out, &json!({ "deleted": id })),
+        Output::Table => Ok(writeln!(out, "deleted {id}")?),
+    }
+}
+
+pub fn logged_in(out: &mut impl Write, fmt: Output, creds: &Credentials, path: &Path) -> anyhow::Result<()> {
+    match fmt {
+        Output::Json => write_json(out, &json!({ "server": creds.server, "email": creds.email })),
+        Output::Table => Ok(writeln!(out, "logged in to {} as {} (credentials saved to {})", creds.server, creds.email, path.display())?),
+    }
+}
+
+fn write_json(out: &mut impl Write, value: &impl Serialize) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// 按字符数左对齐；最后一列不补空格
+fn table<const N: usize>(out: &mut impl Write, header: [String; N], rows: &[[String; N]]) -> anyhow::Result<()> {
+    let mut widths = header.each_ref().map(|h| h.chars().count());
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(rows) {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            line.push_str(cell);
+            if i + 1 < N {
+                line.extend(std::iter::repeat_n(' ', widths[i] - cell.chars().count() + 2));
+            }
+        }
+        writeln!(out, "{line}")?;
+    }
+    Ok(())
+}
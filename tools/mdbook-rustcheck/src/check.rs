@@ -0,0 +1,162 @@
+//! 运行 `cargo check` 并把编译诊断映射回 Markdown 的 `file:line`。
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::extract::{Chapter, Mode};
+use crate::workspace::SourceMap;
+
+#[derive(Debug)]
+pub struct Failure {
+    pub md_path: PathBuf,
+    pub md_line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.md_path.display(), self.md_line, self.message)
+    }
+}
+
+#[derive(Deserialize)]
+struct CargoLine {
+    reason: String,
+    message: Option<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+struct Diagnostic {
+    message: String,
+    level: String,
+    code: Option<DiagCode>,
+    spans: Vec<Span>,
+}
+
+#[derive(Deserialize)]
+struct DiagCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct Span {
+    file_name: String,
+    line_start: usize,
+    is_primary: bool,
+}
+
+/// 联网下载一章工作区的依赖到本机 cargo 缓存，供随后的离线检查使用（CI 中没有 vendor 目录时）。
+/// 解析失败不在这里报错，由离线检查定位到对应的 ```toml 片段
+pub fn fetch(ws: &Path) -> anyhow::Result<()> {
+    Command::new(cargo())
+        .arg("fetch")
+        .arg("--quiet")
+        .current_dir(ws)
+        .stderr(std::process::Stdio::null())
+        .status()
+        .context("spawn cargo fetch")?;
+    Ok(())
+}
+
+fn cargo() -> String {
+    std::env::var("CARGO").unwrap_or_else(|_| "cargo".into())
+}
+
+/// 检查一章生成的工作区，返回映射回 Markdown 的失败列表。
+pub fn run(ch: &Chapter, ws: &Path, target_dir: &Path, map: &SourceMap) -> anyhow::Result<Vec<Failure>> {
+    let output = Command::new(cargo())
+        .args(["check", "--offline", "--workspace", "--bins", "--keep-going", "--message-format=json"])
+        .arg("--target-dir")
+        .arg(target_dir)
+        .current_dir(ws)
+        // sqlx::query! 在没有数据库时走离线缓存，缺失缓存会如实报错而不是去连库
+        .env("SQLX_OFFLINE", "true")
+        .output()
+        .context("spawn cargo check")?;
+
+    let mut failures = Vec::new();
+    let mut failed_files = HashSet::new();
+    for line in output.stdout.split(|b| *b == b'\n').filter(|l| !l.is_empty()) {
+        let Ok(msg) = serde_json::from_slice::<CargoLine>(line) else { continue };
+        let Some(diag) = msg.message.filter(|_| msg.reason == "compiler-message") else { continue };
+        if diag.level != "error" {
+            continue;
+        }
+        let Some(span) = diag.spans.iter().find(|s| s.is_primary) else { continue };
+        let Some((file, origin)) = map.iter().find(|(f, _)| Path::new(&span.file_name).ends_with(f)) else {
+            continue;
+        };
+        failed_files.insert(file.clone());
+        if origin.mode == Mode::CompileFail {
+            continue;
+        }
+        let md_line = origin.md_line(span.line_start);
+        let message = match diag.code {
+            Some(c) => format!("error[{}]: {}", c.code, diag.message),
+            None => format!("error: {}", diag.message),
+        };
+        failures.push(Failure { md_path: ch.path.clone(), md_line, message });
+    }
+
+    // 依赖解析失败等情况下没有可映射的诊断：定位到声明出错依赖的 ```toml 片段
+    if !output.status.success() && failed_files.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let md_line = dep_line(ch, &stderr);
+        let first = stderr.lines().find(|l| l.starts_with("error")).unwrap_or("cargo check failed");
+        failures.push(Failure { md_path: ch.path.clone(), md_line, message: first.to_string() });
+        return Ok(failures);
+    }
+
+    for (file, origin) in map {
+        if origin.mode == Mode::CompileFail && !failed_files.contains(file) {
+            failures.push(Failure {
+                md_path: ch.path.clone(),
+                md_line: origin.md_line,
+                message: "compile_fail block compiled successfully".into(),
+            });
+        }
+    }
+    failures.sort_by_key(|f| f.md_line);
+    Ok(failures)
+}
+
+/// 按 cargo 报错里出现的依赖名找到声明它的 ```toml 片段；cargo 用 `` `name = "..."` ``
+/// 或 `` `name` `` 两种写法提到依赖。找不到时归到本章第一个依赖片段
+fn dep_line(ch: &Chapter, stderr: &str) -> usize {
+    ch.dep_lines
+        .iter()
+        .find(|(name, _)| stderr.contains(&format!("`{name} = ")) || stderr.contains(&format!("`{name}`")))
+        .or(ch.dep_lines.first())
+        .map_or(1, |(_, line)| *line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter() -> Chapter {
+        Chapter {
+            path: PathBuf::from("src/part-6/14-logging-and-config.md"),
+            blocks: Vec::new(),
+            deps: toml::Table::new(),
+            dep_lines: vec![("tracing".into(), 35), ("opentelemetry".into(), 285)],
+        }
+    }
+
+    #[test]
+    fn resolution_errors_point_at_the_declaring_snippet() {
+        let ch = chapter();
+        let feature = "error: failed to select a version for `opentelemetry`.\n\
+                       package `book` depends on `opentelemetry` with feature `rt-tokio` but `opentelemetry` does not have that feature.";
+        assert_eq!(dep_line(&ch, feature), 285);
+        let version = "error: failed to select a version for the requirement `opentelemetry = \"^0.99\"`";
+        assert_eq!(dep_line(&ch, version), 285);
+        // 认不出是哪个依赖时归到第一个依赖片段
+        assert_eq!(dep_line(&ch, "error: failed to load manifest"), 35);
+        assert_eq!(dep_line(&Chapter { dep_lines: Vec::new(), ..chapter() }, "error"), 1);
+    }
+}
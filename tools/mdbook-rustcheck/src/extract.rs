@@ -0,0 +1,193 @@
+//! 从章节 Markdown 中提取 ```rust 代码块与 ```toml 依赖片段。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+
+/// 代码块的检查方式，由 info string 中的属性决定（与 rustdoc 同名）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// 必须通过 `cargo check`；`no_run` 只是不运行，同样要能编译
+    Check,
+    /// `compile_fail`：必须编译失败，用于演示借用检查器报错的片段
+    CompileFail,
+}
+
+#[derive(Debug, Clone)]
+pub struct RustBlock {
+    /// 代码首行在 Markdown 中的行号（从 1 开始）
+    pub line: usize,
+    pub code: String,
+    pub mode: Mode,
+}
+
+#[derive(Debug)]
+pub struct Chapter {
+    /// 相对书根目录的路径，用于报告 `file:line`
+    pub path: PathBuf,
+    pub blocks: Vec<RustBlock>,
+    /// 本章 ```toml 片段中声明的依赖，后出现的覆盖先出现的
+    pub deps: toml::Table,
+    /// 依赖名到声明它的 ```toml 片段首行，依赖解析失败时据此定位
+    pub dep_lines: Vec<(String, usize)>,
+}
+
+/// 列出 `src/part-*/*.md`，按路径排序，保证生成的工作区稳定。
+pub fn chapter_files(root: &Path, src: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let src_dir = root.join(src);
+    let mut files = Vec::new();
+    for part in std::fs::read_dir(&src_dir).with_context(|| format!("read {}", src_dir.display()))? {
+        let part = part?.path();
+        let is_part = part.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("part-"));
+        if !is_part || !part.is_dir() {
+            continue;
+        }
+        for md in std::fs::read_dir(&part)? {
+            let md = md?.path();
+            if md.extension().is_some_and(|e| e == "md") {
+                files.push(md.strip_prefix(root).unwrap_or(&md).to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+pub fn parse_chapter(root: &Path, rel: &Path) -> anyhow::Result<Chapter> {
+    let text = std::fs::read_to_string(root.join(rel)).with_context(|| format!("read {}", rel.display()))?;
+    Ok(parse_markdown(rel, &text))
+}
+
+fn parse_markdown(rel: &Path, text: &str) -> Chapter {
+    let mut chapter =
+        Chapter { path: rel.to_path_buf(), blocks: Vec::new(), deps: toml::Table::new(), dep_lines: Vec::new() };
+
+    // (info string, 开始围栏所在行, 累积的代码)
+    let mut current: Option<(String, usize, String)> = None;
+    for (event, range) in Parser::new_ext(text, Options::empty()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                let fence_line = text[..range.start].matches('\n').count() + 1;
+                current = Some((info.into_string(), fence_line, String::new()));
+            }
+            Event::Text(t) => {
+                if let Some((_, _, code)) = current.as_mut() {
+                    code.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((info, fence_line, code)) = current.take() {
+                    chapter.push_block(&info, fence_line + 1, code);
+                }
+            }
+            _ => {}
+        }
+    }
+    chapter
+}
+
+impl Chapter {
+    fn push_block(&mut self, info: &str, line: usize, code: String) {
+        let mut attrs = info.split(|c: char| c == ',' || c.is_whitespace()).filter(|s| !s.is_empty());
+        let lang = attrs.next();
+        let attrs: Vec<&str> = attrs.collect();
+        // ignore 显式退出检查（```toml,ignore 用于 crates.io 上没有的示意依赖）；
+        // {{#include}} 引入的代码由其所在的真实工程保证可编译
+        if attrs.contains(&"ignore") {
+            return;
+        }
+        match lang {
+            Some("rust") if !code.contains("{{#") => {
+                let mode = if attrs.contains(&"compile_fail") { Mode::CompileFail } else { Mode::Check };
+                self.blocks.push(RustBlock { line, code, mode });
+            }
+            Some("toml") => self.merge_deps(line, &code),
+            _ => {}
+        }
+    }
+
+    /// 合并 `[dependencies]`/`[dev-dependencies]`/`[workspace.dependencies]`；
+    /// 无法解析的片段（如带省略号的示意配置）直接忽略。
+    fn merge_deps(&mut self, line: usize, code: &str) {
+        let Ok(table) = code.parse::<toml::Table>() else { return };
+        let sections = [
+            table.get("dependencies"),
+            table.get("dev-dependencies"),
+            table.get("workspace").and_then(|w| w.get("dependencies")),
+        ];
+        for section in sections.into_iter().flatten() {
+            let Some(section) = section.as_table() else { continue };
+            for (name, spec) in section {
+                // path/git/workspace/私有 registry 依赖在生成的工作区里无法解析
+                let unresolvable = spec.as_table().is_some_and(|t| {
+                    ["path", "git", "workspace", "registry"].iter().any(|k| t.contains_key(*k))
+                });
+                if !unresolvable {
+                    self.deps.insert(name.clone(), spec.clone());
+                    self.dep_lines.retain(|(n, _)| n != name);
+                    self.dep_lines.push((name.clone(), line));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Chapter {
+        parse_markdown(Path::new("src/part-1/01-demo.md"), text)
+    }
+
+    #[test]
+    fn fence_attributes_select_the_mode() {
+        let ch = parse(concat!(
+            "# 标题\n",
+            "```rust\nlet a = 1;\n```\n",
+            "```rust,no_run\nlet b = 2;\n```\n",
+            "```rust,compile_fail\nlet c: u8 = \"x\";\n```\n",
+            "```rust editable\nlet d = 4;\n```\n",
+            "```rust,ignore\nnot rust at all\n```\n",
+            "```go\nfunc main() {}\n```\n",
+        ));
+        let modes: Vec<_> = ch.blocks.iter().map(|b| (b.code.trim(), b.mode)).collect();
+        assert_eq!(
+            modes,
+            [
+                ("let a = 1;", Mode::Check),
+                ("let b = 2;", Mode::Check),
+                ("let c: u8 = \"x\";", Mode::CompileFail),
+                ("let d = 4;", Mode::Check),
+            ]
+        );
+    }
+
+    #[test]
+    fn block_line_is_the_first_code_line() {
+        let ch = parse("第一行\n\n```rust\nfn main() {}\n```\n\n- 列表\n  ```rust\n  let x = 1;\n  ```\n");
+        let lines: Vec<_> = ch.blocks.iter().map(|b| b.line).collect();
+        assert_eq!(lines, [4, 9]);
+    }
+
+    #[test]
+    fn included_code_is_skipped() {
+        let ch = parse("```rust\n{{#include ../../rust-backend/src/main.rs}}\n```\n");
+        assert!(ch.blocks.is_empty());
+    }
+
+    #[test]
+    fn toml_snippets_declare_dependencies() {
+        let ch = parse(concat!(
+            "```toml\n[dependencies]\ntokio = \"1\"\nlocal = { path = \"../local\" }\n```\n",
+            "```toml\n[workspace.dependencies]\ntokio = { version = \"1\", features = [\"full\"] }\n```\n",
+            "```toml,ignore\n[dependencies]\nreth-db = \"0.7\"\n```\n",
+            "```toml\n[dependencies]\nserde = ...\n```\n",
+        ));
+        let names: Vec<_> = ch.deps.keys().map(String::as_str).collect();
+        assert_eq!(names, ["tokio"]);
+        assert_eq!(ch.deps["tokio"]["features"][0].as_str(), Some("full"));
+        assert_eq!(ch.dep_lines, [("tokio".to_string(), 7)]);
+    }
+}
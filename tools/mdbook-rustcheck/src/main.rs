@@ -0,0 +1,146 @@
+//! mdbook-rustcheck：对书中每个 ```rust 代码块做编译检查。
+//!
+//! 两种用法：
+//! - 命令行：`mdbook-rustcheck check --vendor ../vendor`（或 `--fetch`），失败以 `file:line` 输出，退出码非 0；
+//! - mdBook 预处理器：在 book.toml 中注册后随 `mdbook build` 运行，原样返回书的内容。
+
+mod check;
+mod extract;
+mod workspace;
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    /// 检查书中的代码块
+    Check(CheckArgs),
+    /// mdBook 预处理器协议：询问是否支持某个 renderer
+    Supports { renderer: String },
+}
+
+#[derive(clap::Args)]
+struct CheckArgs {
+    /// 书的根目录（book.toml 所在目录）
+    #[arg(long, default_value = ".")]
+    root: PathBuf,
+    /// `cargo vendor` 产出的目录；不指定时使用本机 cargo 缓存离线解析
+    #[arg(long)]
+    vendor: Option<PathBuf>,
+    /// 检查前先联网 `cargo fetch` 各章依赖，适合没有 vendor 目录、缓存为空的 CI
+    #[arg(long, conflicts_with = "vendor")]
+    fetch: bool,
+    /// 生成工作区与编译产物的目录，默认 `<root>/target/rustcheck`
+    #[arg(long)]
+    work_dir: Option<PathBuf>,
+    /// 只检查这些章节（相对书根目录），默认 `src/part-*/*.md`
+    chapters: Vec<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.cmd {
+        Some(Cmd::Check(args)) => run_check(&args),
+        // 只做编译检查，不改写内容，所有 renderer 都可以用
+        Some(Cmd::Supports { .. }) => Ok(true),
+        None => run_preprocessor(),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("mdbook-rustcheck: {e:#}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run_check(args: &CheckArgs) -> anyhow::Result<bool> {
+    let chapters = if args.chapters.is_empty() {
+        extract::chapter_files(&args.root, "src")?
+    } else {
+        args.chapters.clone()
+    };
+    let work_dir = args.work_dir.clone().unwrap_or_else(|| args.root.join("target/rustcheck"));
+    let deps = match &args.vendor {
+        Some(vendor) => Deps::Vendor(vendor),
+        None if args.fetch => Deps::Fetch,
+        None => Deps::Cache,
+    };
+    check_book(&args.root, &chapters, deps, &work_dir)
+}
+
+/// 依赖从哪里来；检查本身总是离线进行
+#[derive(Clone, Copy)]
+enum Deps<'a> {
+    /// 本机 cargo 缓存
+    Cache,
+    /// 检查前联网 `cargo fetch` 到本机缓存
+    Fetch,
+    /// `cargo vendor` 产出的目录
+    Vendor(&'a Path),
+}
+
+/// 预处理器模式：stdin 为 `[context, book]`，stdout 原样写回 book。
+/// `[preprocessor.rustcheck]` 下可配置 `vendor`、`fetch`（同命令行的 `--fetch`）与 `deny`（有失败时中断构建）。
+fn run_preprocessor() -> anyhow::Result<bool> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    let (ctx, book): (serde_json::Value, serde_json::Value) = serde_json::from_str(&input)?;
+
+    let root = PathBuf::from(ctx["root"].as_str().unwrap_or("."));
+    let src = ctx["config"]["book"]["src"].as_str().unwrap_or("src");
+    let opts = &ctx["config"]["preprocessor"]["rustcheck"];
+    let vendor = opts["vendor"].as_str().map(|v| root.join(v));
+    let deps = match &vendor {
+        Some(vendor) => Deps::Vendor(vendor),
+        None if opts["fetch"].as_bool().unwrap_or(false) => Deps::Fetch,
+        None => Deps::Cache,
+    };
+    let deny = opts["deny"].as_bool().unwrap_or(false);
+
+    let chapters = extract::chapter_files(&root, src)?;
+    let ok = check_book(&root, &chapters, deps, &root.join("target/rustcheck"))?;
+    serde_json::to_writer(std::io::stdout(), &book)?;
+    Ok(ok || !deny)
+}
+
+fn check_book(root: &Path, chapters: &[PathBuf], deps: Deps, work_dir: &Path) -> anyhow::Result<bool> {
+    let ws_root = work_dir.join("ws");
+    let target_dir = work_dir.join("target");
+    let vendor = match deps {
+        Deps::Vendor(vendor) => Some(vendor),
+        _ => None,
+    };
+    workspace::prepare(&ws_root, vendor)?;
+
+    let (mut total, mut errors) = (0, 0);
+    for path in chapters {
+        let ch = extract::parse_chapter(root, path)?;
+        if ch.blocks.is_empty() {
+            continue;
+        }
+        let (dir, map) = workspace::generate(&ws_root, &ch)?;
+        if matches!(deps, Deps::Fetch) {
+            check::fetch(&dir)?;
+        }
+        let failures = check::run(&ch, &dir, &target_dir, &map)?;
+        for f in &failures {
+            eprintln!("{f}");
+        }
+        total += ch.blocks.len();
+        errors += failures.len();
+    }
+    eprintln!("mdbook-rustcheck: {total} blocks checked, {errors} errors");
+    Ok(errors == 0)
+}
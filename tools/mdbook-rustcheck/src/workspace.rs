@@ -0,0 +1,173 @@
+//! 把每章的代码块落盘为一个独立的 cargo 工作区，每个代码块一个 bin。
+//!
+//! 各章依赖互不影响：某一章的依赖解析失败不会掩盖其他章的编译结果。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::extract::{Chapter, Mode};
+
+/// 生成文件与 Markdown 位置的对应关系，供诊断回映射。
+#[derive(Debug, Clone)]
+pub struct Origin {
+    /// 代码首行在 Markdown 中的行号
+    pub md_line: usize,
+    /// 生成文件中代码之前插入的行数
+    pub prelude: usize,
+    pub mode: Mode,
+}
+
+impl Origin {
+    /// 生成文件中的行号（从 1 开始）对应的 Markdown 行号；
+    /// 落在包装代码上的诊断（如缺少 main）归到代码块首行
+    pub fn md_line(&self, generated_line: usize) -> usize {
+        self.md_line + generated_line.saturating_sub(self.prelude + 1)
+    }
+}
+
+/// 生成文件（相对章节工作区根目录）到 Markdown 位置的映射。
+pub type SourceMap = HashMap<PathBuf, Origin>;
+
+/// 写入 vendor 源替换配置；放在所有章节工作区的父目录，cargo 向上查找时都能读到。
+pub fn prepare(out: &Path, vendor: Option<&Path>) -> anyhow::Result<()> {
+    std::fs::create_dir_all(out).with_context(|| format!("create {}", out.display()))?;
+    let config = out.join(".cargo/config.toml");
+    match vendor {
+        Some(vendor) => {
+            let vendor = vendor.canonicalize().with_context(|| format!("vendor dir {}", vendor.display()))?;
+            std::fs::create_dir_all(out.join(".cargo"))?;
+            let body = format!(
+                "[source.crates-io]\nreplace-with = \"vendored-sources\"\n\n[source.vendored-sources]\ndirectory = {:?}\n",
+                vendor.display().to_string()
+            );
+            std::fs::write(config, body)?;
+        }
+        None if config.exists() => std::fs::remove_file(config)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// 生成一章的工作区，返回其目录与源码映射。
+pub fn generate(out: &Path, ch: &Chapter) -> anyhow::Result<(PathBuf, SourceMap)> {
+    let dir = out.join(package_name(&ch.path));
+    // 清理上一轮生成的代码块，Cargo.lock 保留以免重复解析
+    let bin_dir = dir.join("src/bin");
+    if bin_dir.exists() {
+        std::fs::remove_dir_all(&bin_dir)?;
+    }
+    std::fs::create_dir_all(&bin_dir)?;
+
+    let mut package = toml::Table::new();
+    package.insert("name".into(), package_name(&ch.path).into());
+    package.insert("version".into(), "0.0.0".into());
+    package.insert("edition".into(), "2021".into());
+    package.insert("publish".into(), false.into());
+    let mut manifest = toml::Table::new();
+    manifest.insert("package".into(), package.into());
+    manifest.insert("dependencies".into(), ch.deps.clone().into());
+    // 空的 [workspace] 让每章自成工作区根
+    manifest.insert("workspace".into(), toml::Table::new().into());
+    std::fs::write(dir.join("Cargo.toml"), toml::to_string(&manifest)?)?;
+
+    let mut map = SourceMap::new();
+    for block in &ch.blocks {
+        let (source, prelude) = wrap(&block.code);
+        let rel = PathBuf::from(format!("src/bin/l{:04}.rs", block.line));
+        std::fs::write(dir.join(&rel), source)?;
+        map.insert(rel, Origin { md_line: block.line, prelude, mode: block.mode });
+    }
+    Ok((dir, map))
+}
+
+/// `src/part-4/09-networking.md` -> `book_part_4_09_networking`
+fn package_name(md: &Path) -> String {
+    let stem: String = md
+        .with_extension("")
+        .components()
+        .skip(1)
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("_");
+    let stem: String = stem.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("book_{stem}")
+}
+
+/// 按 rustdoc 的约定包装代码块，返回源码与前置行数：
+/// - mdBook 的隐藏行（`# ` 前缀）去掉前缀后参与编译；
+/// - 没有 `fn main` 的片段整体包进 `fn main() { ... }`。
+fn wrap(code: &str) -> (String, usize) {
+    let body: Vec<String> = code
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            match trimmed {
+                "#" => String::new(),
+                t if t.starts_with("# ") => format!("{indent}{}", &t[2..]),
+                _ => line.to_string(),
+            }
+        })
+        .collect();
+    let body = body.join("\n");
+
+    let mut out = String::from("#![allow(unused)]\n");
+    if body.contains("fn main(") {
+        out.push_str(&body);
+        out.push('\n');
+        (out, 1)
+    } else {
+        out.push_str("fn main() {\n");
+        out.push_str(&body);
+        out.push_str("\n}\n");
+        (out, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_without_main_are_wrapped() {
+        let (src, prelude) = wrap("let x = 1;\nprintln!(\"{x}\");");
+        assert_eq!(src, "#![allow(unused)]\nfn main() {\nlet x = 1;\nprintln!(\"{x}\");\n}\n");
+        assert_eq!(prelude, 2);
+    }
+
+    #[test]
+    fn snippets_with_main_are_kept() {
+        let (src, prelude) = wrap("fn main() {}");
+        assert_eq!(src, "#![allow(unused)]\nfn main() {}\n");
+        assert_eq!(prelude, 1);
+    }
+
+    #[test]
+    fn hidden_lines_are_compiled_without_the_marker() {
+        let (src, _) = wrap("# use std::fmt;\n  # let y = 2;\n#\n#[derive(Debug)]\nstruct S;");
+        assert_eq!(src, "#![allow(unused)]\nfn main() {\nuse std::fmt;\n  let y = 2;\n\n#[derive(Debug)]\nstruct S;\n}\n");
+    }
+
+    #[test]
+    fn generated_lines_map_back_to_markdown() {
+        // 代码块从 Markdown 第 40 行开始，包进 main 后前面多了两行
+        let origin = Origin { md_line: 40, prelude: 2, mode: Mode::Check };
+        assert_eq!(origin.md_line(3), 40);
+        assert_eq!(origin.md_line(7), 44);
+        // 落在 `#![allow(unused)]`、`fn main() {` 上的诊断归到首行
+        assert_eq!(origin.md_line(1), 40);
+        assert_eq!(origin.md_line(2), 40);
+
+        let origin = Origin { md_line: 10, prelude: 1, mode: Mode::Check };
+        assert_eq!(origin.md_line(2), 10);
+        assert_eq!(origin.md_line(5), 13);
+    }
+
+    #[test]
+    fn package_name_is_derived_from_the_chapter_path() {
+        assert_eq!(package_name(Path::new("src/part-4/09-networking.md")), "book_part_4_09_networking");
+        assert_eq!(package_name(Path::new("src/part-6/testing-and-benchmarking.md")), "book_part_6_testing_and_benchmarking");
+    }
+}